@@ -1,7 +1,169 @@
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::account_backup::{self, AccountBackup};
+use crate::account_db::AccountDb;
+use crate::account_format::{self, DecodeError, FORMAT_VERSION};
+use crate::account_export;
+use crate::account_import::{self, ImportOptions, ImportReport};
+use crate::account_query::{self, AccountQuery, AccountQueryResult};
+use crate::error::{AppError, AppResult};
+use crate::fs_util::{file_stamp, write_atomic, FileLock, FileStamp, STARTUP_LOCK_TIMEOUT};
+use crate::vault::{Vault, VaultStatus};
+use tracing::{info, warn};
+
+/// 账号状态，序列化为稳定的英文值，供前端和外部工具过滤（显示文案由前端决定）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AccountState {
+    /// 最近一次刷新/查询正常
+    Active,
+    /// 账号被封禁
+    Banned,
+    /// access token 已过期，尚未刷新成功
+    TokenExpired,
+    /// 刷新失败（网络等临时错误），稍后可重试
+    RefreshFailed,
+    /// refresh token 失效，需要重新登录
+    NeedsReauth,
+    /// 从未同步或无法识别的旧状态
+    Unknown,
+}
+
+impl AccountState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountState::Active => "active",
+            AccountState::Banned => "banned",
+            AccountState::TokenExpired => "token-expired",
+            AccountState::RefreshFailed => "refresh-failed",
+            AccountState::NeedsReauth => "needs-reauth",
+            AccountState::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStatus {
+    pub state: AccountState,
+    // 进入该状态的原因（封禁原因、错误信息等）
+    pub reason: Option<String>,
+    // 进入该状态的时间 (RFC 3339)，迁移自旧版本的状态为 None
+    pub since: Option<String>,
+}
+
+impl Default for AccountStatus {
+    fn default() -> Self {
+        Self { state: AccountState::Unknown, reason: None, since: None }
+    }
+}
+
+impl AccountStatus {
+    pub fn new(state: AccountState, reason: Option<String>) -> Self {
+        Self {
+            state,
+            reason,
+            since: Some(Local::now().to_rfc3339()),
+        }
+    }
+
+    pub fn active() -> Self {
+        Self::new(AccountState::Active, None)
+    }
+
+    /// 根据 usage 查询结果判断状态
+    /// 网络、5xx、限流等错误说明不了账号状态，返回 None，调用方保留原状态
+    pub fn from_usage_result<T>(result: &Result<T, AppError>) -> Option<Self> {
+        match result {
+            Ok(_) => Some(Self::active()),
+            Err(AppError::AccountSuspended { reason }) => Some(Self::new(AccountState::Banned, Some(reason.clone()))),
+            Err(e) if e.is_auth() => Some(Self::new(AccountState::TokenExpired, Some(e.to_string()))),
+            Err(_) => None,
+        }
+    }
+
+    /// 根据刷新 token 的错误判断状态
+    pub fn from_refresh_error(error: &AppError) -> Self {
+        let state = match ErrorCategory::of(error) {
+            ErrorCategory::Banned => AccountState::Banned,
+            ErrorCategory::Auth => AccountState::NeedsReauth,
+            _ => AccountState::RefreshFailed,
+        };
+        let reason = match error {
+            AppError::AccountSuspended { reason } => reason.clone(),
+            e => e.to_string(),
+        };
+        Self::new(state, Some(reason))
+    }
+
+    /// 旧版本的中文状态字符串
+    pub fn from_legacy(status: &str) -> Self {
+        let state = match status {
+            "正常" | "有效" => AccountState::Active,
+            "已封禁" | "封禁" => AccountState::Banned,
+            "Token已失效" => AccountState::TokenExpired,
+            "刷新失败" => AccountState::RefreshFailed,
+            _ => AccountState::Unknown,
+        };
+        let reason = (state == AccountState::Unknown && !status.is_empty()).then(|| status.to_string());
+        Self { state, reason, since: None }
+    }
+}
+
+// ============================================================
+// 同步记录
+// ============================================================
+
+/// 每个账号保留的失败记录条数
+const ERROR_HISTORY_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncOperation {
+    /// 刷新 token
+    Refresh,
+    /// 查询 usage
+    Usage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCategory {
+    Banned,
+    /// token 无效或已过期，需要重新登录
+    Auth,
+    Network,
+    RateLimited,
+    /// 服务端 5xx
+    Server,
+    Other,
+}
+
+impl ErrorCategory {
+    /// 按错误类型归类
+    pub fn of(error: &AppError) -> Self {
+        match error {
+            AppError::AccountSuspended { .. } => ErrorCategory::Banned,
+            AppError::AuthExpired(_) | AppError::InvalidGrant(_) => ErrorCategory::Auth,
+            AppError::RateLimited { .. } => ErrorCategory::RateLimited,
+            AppError::Network(_) => ErrorCategory::Network,
+            e if e.is_server_error() => ErrorCategory::Server,
+            _ => ErrorCategory::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncError {
+    pub at: String,
+    pub operation: SyncOperation,
+    pub category: ErrorCategory,
+    pub message: String,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -9,8 +171,12 @@ pub struct Account {
     pub id: String,
     pub email: String,
     pub label: String,
-    pub status: String,
+    pub status: AccountStatus,
     pub added_at: String,
+    // 分类
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub group: Option<String>,
     // 认证信息
     pub access_token: Option<String>,
     pub refresh_token: Option<String>,
@@ -19,11 +185,14 @@ pub struct Account {
     pub expires_at: Option<String>,
     // 账号信息
     pub provider: Option<String>,
+    pub auth_method: Option<String>,  // "social" / "IdC" / "web_oauth"，旧账号为空时按 provider 推断
     pub user_id: Option<String>,
     // IdC 专用
     pub client_id: Option<String>,
     pub client_secret: Option<String>,
+    pub client_secret_expires_at: Option<String>,  // 客户端注册过期时间 (RFC 3339)，过期后需要重新注册
     pub region: Option<String>,
+    pub start_url: Option<String>,  // IdC 登录的 start URL，Enterprise 为组织的 Identity Center 地址
     pub client_id_hash: Option<String>,
     pub sso_session_id: Option<String>,
     pub id_token: Option<String>,
@@ -31,6 +200,15 @@ pub struct Account {
     pub profile_arn: Option<String>,
     // 原始 usage API 响应
     pub usage_data: Option<serde_json::Value>,
+    // 同步记录 (RFC 3339)
+    pub last_synced_at: Option<String>,   // 最近一次刷新 + usage 都成功
+    pub last_refresh_at: Option<String>,  // 最近一次刷新 token 成功
+    pub last_error: Option<SyncError>,
+    #[serde(default)]
+    pub error_history: Vec<SyncError>,    // 最近的失败记录，最新的在最后
+    // 启用加密时，敏感字段封存在这里（仅出现在磁盘上）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed_secrets: Option<String>,
 }
 
 
@@ -41,37 +219,216 @@ impl Account {
             id: Uuid::new_v4().to_string(),
             email,
             label,
-            status: "正常".to_string(),
+            status: AccountStatus::active(),
             added_at: now.format("%Y/%m/%d %H:%M:%S").to_string(),
+            tags: Vec::new(),
+            group: None,
             access_token: None,
             refresh_token: None,
             csrf_token: None,
             session_token: None,
             expires_at: None,
             provider: None,
+            auth_method: None,
             user_id: None,
             client_id: None,
             client_secret: None,
+            client_secret_expires_at: None,
             region: None,
+            start_url: None,
             client_id_hash: None,
             sso_session_id: None,
             id_token: None,
             profile_arn: None,
             usage_data: None,
+            last_synced_at: None,
+            last_refresh_at: None,
+            last_error: None,
+            error_history: Vec::new(),
+            sealed_secrets: None,
+        }
+    }
+
+    pub fn expires_at_time(&self) -> Option<DateTime<Local>> {
+        self.expires_at.as_deref().and_then(account_query::parse_time)
+    }
+
+    /// IdC 客户端注册的过期时间，旧账号没有记录
+    pub fn client_registration_expires_time(&self) -> Option<DateTime<Local>> {
+        self.client_secret_expires_at.as_deref().and_then(account_query::parse_time)
+    }
+
+    pub fn client_registration_expired(&self) -> bool {
+        self.client_registration_expires_time().is_some_and(|t| t <= Local::now())
+    }
+
+    pub fn record_refresh_success(&mut self) {
+        self.last_refresh_at = Some(Local::now().to_rfc3339());
+        if self.last_error.as_ref().map(|e| e.operation) == Some(SyncOperation::Refresh) {
+            self.last_error = None;
+        }
+    }
+
+    pub fn record_sync_success(&mut self) {
+        let now = Local::now().to_rfc3339();
+        self.last_refresh_at = Some(now.clone());
+        self.last_synced_at = Some(now);
+        self.last_error = None;
+    }
+
+    /// 记录一次失败（保留最近 ERROR_HISTORY_LIMIT 条）
+    pub fn record_error(&mut self, operation: SyncOperation, error: &AppError) {
+        let error = SyncError {
+            at: Local::now().to_rfc3339(),
+            operation,
+            category: ErrorCategory::of(error),
+            message: error.to_string(),
+        };
+        self.error_history.push(error.clone());
+        if self.error_history.len() > ERROR_HISTORY_LIMIT {
+            let excess = self.error_history.len() - ERROR_HISTORY_LIMIT;
+            self.error_history.drain(..excess);
+        }
+        self.last_error = Some(error);
+    }
+
+    /// 更新状态；状态不变时保留原来的 since
+    pub fn set_status(&mut self, status: AccountStatus) {
+        if self.status.state == status.state {
+            self.status.reason = status.reason;
+        } else {
+            self.status = status;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacetCount {
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountFacets {
+    pub tags: Vec<FacetCount>,
+    pub groups: Vec<FacetCount>,
+}
+
+fn bump_facet(facets: &mut Vec<FacetCount>, name: &str) {
+    match facets.iter_mut().find(|f| f.name == name) {
+        Some(f) => f.count += 1,
+        None => facets.push(FacetCount { name: name.to_string(), count: 1 }),
+    }
+}
+
+/// 去掉首尾空白、空标签和重复标签（不区分大小写）
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !normalized.iter().any(|n| n.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
         }
     }
+    normalized
+}
+
+/// 存储后端信息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageInfo {
+    pub backend: String,
+    pub path: String,
+    pub format_version: u32,
+    // 不为空时禁止写入（例如文件由更新版本的应用写入）
+    pub read_only: Option<String>,
+    // 本次启动隔离的损坏文件
+    pub quarantined: Vec<String>,
 }
 
 pub struct AccountStore {
     pub accounts: Vec<Account>,
     file_path: PathBuf,
+    // SQLite 后端（None 表示使用 accounts.json）
+    db: Option<AccountDb>,
+    vault: Vault,
+    // 保留的备份数量
+    backup_keep: usize,
+    read_only: Option<String>,
+    quarantined: Vec<String>,
+    // 上次与磁盘同步时的账号快照 (id -> JSON)，用于合并其他进程的修改
+    baseline: HashMap<String, String>,
+    // 上次同步时 accounts.json 的修改时间和大小
+    disk_stamp: Option<FileStamp>,
 }
 
 impl AccountStore {
     pub fn new() -> Self {
         let file_path = Self::get_storage_path();
-        let accounts = Self::load_from_file(&file_path);
-        Self { accounts, file_path }
+        // 首次导入 accounts.db 等初始化操作也要和其他实例互斥
+        let lock = FileLock::acquire_within(&file_path, STARTUP_LOCK_TIMEOUT)
+            .map_err(|e| warn!("Failed to lock accounts: {}", e))
+            .ok();
+        let vault = Vault::load(file_path.parent().unwrap_or(&file_path));
+        let db = if crate::commands::app_settings_cmd::get_account_storage() == "sqlite" {
+            match Self::open_db(&file_path) {
+                Ok(db) => Some(db),
+                Err(e) => {
+                    warn!("Failed to open accounts.db, fallback to JSON: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let backup_keep = crate::commands::app_settings_cmd::get_account_backup_count();
+        let mut store = Self {
+            accounts: Vec::new(),
+            file_path,
+            db,
+            vault,
+            backup_keep,
+            read_only: None,
+            quarantined: Vec::new(),
+            baseline: HashMap::new(),
+            disk_stamp: None,
+        };
+        // 启用加密时启动即为锁定状态，解锁后才加载账号
+        if !store.vault.is_enabled() {
+            store.accounts = store.read_persisted();
+            store.mark_synced();
+        }
+        drop(lock);
+        store
+    }
+
+    /// 打开 accounts.db，首次使用时导入 accounts.json
+    fn open_db(file_path: &Path) -> AppResult<AccountDb> {
+        let db = AccountDb::open(&file_path.with_file_name("accounts.db"))?;
+        db.import_json_once(file_path)?;
+        Ok(db)
+    }
+
+    /// 从当前后端读取磁盘上的账号（加密时为封存状态）
+    fn read_persisted(&mut self) -> Vec<Account> {
+        let db = match &self.db {
+            Some(db) => db,
+            None => return self.load_from_file(),
+        };
+        let version = db.format_version().unwrap_or(FORMAT_VERSION);
+        let accounts = db.load_all().unwrap_or_else(|e| {
+            warn!("Failed to load accounts.db: {}", e);
+            Vec::new()
+        });
+        if version > FORMAT_VERSION {
+            self.read_only = Some(format!(
+                "accounts.db 由更新版本的应用写入 (格式 v{}，当前支持 v{})，为避免数据丢失已禁止写入",
+                version, FORMAT_VERSION
+            ));
+        }
+        accounts
     }
 
     fn get_storage_path() -> PathBuf {
@@ -84,64 +441,588 @@ impl AccountStore {
         data_dir.join(".kiro-account-manager").join("accounts.json")
     }
 
-    fn load_from_file(path: &PathBuf) -> Vec<Account> {
-        if let Ok(content) = std::fs::read_to_string(path) {
-            serde_json::from_str(&content).unwrap_or_default()
+    fn load_from_file(&mut self) -> Vec<Account> {
+        let content = match std::fs::read_to_string(&self.file_path) {
+            Ok(content) => content,
+            Err(_) => return Vec::new(),
+        };
+
+        match account_format::decode(&content) {
+            Ok(decoded) => {
+                if !decoded.rejected.is_empty() {
+                    self.quarantine(&content, &format!("{} 个账号条目无法解析", decoded.rejected.len()));
+                }
+                if decoded.format_version < FORMAT_VERSION {
+                    info!("Migrated accounts.json v{} -> v{}", decoded.format_version, FORMAT_VERSION);
+                }
+                decoded.accounts
+            }
+            Err(DecodeError::NewerVersion { format_version, app_version, accounts }) => {
+                self.read_only = Some(format!(
+                    "accounts.json 由更新版本的应用写入 (格式 v{}，应用 {})，为避免数据丢失已禁止写入",
+                    format_version,
+                    app_version.as_deref().unwrap_or("未知")
+                ));
+                accounts
+            }
+            Err(DecodeError::Unparseable(e)) => {
+                self.quarantine(&content, &e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 把无法完整解析的 accounts.json 另存一份，避免下次保存时被覆盖丢失
+    fn quarantine(&mut self, content: &str, reason: &str) {
+        let file_name = format!("accounts.corrupt-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        let path = self.file_path.with_file_name(&file_name);
+        match write_atomic(&path, content.as_bytes()) {
+            Ok(()) => {
+                warn!("Quarantined accounts.json to {} ({})", file_name, reason);
+                self.quarantined.push(file_name);
+            }
+            Err(e) => {
+                // 隔离失败时禁止写入，保留原文件
+                warn!("Failed to quarantine accounts.json: {}", e);
+                self.read_only = Some(format!("accounts.json 无法解析且隔离失败: {}", reason));
+            }
+        }
+    }
+
+    /// 磁盘上的账号形式（启用加密时敏感字段已封存）
+    fn persisted_form(&self) -> AppResult<Vec<Account>> {
+        if !self.vault.is_enabled() {
+            return Ok(self.accounts.clone());
+        }
+        // 锁定时内存中只有解锁前新增的账号，无法封存，等解锁后再合并保存
+        if self.vault.is_locked() {
+            return Err(AppError::Other("账号库已锁定，新账号将在解锁后保存".to_string()));
+        }
+        self.accounts.iter().map(|a| self.vault.seal_account(a)).collect::<Result<_, String>>().map_err(AppError::Other)
+    }
+
+    /// 持有进程间锁保存：磁盘被其他进程改过时先合并再写入
+    pub fn save_to_file(&mut self) -> AppResult<()> {
+        let _lock = FileLock::acquire(&self.file_path)?;
+        self.merge_external_changes()?;
+        if let Some(reason) = &self.read_only {
+            return Err(AppError::Other(reason.clone()));
+        }
+        let accounts = self.persisted_form()?;
+        let json = account_format::encode(&accounts)?;
+
+        // 覆盖前备份磁盘上的现有内容，备份失败不影响保存
+        if let Some(current) = self.disk_content().filter(|current| *current != json) {
+            if let Err(e) = account_backup::create_backup_if_due(&self.backup_dir(), &current, self.backup_keep) {
+                warn!("Backup failed: {}", e);
+            }
+        }
+
+        match &self.db {
+            Some(db) => db.save_all(&accounts)?,
+            None => write_atomic(&self.file_path, json.as_bytes())
+                .map_err(|e| AppError::io("保存 accounts.json 失败", e))?,
+        }
+        self.mark_synced();
+        Ok(())
+    }
+
+    /// 磁盘上当前保存的账号（SQLite 后端编码为 accounts.json 格式），尚未保存过时为 None
+    fn disk_content(&self) -> Option<String> {
+        match &self.db {
+            Some(db) => match db.load_all() {
+                Ok(accounts) if !accounts.is_empty() => account_format::encode(&accounts).ok(),
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("Failed to read accounts.db for backup: {}", e);
+                    None
+                }
+            },
+            None => std::fs::read_to_string(&self.file_path).ok(),
+        }
+    }
+
+    // ============================================================
+    // 多实例同步
+    // ============================================================
+
+    /// 磁盘上的账号若被其他进程修改则合并进内存（供读取账号列表前调用）
+    pub fn reload_if_changed(&mut self) -> AppResult<bool> {
+        if self.vault.is_locked() {
+            return Ok(false);
+        }
+        let _lock = FileLock::acquire(&self.file_path)?;
+        let merged = self.merge_external_changes()?;
+        if merged {
+            self.mark_synced();
+        }
+        Ok(merged)
+    }
+
+    fn disk_changed(&self) -> bool {
+        match &self.db {
+            Some(db) => db.changed_externally(),
+            // 文件被删除不视为修改，避免把账号全部当作已删除
+            None => matches!(file_stamp(&self.file_path), Some(stamp) if Some(stamp) != self.disk_stamp),
+        }
+    }
+
+    /// 记录当前内存状态为与磁盘一致
+    fn mark_synced(&mut self) {
+        self.baseline = self.accounts.iter()
+            .filter_map(|a| Some((a.id.clone(), serde_json::to_string(a).ok()?)))
+            .collect();
+        self.disk_stamp = file_stamp(&self.file_path);
+    }
+
+    /// 调用方需持有 FileLock；返回是否发生了合并
+    fn merge_external_changes(&mut self) -> AppResult<bool> {
+        if self.vault.is_locked() || !self.disk_changed() {
+            return Ok(false);
+        }
+        let quarantined = self.quarantined.len();
+        let persisted = self.read_persisted();
+        if self.quarantined.len() > quarantined {
+            // 磁盘文件已损坏并被隔离，以内存中的账号为准
+            return Ok(false);
+        }
+        let disk = if self.vault.is_enabled() {
+            persisted.into_iter()
+                .map(|a| self.vault.open_account(a))
+                .collect::<Result<Vec<_>, _>>()?
         } else {
-            Vec::new()
+            persisted
+        };
+        info!("Accounts changed by another process, merging {} accounts from disk", disk.len());
+        self.merge_from_disk(disk);
+        Ok(true)
+    }
+
+    /// 三方合并：本进程改过的账号以本进程为准，其余采用磁盘上的版本；
+    /// 其他进程新增的账号放在最前，双方删除的账号都不再恢复
+    fn merge_from_disk(&mut self, disk: Vec<Account>) {
+        let mut theirs: HashMap<String, Account> = disk.iter()
+            .map(|a| (a.id.clone(), a.clone()))
+            .collect();
+
+        let mut merged = Vec::with_capacity(self.accounts.len().max(disk.len()));
+        for ours in std::mem::take(&mut self.accounts) {
+            let changed_here = self.baseline.get(&ours.id) != serde_json::to_string(&ours).ok().as_ref();
+            match theirs.remove(&ours.id) {
+                _ if changed_here => merged.push(ours),
+                Some(disk_version) => merged.push(disk_version),
+                None => {}
+            }
         }
+
+        let added: Vec<Account> = disk.into_iter()
+            .filter(|a| theirs.contains_key(&a.id) && !self.baseline.contains_key(&a.id))
+            .collect();
+        merged.splice(0..0, added);
+        self.accounts = merged;
+    }
+
+    // ============================================================
+    // 备份
+    // ============================================================
+
+    fn backup_dir(&self) -> PathBuf {
+        account_backup::backup_dir(self.file_path.parent().unwrap_or(&self.file_path))
     }
 
-    pub fn save_to_file(&self) {
-        if let Some(parent) = self.file_path.parent() {
-            let _ = std::fs::create_dir_all(parent);
+    pub fn list_backups(&self) -> AppResult<Vec<AccountBackup>> {
+        account_backup::list_backups(&self.backup_dir())
+    }
+
+    /// 从备份恢复（恢复前先备份当前账号，便于撤销）
+    pub fn restore_backup(&mut self, file_name: &str) -> AppResult<usize> {
+        if let Some(reason) = &self.read_only {
+            return Err(AppError::Other(reason.clone()));
         }
-        if let Ok(json) = serde_json::to_string_pretty(&self.accounts) {
-            let _ = std::fs::write(&self.file_path, json);
+        let content = account_backup::read_backup(&self.backup_dir(), file_name)?;
+        let persisted = match account_format::decode(&content) {
+            Ok(decoded) => decoded.accounts,
+            Err(DecodeError::NewerVersion { format_version, .. }) => {
+                return Err(AppError::Other(format!("备份格式版本 v{} 高于当前支持的 v{}", format_version, FORMAT_VERSION)));
+            }
+            Err(DecodeError::Unparseable(e)) => return Err(AppError::Parse(format!("解析备份失败: {}", e))),
+        };
+        let restored = if self.vault.is_enabled() {
+            persisted.into_iter()
+                .map(|a| self.vault.open_account(a))
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            persisted
+        };
+
+        let current = account_format::encode(&self.persisted_form()?)?;
+        account_backup::create_backup(&self.backup_dir(), &current, self.backup_keep)?;
+
+        self.accounts = restored;
+        self.save_to_file()?;
+        Ok(self.accounts.len())
+    }
+
+    // ============================================================
+    // 存储后端
+    // ============================================================
+
+    pub fn storage_info(&self) -> StorageInfo {
+        let (backend, path) = match &self.db {
+            Some(_) => ("sqlite", self.file_path.with_file_name("accounts.db")),
+            None => ("json", self.file_path.clone()),
+        };
+        StorageInfo {
+            backend: backend.to_string(),
+            path: path.to_string_lossy().to_string(),
+            format_version: FORMAT_VERSION,
+            read_only: self.read_only.clone(),
+            quarantined: self.quarantined.clone(),
         }
     }
 
+    /// 切换存储后端，并把内存中的账号写入新后端
+    pub fn set_backend(&mut self, backend: &str) -> AppResult<StorageInfo> {
+        if self.vault.is_locked() {
+            return Err(AppError::Other("账号库已锁定，请先解锁".to_string()));
+        }
+        if let Some(reason) = &self.read_only {
+            return Err(AppError::Other(reason.clone()));
+        }
+        match backend {
+            "sqlite" => {
+                if self.db.is_none() {
+                    let db = Self::open_db(&self.file_path)?;
+                    db.load_all()?;
+                    self.db = Some(db);
+                }
+            }
+            "json" => self.db = None,
+            other => return Err(AppError::Other(format!("不支持的存储后端: {}", other))),
+        }
+        self.save_to_file()?;
+        Ok(self.storage_info())
+    }
+
+    /// 第一个匹配的账号，没有则用 create 新建并放在最前（登录、添加账号时使用）
+    pub fn find_or_insert(&mut self, matches: impl Fn(&Account) -> bool, create: impl FnOnce() -> Account) -> &mut Account {
+        let index = match self.accounts.iter().position(matches) {
+            Some(index) => index,
+            None => {
+                self.accounts.insert(0, create());
+                0
+            }
+        };
+        &mut self.accounts[index]
+    }
+
+    // ============================================================
+    // 加密账号库
+    // ============================================================
+
+    pub fn vault_status(&self) -> VaultStatus {
+        self.vault.status()
+    }
+
+    /// 启用加密，并立即把现有明文账号封存写回（原地迁移）
+    pub fn enable_vault(&mut self, passphrase: &str) -> AppResult<()> {
+        self.vault.enable(passphrase)?;
+        self.save_to_file()
+    }
+
+    /// 解锁并加载账号，锁定期间新增的账号会合并进来
+    pub fn unlock_vault(&mut self, passphrase: &str) -> AppResult<()> {
+        if !self.vault.is_locked() {
+            return Ok(());
+        }
+        self.vault.unlock(passphrase)?;
+
+        let mut accounts = Vec::new();
+        for account in self.read_persisted() {
+            match self.vault.open_account(account) {
+                Ok(a) => accounts.push(a),
+                Err(e) => {
+                    self.vault.abort_unlock();
+                    return Err(e.into());
+                }
+            }
+        }
+        for pending in self.accounts.drain(..) {
+            if !accounts.iter().any(|a| a.id == pending.id) {
+                accounts.insert(0, pending);
+            }
+        }
+        self.accounts = accounts;
+        self.save_to_file()?;
+        Ok(self.vault.settle_pending()?)
+    }
+
+    /// 保存后清除密钥和内存中的账号
+    pub fn lock_vault(&mut self) -> AppResult<()> {
+        if !self.vault.is_enabled() {
+            return Err(AppError::Other("账号库未启用加密".to_string()));
+        }
+        if self.vault.is_locked() {
+            return Ok(());
+        }
+        self.save_to_file()?;
+        self.vault.lock();
+        self.accounts.clear();
+        self.baseline.clear();
+        Ok(())
+    }
+
+    /// 关闭加密，账号以明文写回
+    pub fn disable_vault(&mut self, passphrase: &str) -> AppResult<()> {
+        self.unlock_vault(passphrase)?;
+        self.vault.disable(passphrase)?;
+        self.save_to_file()
+    }
+
+    /// 更换主密码：先用新密钥重新封存并写入账号，再替换 vault.json
+    /// 任一步失败都恢复旧密钥；替换前崩溃时 vault.pending.json 保证新密码仍能解锁
+    pub fn change_vault_passphrase(&mut self, old_passphrase: &str, new_passphrase: &str) -> AppResult<()> {
+        self.unlock_vault(old_passphrase)?;
+        let previous = self.vault.begin_change_passphrase(old_passphrase, new_passphrase)?;
+        if let Err(e) = self.save_to_file() {
+            // 账号写入是原子的，失败时磁盘上仍是旧密钥封存
+            self.vault.rollback_passphrase(previous);
+            self.vault.discard_pending();
+            return Err(e);
+        }
+        if let Err(e) = self.vault.commit_passphrase() {
+            // 账号已是新密钥封存，用旧密钥写回；写回也失败时保留 vault.pending.json 供新密码解锁
+            self.vault.rollback_passphrase(previous);
+            if self.save_to_file().is_ok() {
+                self.vault.discard_pending();
+            }
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// 记录刷新失败（错误历史和状态）并保存，原样返回错误便于 `map_err` 链式使用
+    pub fn record_refresh_error(&mut self, id: &str, error: AppError) -> AppError {
+        if let Some(account) = self.accounts.iter_mut().find(|a| a.id == id) {
+            account.record_error(SyncOperation::Refresh, &error);
+            account.set_status(AccountStatus::from_refresh_error(&error));
+            if let Err(e) = self.save_to_file() {
+                warn!("Failed to save account status: {}", e);
+            }
+        }
+        error
+    }
+
     pub fn get_all(&self) -> Vec<Account> {
         self.accounts.clone()
     }
 
-    pub fn delete(&mut self, id: &str) -> bool {
+    pub fn delete(&mut self, id: &str) -> AppResult<bool> {
         let len_before = self.accounts.len();
         self.accounts.retain(|a| a.id != id);
         let deleted = self.accounts.len() < len_before;
         if deleted {
-            self.save_to_file();
+            self.save_to_file()?;
+        }
+        Ok(deleted)
+    }
+
+    // ============================================================
+    // 查询与分类
+    // ============================================================
+
+    pub fn query(&self, query: &AccountQuery) -> AccountQueryResult {
+        query.run(&self.accounts)
+    }
+
+    /// 批量命令的目标账号：显式 id 列表或查询结果（两者都给时取并集）
+    pub fn resolve_ids(&self, ids: Option<Vec<String>>, query: Option<&AccountQuery>) -> AppResult<Vec<String>> {
+        if ids.is_none() && query.is_none() {
+            return Err(AppError::Other("需要指定账号 id 或查询条件".to_string()));
+        }
+        let mut resolved = ids.unwrap_or_default();
+        if let Some(query) = query {
+            for account in query.run(&self.accounts).accounts {
+                if !resolved.contains(&account.id) {
+                    resolved.push(account.id);
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// 批量增删标签，返回有变化的账号数
+    pub fn update_tags(&mut self, ids: &[String], add: &[String], remove: &[String]) -> AppResult<usize> {
+        let add = normalize_tags(add);
+        let remove = normalize_tags(remove);
+        let mut changed = 0;
+        for account in self.accounts.iter_mut().filter(|a| ids.contains(&a.id)) {
+            let before = account.tags.clone();
+            account.tags.retain(|t| !remove.iter().any(|r| r.eq_ignore_ascii_case(t)));
+            for tag in &add {
+                if !account.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    account.tags.push(tag.clone());
+                }
+            }
+            if account.tags != before {
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.save_to_file()?;
+        }
+        Ok(changed)
+    }
+
+    /// 批量设置分组（None 或空字符串表示移出分组），返回有变化的账号数
+    pub fn set_group(&mut self, ids: &[String], group: Option<String>) -> AppResult<usize> {
+        let group = group.map(|g| g.trim().to_string()).filter(|g| !g.is_empty());
+        let mut changed = 0;
+        for account in self.accounts.iter_mut().filter(|a| ids.contains(&a.id)) {
+            if account.group != group {
+                account.group = group.clone();
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.save_to_file()?;
         }
-        deleted
+        Ok(changed)
     }
 
-    pub fn delete_many(&mut self, ids: &[String]) -> usize {
+    /// 所有标签和分组及其账号数
+    pub fn facets(&self) -> AccountFacets {
+        let mut facets = AccountFacets::default();
+        for account in &self.accounts {
+            for tag in &account.tags {
+                bump_facet(&mut facets.tags, tag);
+            }
+            if let Some(group) = &account.group {
+                bump_facet(&mut facets.groups, group);
+            }
+        }
+        facets
+    }
+
+    pub fn delete_many(&mut self, ids: &[String]) -> AppResult<usize> {
         let len_before = self.accounts.len();
         self.accounts.retain(|a| !ids.contains(&a.id));
         let deleted = len_before - self.accounts.len();
         if deleted > 0 {
-            self.save_to_file();
+            self.save_to_file()?;
         }
-        deleted
+        Ok(deleted)
     }
 
-    pub fn import_from_json(&mut self, json: &str) -> Result<usize, String> {
-        match serde_json::from_str::<Vec<Account>>(json) {
-            Ok(imported) => {
-                let count = imported.len();
-                for account in imported {
-                    if !self.accounts.iter().any(|a| a.id == account.id) {
-                        self.accounts.push(account);
-                    }
-                }
-                self.save_to_file();
-                Ok(count)
-            }
-            Err(e) => Err(e.to_string()),
+    /// 按导入模式合并账号；dry-run 时只返回差异，不修改账号
+    /// 支持 accounts.json、加密备份和 IDE 的 kiro-auth-token.json
+    pub fn import_from_json(&mut self, json: &str, options: &ImportOptions) -> AppResult<ImportReport> {
+        if self.vault.is_locked() {
+            return Err(AppError::Other("账号库已锁定，请先解锁".to_string()));
         }
+        let incoming = account_export::decode_import(
+            json,
+            options.password.as_deref(),
+            options.client_registration.as_deref(),
+        )?;
+
+        let mut merged = self.accounts.clone();
+        let report = account_import::merge_accounts(&mut merged, incoming, options)?;
+        if !options.dry_run && report.has_changes() {
+            self.accounts = merged;
+            self.save_to_file()?;
+        }
+        Ok(report)
     }
 
     pub fn export_to_json(&self) -> String {
         serde_json::to_string_pretty(&self.accounts).unwrap_or_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usage_errors_that_say_nothing_about_the_account_keep_its_status() {
+        let network: Result<(), AppError> = Err(AppError::Network("timeout".to_string()));
+        assert_eq!(AccountStatus::from_usage_result(&network), None);
+        let server: Result<(), AppError> = Err(AppError::Http { status: 503, message: String::new() });
+        assert_eq!(AccountStatus::from_usage_result(&server), None);
+
+        let suspended: Result<(), AppError> = Err(AppError::AccountSuspended { reason: "x".to_string() });
+        assert_eq!(AccountStatus::from_usage_result(&suspended).map(|s| s.state), Some(AccountState::Banned));
+        assert_eq!(AccountStatus::from_usage_result(&Ok(())).map(|s| s.state), Some(AccountState::Active));
+    }
+
+    fn test_store(accounts: Vec<Account>) -> AccountStore {
+        let dir = std::env::temp_dir().join(format!("kiro-store-test-{}", uuid::Uuid::new_v4()));
+        let mut store = AccountStore {
+            accounts,
+            file_path: dir.join("accounts.json"),
+            db: None,
+            vault: Vault::load(&dir),
+            backup_keep: 1,
+            read_only: None,
+            quarantined: Vec::new(),
+            baseline: HashMap::new(),
+            disk_stamp: None,
+        };
+        store.mark_synced();
+        store
+    }
+
+    #[test]
+    fn save_backs_up_the_content_being_replaced() {
+        let mut store = test_store(vec![Account::new("a@example.com".to_string(), "a".to_string())]);
+        store.save_to_file().unwrap();
+        assert!(store.list_backups().unwrap().is_empty());
+
+        // 误清空账号后，备份里应是清空前的内容
+        store.accounts.clear();
+        store.save_to_file().unwrap();
+        let backups = store.list_backups().unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].account_count, 1);
+    }
+
+    fn labels(accounts: &[Account]) -> Vec<&str> {
+        accounts.iter().map(|a| a.label.as_str()).collect()
+    }
+
+    #[test]
+    fn three_way_merge_keeps_local_edits_and_takes_the_rest_from_disk() {
+        let base: Vec<Account> = ["a", "b", "c", "e"].iter()
+            .map(|name| Account::new(format!("{}@example.com", name), name.to_string()))
+            .collect();
+        let mut store = test_store(base.clone());
+
+        // 本进程：改了 a，删了 c
+        store.accounts[0].label = "a-ours".to_string();
+        store.accounts.retain(|a| a.label != "c");
+
+        // 其他进程：改了 a 和 b，删了 e，新增 d
+        let mut disk = base.clone();
+        disk[0].label = "a-theirs".to_string();
+        disk[1].label = "b-theirs".to_string();
+        disk.retain(|a| a.label != "e");
+        disk.push(Account::new("d@example.com".to_string(), "d".to_string()));
+
+        store.merge_from_disk(disk);
+        assert_eq!(labels(&store.accounts), vec!["d", "a-ours", "b-theirs"]);
+    }
+
+    #[test]
+    fn three_way_merge_without_local_changes_adopts_disk() {
+        let base: Vec<Account> = ["a", "b"].iter()
+            .map(|name| Account::new(format!("{}@example.com", name), name.to_string()))
+            .collect();
+        let mut store = test_store(base.clone());
+
+        let mut disk = base;
+        disk[1].label = "b-theirs".to_string();
+        store.merge_from_disk(disk);
+        assert_eq!(labels(&store.accounts), vec!["a", "b-theirs"]);
+    }
+}
@@ -0,0 +1,133 @@
+// accounts.json 滚动备份
+// 备份存放在 ~/.kiro-account-manager/backups/accounts-YYYYMMDD-HHMMSS-mmm.json
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+use crate::fs_util::write_atomic;
+
+/// 两次自动备份的最小间隔（秒），避免频繁刷新 token 时备份被同一时刻的快照挤满
+const BACKUP_MIN_INTERVAL_SECS: u64 = 10 * 60;
+const BACKUP_PREFIX: &str = "accounts-";
+const BACKUP_SUFFIX: &str = ".json";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountBackup {
+    pub file_name: String,
+    pub created_at: String,
+    pub account_count: usize,
+    pub size: u64,
+}
+
+pub fn backup_dir(store_dir: &Path) -> PathBuf {
+    store_dir.join("backups")
+}
+
+/// 写入一份新备份并清理超出数量的旧备份
+pub fn create_backup(dir: &Path, content: &str, keep: usize) -> AppResult<AccountBackup> {
+    let now = chrono::Local::now();
+    let file_name = format!("{}{}{}", BACKUP_PREFIX, now.format("%Y%m%d-%H%M%S-%3f"), BACKUP_SUFFIX);
+    write_atomic(&dir.join(&file_name), content.as_bytes())
+        .map_err(|e| AppError::io("写入备份失败", e))?;
+    prune_backups(dir, keep)?;
+
+    Ok(AccountBackup {
+        file_name,
+        created_at: now.format("%Y/%m/%d %H:%M:%S").to_string(),
+        account_count: count_accounts(content),
+        size: content.len() as u64,
+    })
+}
+
+/// 距上次备份超过最小间隔时才备份
+pub fn create_backup_if_due(dir: &Path, content: &str, keep: usize) -> AppResult<Option<AccountBackup>> {
+    let newest_age = list_backup_files(dir)?
+        .first()
+        .and_then(|path| path.metadata().ok())
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|modified| modified.elapsed().ok());
+
+    match newest_age {
+        Some(age) if age.as_secs() < BACKUP_MIN_INTERVAL_SECS => Ok(None),
+        _ => create_backup(dir, content, keep).map(Some),
+    }
+}
+
+/// 列出备份（最新的在前）
+pub fn list_backups(dir: &Path) -> AppResult<Vec<AccountBackup>> {
+    let mut backups = Vec::new();
+    for path in list_backup_files(dir)? {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        backups.push(AccountBackup {
+            created_at: parse_backup_time(&file_name).unwrap_or_default(),
+            account_count: count_accounts(&content),
+            size: content.len() as u64,
+            file_name,
+        });
+    }
+    Ok(backups)
+}
+
+/// 读取指定备份的内容
+pub fn read_backup(dir: &Path, file_name: &str) -> AppResult<String> {
+    let is_backup_name = file_name.starts_with(BACKUP_PREFIX)
+        && file_name.ends_with(BACKUP_SUFFIX)
+        && !file_name.contains(['/', '\\'])
+        && !file_name.contains("..");
+    if !is_backup_name {
+        return Err(AppError::Other(format!("无效的备份文件名: {}", file_name)));
+    }
+    std::fs::read_to_string(dir.join(file_name))
+        .map_err(|e| AppError::io("读取备份失败", e))
+}
+
+/// 备份文件按文件名（即时间）倒序
+fn list_backup_files(dir: &Path) -> AppResult<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| AppError::io("读取备份目录失败", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy())
+                .map(|n| n.starts_with(BACKUP_PREFIX) && n.ends_with(BACKUP_SUFFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+    files.sort();
+    files.reverse();
+    Ok(files)
+}
+
+fn prune_backups(dir: &Path, keep: usize) -> AppResult<()> {
+    for path in list_backup_files(dir)?.into_iter().skip(keep.max(1)) {
+        std::fs::remove_file(&path)
+            .map_err(|e| AppError::io("删除旧备份失败", e))?;
+    }
+    Ok(())
+}
+
+fn parse_backup_time(file_name: &str) -> Option<String> {
+    let stamp = file_name.strip_prefix(BACKUP_PREFIX)?.strip_suffix(BACKUP_SUFFIX)?;
+    let stamp = stamp.get(..15)?;
+    chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S")
+        .ok()
+        .map(|t| t.format("%Y/%m/%d %H:%M:%S").to_string())
+}
+
+/// 兼容 v1 裸数组和 v2 信封格式
+fn count_accounts(content: &str) -> usize {
+    serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|v| {
+            v.as_array()
+                .or_else(|| v.get("accounts").and_then(|a| a.as_array()))
+                .map(|a| a.len())
+        })
+        .unwrap_or(0)
+}
@@ -0,0 +1,249 @@
+// SQLite 账号存储 (accounts.db)
+// 每个账号一行，完整 Account 以 JSON 存在 data 列，email/provider 单独建索引
+
+use rusqlite::{params, Connection, OptionalExtension};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::account::Account;
+use crate::account_format::{self, DecodeError, FORMAT_VERSION};
+use crate::error::{AppError, AppResult};
+use tracing::{info, warn};
+
+/// 按版本号顺序执行的迁移脚本，已执行的版本记录在 schema_migrations
+const MIGRATIONS: &[(i64, &str)] = &[
+    (1, "
+        CREATE TABLE accounts (
+            id TEXT PRIMARY KEY,
+            email TEXT NOT NULL,
+            provider TEXT,
+            status TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            data TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE INDEX idx_accounts_email ON accounts(email);
+        CREATE INDEX idx_accounts_provider ON accounts(provider);
+        CREATE TABLE meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+    "),
+];
+
+const META_JSON_IMPORTED: &str = "json_imported_at";
+const META_FORMAT_VERSION: &str = "account_format_version";
+/// accounts.db 在账号格式 v2 时引入，没有记录版本的库按 v2 处理
+const DB_INITIAL_FORMAT_VERSION: u32 = 2;
+
+pub struct AccountDb {
+    conn: Connection,
+    /// 上次写入的行内容（id -> (position, data)），保存时只写有变化的行
+    written: RefCell<HashMap<String, (i64, String)>>,
+    /// 上次看到的 PRAGMA data_version，其他连接提交后会变化
+    data_version: Cell<i64>,
+}
+
+impl AccountDb {
+    pub fn open(path: &Path) -> AppResult<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::io("创建目录失败", e))?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| AppError::io("打开 accounts.db 失败", e))?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+            .map_err(|e| AppError::io("设置 accounts.db 失败", e))?;
+
+        let db = Self {
+            conn,
+            written: RefCell::new(HashMap::new()),
+            data_version: Cell::new(0),
+        };
+        db.migrate()?;
+        db.data_version.set(db.read_data_version()?);
+        Ok(db)
+    }
+
+    /// 执行未应用的迁移
+    fn migrate(&self) -> AppResult<()> {
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                applied_at TEXT NOT NULL
+            );"
+        ).map_err(|e| AppError::io("创建迁移表失败", e))?;
+
+        let current: i64 = self.conn
+            .query_row("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", [], |row| row.get(0))
+            .map_err(|e| AppError::io("读取数据库版本失败", e))?;
+
+        for (version, sql) in MIGRATIONS.iter().filter(|(v, _)| *v > current) {
+            let tx = self.conn.unchecked_transaction()
+                .map_err(|e| AppError::io("开启事务失败", e))?;
+            tx.execute_batch(sql)
+                .map_err(|e| AppError::io(&format!("执行迁移 v{} 失败", version), e))?;
+            tx.execute(
+                "INSERT INTO schema_migrations (version, applied_at) VALUES (?1, ?2)",
+                params![version, chrono::Local::now().to_rfc3339()],
+            ).map_err(|e| AppError::io(&format!("记录迁移 v{} 失败", version), e))?;
+            tx.commit().map_err(|e| AppError::io(&format!("提交迁移 v{} 失败", version), e))?;
+            info!("Applied migration v{}", version);
+        }
+        Ok(())
+    }
+
+    /// 首次使用时从 accounts.json 导入（只执行一次，原文件保留）
+    pub fn import_json_once(&self, json_path: &Path) -> AppResult<usize> {
+        if self.get_meta(META_JSON_IMPORTED)?.is_some() {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        if let Ok(content) = std::fs::read_to_string(json_path) {
+            let accounts = match account_format::decode(&content) {
+                Ok(decoded) => decoded.accounts,
+                Err(DecodeError::NewerVersion { format_version, .. }) => {
+                    return Err(AppError::Other(format!("accounts.json 格式版本 v{} 高于当前支持的 v{}", format_version, FORMAT_VERSION)));
+                }
+                Err(DecodeError::Unparseable(e)) => {
+                    return Err(AppError::Parse(format!("解析 accounts.json 失败: {}", e)));
+                }
+            };
+            count = accounts.len();
+            self.save_all(&accounts)?;
+        }
+
+        self.set_meta(META_JSON_IMPORTED, &chrono::Local::now().to_rfc3339())?;
+        info!("Imported {} accounts from {:?}", count, json_path);
+        Ok(count)
+    }
+
+    fn read_data_version(&self) -> AppResult<i64> {
+        self.conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))
+            .map_err(|e| AppError::io("读取 data_version 失败", e))
+    }
+
+    /// 自上次调用以来是否有其他进程写入过 accounts.db
+    pub fn changed_externally(&self) -> bool {
+        match self.read_data_version() {
+            Ok(version) => self.data_version.replace(version) != version,
+            Err(e) => {
+                warn!("{}", e);
+                false
+            }
+        }
+    }
+
+    /// 库中账号数据的格式版本
+    pub fn format_version(&self) -> AppResult<u32> {
+        Ok(self.get_meta(META_FORMAT_VERSION)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DB_INITIAL_FORMAT_VERSION))
+    }
+
+    /// 读取全部账号并迁移到当前格式；由更新版本写入时只尽力解析
+    pub fn load_all(&self) -> AppResult<Vec<Account>> {
+        let version = self.format_version()?;
+        let mut stmt = self.conn
+            .prepare("SELECT id, position, data FROM accounts ORDER BY position")
+            .map_err(|e| AppError::io("查询账号失败", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?)))
+            .map_err(|e| AppError::io("查询账号失败", e))?;
+
+        let mut accounts = Vec::new();
+        let mut written = HashMap::new();
+        for row in rows {
+            let (id, position, data) = row.map_err(|e| AppError::io("读取账号失败", e))?;
+            let parsed = if version > FORMAT_VERSION {
+                serde_json::from_str::<Account>(&data).map_err(|e| e.to_string())
+            } else {
+                serde_json::from_str(&data)
+                    .map_err(|e| e.to_string())
+                    .and_then(|value| account_format::migrate_account(value, version))
+            };
+            match parsed {
+                Ok(account) => {
+                    accounts.push(account);
+                    written.insert(id, (position, data));
+                }
+                // 不记入 written，保存时不会被删除
+                Err(e) => warn!("Skip unreadable row {}: {}", id, e),
+            }
+        }
+        *self.written.borrow_mut() = written;
+        Ok(accounts)
+    }
+
+    /// 保存完整账号列表：只写入新增/变化的行，删除已不存在的行
+    pub fn save_all(&self, accounts: &[Account]) -> AppResult<()> {
+        let mut written = self.written.borrow_mut();
+        let tx = self.conn.unchecked_transaction()
+            .map_err(|e| AppError::io("开启事务失败", e))?;
+        let now = chrono::Local::now().to_rfc3339();
+
+        let mut next = HashMap::with_capacity(accounts.len());
+        for (position, account) in accounts.iter().enumerate() {
+            let position = position as i64;
+            let data = serde_json::to_string(account)
+                .map_err(|e| AppError::Parse(format!("序列化账号失败: {}", e)))?;
+            let unchanged = written.get(&account.id)
+                .map(|(p, d)| *p == position && *d == data)
+                .unwrap_or(false);
+            if !unchanged {
+                tx.execute(
+                    "INSERT INTO accounts (id, email, provider, status, position, data, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                     ON CONFLICT(id) DO UPDATE SET
+                        email = excluded.email,
+                        provider = excluded.provider,
+                        status = excluded.status,
+                        position = excluded.position,
+                        data = excluded.data,
+                        updated_at = excluded.updated_at",
+                    params![account.id, account.email, account.provider, account.status.state.as_str(), position, data, now],
+                ).map_err(|e| AppError::io("写入账号失败", e))?;
+            }
+            next.insert(account.id.clone(), (position, data));
+        }
+
+        for id in written.keys().filter(|id| !next.contains_key(*id)) {
+            tx.execute("DELETE FROM accounts WHERE id = ?1", params![id])
+                .map_err(|e| AppError::io("删除账号失败", e))?;
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![META_FORMAT_VERSION, FORMAT_VERSION.to_string()],
+        ).map_err(|e| AppError::io("写入格式版本失败", e))?;
+
+        tx.commit().map_err(|e| AppError::io("提交事务失败", e))?;
+        *written = next;
+        Ok(())
+    }
+
+    // ============================================================
+    // meta 表
+    // ============================================================
+
+    fn get_meta(&self, key: &str) -> AppResult<Option<String>> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .map_err(|e| AppError::io("读取 meta 失败", e))
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> AppResult<()> {
+        self.conn
+            .execute(
+                "INSERT INTO meta (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![key, value],
+            )
+            .map(|_| ())
+            .map_err(|e| AppError::io("写入 meta 失败", e))
+    }
+}
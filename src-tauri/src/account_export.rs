@@ -0,0 +1,198 @@
+// 账号导出配置
+// - full: 完整 accounts.json（含所有 token，仅用于本机迁移）
+// - redacted: 不含任何 token 的账号清单
+// - encrypted: 用密码加密的完整备份
+// - ide-token: 单个账号的 kiro-auth-token.json（IdC 账号另带 {clientIdHash}.json）
+// 导入时识别加密备份和 IDE token 文件
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::account::{Account, AccountStatus};
+use crate::account_format::{self, DecodeError, FORMAT_VERSION};
+use crate::account_query::parse_time;
+use crate::jwt;
+use crate::kiro::{self, ClientRegistration, KiroLocalToken};
+use crate::token_manager::TokenManager;
+use crate::vault::{self, PasswordSealed};
+
+const BUNDLE_FORMAT: &str = "kiro-account-bundle";
+const BUNDLE_VERSION: u32 = 1;
+const BUNDLE_AAD: &[u8] = b"kiro-account-bundle-v1";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportProfile {
+    #[default]
+    Full,
+    Redacted,
+    Encrypted,
+    IdeToken,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFile {
+    /// 建议的文件名
+    pub file_name: String,
+    pub content: String,
+}
+
+/// 加密备份文件
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptedBundle {
+    format: String,
+    version: u32,
+    created_at: String,
+    encryption: PasswordSealed,
+}
+
+/// 按配置导出选中的账号（调用方负责筛选）
+pub fn export(accounts: &[Account], profile: ExportProfile, password: Option<&str>) -> Result<Vec<ExportFile>, String> {
+    let date = chrono::Local::now().format("%Y-%m-%d");
+    match profile {
+        ExportProfile::Full => Ok(vec![ExportFile {
+            file_name: format!("kiro-accounts-{}.json", date),
+            content: account_format::encode(accounts)?,
+        }]),
+        ExportProfile::Redacted => {
+            let redacted: Vec<Account> = accounts.iter().map(redact).collect();
+            Ok(vec![ExportFile {
+                file_name: format!("kiro-accounts-inventory-{}.json", date),
+                content: encode_redacted(&redacted)?,
+            }])
+        }
+        ExportProfile::Encrypted => {
+            let password = password.ok_or("加密导出需要密码")?;
+            let plain = account_format::encode(accounts)?;
+            let bundle = EncryptedBundle {
+                format: BUNDLE_FORMAT.to_string(),
+                version: BUNDLE_VERSION,
+                created_at: chrono::Local::now().to_rfc3339(),
+                encryption: vault::seal_with_password(plain.as_bytes(), password, BUNDLE_AAD)?,
+            };
+            Ok(vec![ExportFile {
+                file_name: format!("kiro-accounts-{}.kbundle.json", date),
+                content: serde_json::to_string_pretty(&bundle)
+                    .map_err(|e| format!("序列化失败: {}", e))?,
+            }])
+        }
+        ExportProfile::IdeToken => match accounts {
+            [account] => export_ide_token(account),
+            _ => Err("IDE token 格式一次只能导出一个账号".to_string()),
+        },
+    }
+}
+
+/// 去掉所有 token 和密钥
+fn redact(account: &Account) -> Account {
+    let mut account = account.clone();
+    account.access_token = None;
+    account.refresh_token = None;
+    account.csrf_token = None;
+    account.session_token = None;
+    account.id_token = None;
+    account.client_secret = None;
+    account.sealed_secrets = None;
+    account
+}
+
+/// 信封上加 "redacted": true，导入时据此拒绝
+fn encode_redacted(accounts: &[Account]) -> Result<String, String> {
+    let mut envelope: Value = serde_json::from_str(&account_format::encode(accounts)?)
+        .map_err(|e| format!("序列化账号失败: {}", e))?;
+    envelope["redacted"] = Value::Bool(true);
+    serde_json::to_string_pretty(&envelope).map_err(|e| format!("序列化账号失败: {}", e))
+}
+
+fn export_ide_token(account: &Account) -> Result<Vec<ExportFile>, String> {
+    Ok(kiro::ide_files(account)?
+        .into_iter()
+        .map(|f| ExportFile { file_name: f.file_name, content: f.content })
+        .collect())
+}
+
+// ============================================================
+// 导入识别
+// ============================================================
+
+/// 解析导入内容：加密备份、IDE token 文件、accounts.json（v1 数组或信封）
+/// client_registration 为 IdC 账号 token 文件配套的 {clientIdHash}.json 内容
+pub fn decode_import(content: &str, password: Option<&str>, client_registration: Option<&str>) -> Result<Vec<Account>, String> {
+    let root: Value = serde_json::from_str(content)
+        .map_err(|e| format!("解析导入文件失败: {}", e))?;
+
+    if root.get("format").and_then(|f| f.as_str()) == Some(BUNDLE_FORMAT) {
+        let bundle: EncryptedBundle = serde_json::from_value(root)
+            .map_err(|e| format!("解析加密备份失败: {}", e))?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(format!("加密备份版本 v{} 高于当前支持的 v{}", bundle.version, BUNDLE_VERSION));
+        }
+        let password = password.ok_or("该文件是加密备份，需要提供密码")?;
+        let plain = vault::open_with_password(&bundle.encryption, password, BUNDLE_AAD)?;
+        let plain = String::from_utf8(plain).map_err(|e| format!("解析加密备份失败: {}", e))?;
+        return decode_accounts(&plain);
+    }
+
+    if root.get("refreshToken").is_some() && root.get("authMethod").is_some() {
+        let token: KiroLocalToken = serde_json::from_value(root)
+            .map_err(|e| format!("解析 kiro-auth-token.json 失败: {}", e))?;
+        let registration = client_registration
+            .map(|c| serde_json::from_str::<ClientRegistration>(c).map_err(|e| format!("解析客户端注册信息失败: {}", e)))
+            .transpose()?;
+        return Ok(vec![account_from_ide_token(token, registration)?]);
+    }
+
+    if root.get("redacted").and_then(|r| r.as_bool()) == Some(true) {
+        return Err("该文件是不含 token 的账号清单，不能导入".to_string());
+    }
+    decode_accounts(content)
+}
+
+fn decode_accounts(content: &str) -> Result<Vec<Account>, String> {
+    match account_format::decode(content) {
+        Ok(decoded) => Ok(decoded.accounts),
+        Err(DecodeError::NewerVersion { format_version, .. }) => {
+            Err(format!("导入文件格式版本 v{} 高于当前支持的 v{}", format_version, FORMAT_VERSION))
+        }
+        Err(DecodeError::Unparseable(e)) => Err(e),
+    }
+}
+
+/// token 文件里没有邮箱，token 是 JWT 时从 claims 补全身份，否则导入后需同步一次才能得到账号信息
+fn account_from_ide_token(token: KiroLocalToken, registration: Option<ClientRegistration>) -> Result<Account, String> {
+    let idc = token.auth_method.as_deref() == Some("IdC");
+    if idc && registration.is_none() {
+        return Err(format!(
+            "IdC 账号还需要客户端注册文件 {}.json",
+            token.client_id_hash.as_deref().unwrap_or("<clientIdHash>")
+        ));
+    }
+
+    let provider = token.provider.clone().unwrap_or_else(|| if idc { "BuilderId" } else { "Google" }.to_string());
+    let mut account = Account::new("unknown@kiro.dev".to_string(), format!("Kiro {} 账号 (导入)", provider));
+    account.status = AccountStatus::default();
+    account.access_token = token.access_token;
+    account.refresh_token = Some(token.refresh_token.ok_or("kiro-auth-token.json 缺少 refreshToken")?);
+    account.expires_at = token.expires_at.filter(|t| parse_time(t).is_some());
+    account.provider = Some(provider);
+    account.auth_method = token.auth_method;
+    account.profile_arn = token.profile_arn;
+    account.id_token = token.id_token;
+    account.sso_session_id = token.sso_session_id;
+    account.client_id_hash = token.client_id_hash;
+    account.region = token.region;
+    let claims = account.id_token.as_deref()
+        .and_then(jwt::decode_claims)
+        .or_else(|| account.access_token.as_deref().and_then(jwt::decode_claims));
+    if let Some(claims) = claims {
+        TokenManager::apply_claims(&mut account, claims);
+    }
+    if let Some(registration) = registration {
+        account.client_id = Some(registration.client_id);
+        account.client_secret = Some(registration.client_secret);
+        account.client_secret_expires_at = registration.expires_at;
+    }
+    Ok(account)
+}
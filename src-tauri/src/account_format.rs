@@ -0,0 +1,218 @@
+// accounts.json 版本化格式
+// v1: 裸数组 [Account, ...]
+// v2: 信封 { formatVersion, appVersion, savedAt, accounts: [...] }
+// v3: status 由中文字符串改为 { state, reason, since }
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::account::{Account, AccountStatus};
+use tracing::warn;
+
+/// 当前写入的格式版本
+pub const FORMAT_VERSION: u32 = 3;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// 迁移链：MIGRATIONS[i] 把单个账号从 v(i+1) 迁移到 v(i+2)
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+];
+
+/// v2 只引入外层信封，账号结构不变
+fn migrate_v1_to_v2(_account: &mut Value) -> Result<(), String> {
+    Ok(())
+}
+
+/// "正常" / "已封禁" 等字符串转为结构化状态
+fn migrate_v2_to_v3(account: &mut Value) -> Result<(), String> {
+    let obj = account.as_object_mut().ok_or("账号条目不是对象")?;
+    let status = match obj.get("status") {
+        Some(Value::String(legacy)) => AccountStatus::from_legacy(legacy),
+        Some(Value::Object(_)) => return Ok(()),
+        _ => AccountStatus::default(),
+    };
+    obj.insert("status".to_string(), serde_json::to_value(status).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    format_version: u32,
+    app_version: Option<String>,
+    accounts: Vec<Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvelopeRef<'a> {
+    format_version: u32,
+    app_version: &'a str,
+    saved_at: String,
+    accounts: &'a [Account],
+}
+
+/// 解析结果
+pub struct Decoded {
+    pub accounts: Vec<Account>,
+    pub format_version: u32,
+    /// 无法解析的账号条目（原文）
+    pub rejected: Vec<Value>,
+}
+
+pub enum DecodeError {
+    /// 整个文件无法解析
+    Unparseable(String),
+    /// 由更新版本的应用写入，尽力解析出的账号仅供只读展示
+    NewerVersion {
+        format_version: u32,
+        app_version: Option<String>,
+        accounts: Vec<Account>,
+    },
+}
+
+/// 解析 accounts.json（兼容 v1 裸数组），并把账号迁移到当前版本
+pub fn decode(content: &str) -> Result<Decoded, DecodeError> {
+    let root: Value = serde_json::from_str(content)
+        .map_err(|e| DecodeError::Unparseable(e.to_string()))?;
+
+    let (format_version, app_version, values) = match root {
+        Value::Array(values) => (1, None, values),
+        Value::Object(_) => {
+            let envelope: Envelope = serde_json::from_value(root)
+                .map_err(|e| DecodeError::Unparseable(e.to_string()))?;
+            (envelope.format_version, envelope.app_version, envelope.accounts)
+        }
+        _ => return Err(DecodeError::Unparseable("expected array or object".to_string())),
+    };
+
+    if format_version > FORMAT_VERSION {
+        let accounts = values.into_iter()
+            .filter_map(|v| serde_json::from_value(v).ok())
+            .collect();
+        return Err(DecodeError::NewerVersion { format_version, app_version, accounts });
+    }
+
+    let mut accounts = Vec::with_capacity(values.len());
+    let mut rejected = Vec::new();
+    for value in values {
+        match migrate_account(value.clone(), format_version) {
+            Ok(account) => accounts.push(account),
+            Err(e) => {
+                warn!("Reject account entry: {}", e);
+                rejected.push(value);
+            }
+        }
+    }
+
+    Ok(Decoded { accounts, format_version, rejected })
+}
+
+/// 把单个账号从 from 版本迁移到当前版本
+pub fn migrate_account(mut value: Value, from: u32) -> Result<Account, String> {
+    if from == 0 || from > FORMAT_VERSION {
+        return Err(format!("不支持的格式版本: v{}", from));
+    }
+    for migration in &MIGRATIONS[(from - 1) as usize..] {
+        migration(&mut value)?;
+    }
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// 序列化为当前版本的信封格式
+pub fn encode(accounts: &[Account]) -> Result<String, String> {
+    serde_json::to_string_pretty(&EnvelopeRef {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION"),
+        saved_at: chrono::Local::now().to_rfc3339(),
+        accounts,
+    })
+    .map_err(|e| format!("序列化账号失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::AccountState;
+    use serde_json::json;
+
+    /// 旧版本写入的账号：status 还是中文字符串
+    fn legacy_account(email: &str, status: &str) -> Value {
+        let mut value = serde_json::to_value(Account::new(email.to_string(), "test".to_string())).unwrap();
+        value["status"] = json!(status);
+        value
+    }
+
+    fn decoded(content: &str) -> Decoded {
+        match decode(content) {
+            Ok(decoded) => decoded,
+            Err(_) => panic!("decode failed"),
+        }
+    }
+
+    #[test]
+    fn decodes_v1_bare_array_and_migrates_status() {
+        let content = json!([legacy_account("a@example.com", "正常"), legacy_account("b@example.com", "已封禁")]).to_string();
+        let decoded = decoded(&content);
+        assert_eq!(decoded.format_version, 1);
+        assert!(decoded.rejected.is_empty());
+        let states: Vec<_> = decoded.accounts.iter().map(|a| a.status.state).collect();
+        assert_eq!(states, vec![AccountState::Active, AccountState::Banned]);
+    }
+
+    #[test]
+    fn decodes_v2_envelope() {
+        let content = json!({
+            "formatVersion": 2,
+            "appVersion": "1.0.0",
+            "accounts": [legacy_account("a@example.com", "Token已失效")],
+        }).to_string();
+        let decoded = decoded(&content);
+        assert_eq!(decoded.format_version, 2);
+        assert_eq!(decoded.accounts[0].email, "a@example.com");
+        assert_eq!(decoded.accounts[0].status.state, AccountState::TokenExpired);
+    }
+
+    #[test]
+    fn current_format_round_trips() {
+        let accounts = vec![Account::new("a@example.com".to_string(), "a".to_string())];
+        let decoded = decoded(&encode(&accounts).unwrap());
+        assert_eq!(decoded.format_version, FORMAT_VERSION);
+        assert_eq!(decoded.accounts[0].id, accounts[0].id);
+    }
+
+    #[test]
+    fn refuses_newer_format_version() {
+        let account = serde_json::to_value(Account::new("a@example.com".to_string(), "a".to_string())).unwrap();
+        let content = json!({
+            "formatVersion": FORMAT_VERSION + 1,
+            "appVersion": "99.0.0",
+            "accounts": [account],
+        }).to_string();
+        match decode(&content) {
+            Err(DecodeError::NewerVersion { format_version, app_version, accounts }) => {
+                assert_eq!(format_version, FORMAT_VERSION + 1);
+                assert_eq!(app_version.as_deref(), Some("99.0.0"));
+                assert_eq!(accounts.len(), 1);
+            }
+            _ => panic!("expected NewerVersion"),
+        }
+    }
+
+    #[test]
+    fn rejects_bad_entries_and_keeps_the_rest() {
+        let bad = json!({ "email": "broken@example.com" });
+        let content = json!([legacy_account("a@example.com", "正常"), bad.clone()]).to_string();
+        let decoded = decoded(&content);
+        assert_eq!(decoded.accounts.len(), 1);
+        assert_eq!(decoded.rejected, vec![bad]);
+    }
+
+    #[test]
+    fn unparseable_content_is_an_error() {
+        assert!(matches!(decode("{not json"), Err(DecodeError::Unparseable(_))));
+        assert!(matches!(decode("\"text\""), Err(DecodeError::Unparseable(_))));
+    }
+}
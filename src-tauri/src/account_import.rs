@@ -0,0 +1,319 @@
+// 账号导入合并
+// 先按 id、再按 email + provider 匹配已有账号，按导入模式决定如何合并
+// dry-run 时只返回每个账号的变化，不写入
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::account::Account;
+use crate::token_manager::is_placeholder_email;
+
+/// keep-newest-token 模式下随 token 一起替换的字段
+const TOKEN_FIELDS: &[&str] = &[
+    "accessToken",
+    "refreshToken",
+    "csrfToken",
+    "sessionToken",
+    "expiresAt",
+    "idToken",
+    "ssoSessionId",
+    "clientId",
+    "clientSecret",
+    "clientIdHash",
+    "region",
+    "profileArn",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportMode {
+    /// 已存在的账号保持不变
+    #[default]
+    Skip,
+    /// 用导入的账号整体覆盖
+    Overwrite,
+    /// 只在导入的 token 更新（expiresAt 更晚）时替换 token 相关字段
+    KeepNewestToken,
+    /// 只补全已有账号中为空的字段，标签取并集
+    MergeFields,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportOptions {
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    /// 加密备份的密码
+    pub password: Option<String>,
+    /// 导入 IdC 账号的 kiro-auth-token.json 时配套的 {clientIdHash}.json 内容
+    pub client_registration: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportAction {
+    New,
+    Updated,
+    Unchanged,
+    /// 与已有账号有差异但未合并（或匹配到多个不同账号）
+    Conflict,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportItem {
+    pub email: String,
+    pub provider: Option<String>,
+    pub action: ImportAction,
+    /// 匹配到的已有账号
+    pub existing_id: Option<String>,
+    /// 会被修改的字段
+    pub changed_fields: Vec<String>,
+    /// 两边都有值且不同、未被合并的字段
+    pub conflicting_fields: Vec<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub conflicts: usize,
+    pub items: Vec<ImportItem>,
+}
+
+impl ImportReport {
+    pub fn has_changes(&self) -> bool {
+        self.added > 0 || self.updated > 0
+    }
+}
+
+/// 把 incoming 合并进 accounts（原地修改），返回每个账号的处理结果
+/// 导入文件内部的重复账号会匹配到前面刚导入的那个
+pub fn merge_accounts(accounts: &mut Vec<Account>, incoming: Vec<Account>, options: &ImportOptions) -> Result<ImportReport, String> {
+    let mut report = ImportReport {
+        mode: options.mode,
+        dry_run: options.dry_run,
+        added: 0,
+        updated: 0,
+        unchanged: 0,
+        conflicts: 0,
+        items: Vec::with_capacity(incoming.len()),
+    };
+
+    for mut account in incoming {
+        // 封存字段只对原账号库的密钥有效
+        account.sealed_secrets = None;
+
+        let item = match find_match(accounts, &account) {
+            Match::None => {
+                let item = new_item(&account, ImportAction::New, None);
+                accounts.push(account);
+                item
+            }
+            Match::Ambiguous(id_match, email_match) => {
+                let mut item = new_item(&account, ImportAction::Conflict, Some(id_match));
+                item.reason = Some(format!("id 与 email+provider 分别匹配到不同账号 ({})", email_match));
+                item
+            }
+            Match::Found(idx) => merge_one(&mut accounts[idx], account, options.mode)?,
+        };
+
+        match item.action {
+            ImportAction::New => report.added += 1,
+            ImportAction::Updated => report.updated += 1,
+            ImportAction::Unchanged => report.unchanged += 1,
+            ImportAction::Conflict => report.conflicts += 1,
+        }
+        report.items.push(item);
+    }
+    Ok(report)
+}
+
+enum Match {
+    None,
+    Found(usize),
+    /// id 匹配的账号和 email+provider 匹配的账号不是同一个
+    Ambiguous(String, String),
+}
+
+fn find_match(accounts: &[Account], incoming: &Account) -> Match {
+    let by_id = accounts.iter().position(|a| a.id == incoming.id);
+    // 占位邮箱（如从 IDE token 导入、还没同步过的账号）不代表身份，不按邮箱匹配
+    let by_email = if is_placeholder_email(&incoming.email) {
+        None
+    } else {
+        accounts.iter().position(|a| {
+            a.email.eq_ignore_ascii_case(&incoming.email) && a.provider == incoming.provider
+        })
+    };
+    match (by_id, by_email) {
+        (Some(i), Some(e)) if i != e => Match::Ambiguous(accounts[i].id.clone(), accounts[e].id.clone()),
+        (Some(i), _) | (None, Some(i)) => Match::Found(i),
+        (None, None) => Match::None,
+    }
+}
+
+fn new_item(account: &Account, action: ImportAction, existing_id: Option<String>) -> ImportItem {
+    ImportItem {
+        email: account.email.clone(),
+        provider: account.provider.clone(),
+        action,
+        existing_id,
+        changed_fields: Vec::new(),
+        conflicting_fields: Vec::new(),
+        reason: None,
+    }
+}
+
+fn merge_one(existing: &mut Account, incoming: Account, mode: ImportMode) -> Result<ImportItem, String> {
+    let mut item = new_item(&incoming, ImportAction::Unchanged, Some(existing.id.clone()));
+    let current = to_object(existing)?;
+    let theirs = to_object(&incoming)?;
+    let mut merged = current.clone();
+
+    match mode {
+        ImportMode::Skip => {
+            item.conflicting_fields = differing_fields(&current, &theirs);
+        }
+        ImportMode::Overwrite => {
+            merged = theirs.clone();
+        }
+        ImportMode::KeepNewestToken => {
+            match (existing.expires_at_time(), incoming.expires_at_time()) {
+                (Some(ours), Some(new)) if new > ours => copy_fields(&mut merged, &theirs, TOKEN_FIELDS),
+                // 较旧的 token 不导入，差异是预期内的
+                (Some(_), Some(_)) => {}
+                // 本地没有过期时间，以导入的为准
+                (None, Some(_)) => copy_fields(&mut merged, &theirs, TOKEN_FIELDS),
+                (_, None) => {
+                    item.conflicting_fields = differing_fields(&current, &theirs).into_iter()
+                        .filter(|f| TOKEN_FIELDS.contains(&f.as_str()))
+                        .collect();
+                    if !item.conflicting_fields.is_empty() {
+                        item.reason = Some("导入的账号缺少 expiresAt，无法比较 token 新旧".to_string());
+                    }
+                }
+            }
+        }
+        ImportMode::MergeFields => {
+            for (key, value) in &theirs {
+                if value.is_null() {
+                    continue;
+                }
+                match merged.get_mut(key) {
+                    Some(Value::Array(ours)) if key == "tags" => {
+                        for tag in value.as_array().into_iter().flatten() {
+                            if !ours.contains(tag) {
+                                ours.push(tag.clone());
+                            }
+                        }
+                    }
+                    Some(ours) if ours.is_null() => *ours = value.clone(),
+                    Some(ours) if ours != value => item.conflicting_fields.push(key.clone()),
+                    Some(_) => {}
+                    None => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    // 保留本地 id
+    merged.insert("id".to_string(), Value::String(existing.id.clone()));
+    item.changed_fields = differing_fields(&current, &merged);
+
+    item.action = if !item.changed_fields.is_empty() {
+        ImportAction::Updated
+    } else if !item.conflicting_fields.is_empty() || item.reason.is_some() {
+        ImportAction::Conflict
+    } else {
+        ImportAction::Unchanged
+    };
+
+    if item.action == ImportAction::Updated {
+        *existing = serde_json::from_value(Value::Object(merged))
+            .map_err(|e| format!("合并账号 {} 失败: {}", incoming.email, e))?;
+    }
+    Ok(item)
+}
+
+fn to_object(account: &Account) -> Result<Map<String, Value>, String> {
+    match serde_json::to_value(account).map_err(|e| format!("序列化账号失败: {}", e))? {
+        Value::Object(map) => Ok(map),
+        _ => Err("序列化账号失败".to_string()),
+    }
+}
+
+fn copy_fields(target: &mut Map<String, Value>, source: &Map<String, Value>, fields: &[&str]) {
+    for field in fields {
+        let value = source.get(*field).cloned().unwrap_or(Value::Null);
+        target.insert(field.to_string(), value);
+    }
+}
+
+/// 值不同的字段（不含 id）
+fn differing_fields(a: &Map<String, Value>, b: &Map<String, Value>) -> Vec<String> {
+    let mut fields: Vec<String> = a.keys()
+        .chain(b.keys().filter(|k| !a.contains_key(*k)))
+        .filter(|k| k.as_str() != "id")
+        .filter(|k| a.get(*k).unwrap_or(&Value::Null) != b.get(*k).unwrap_or(&Value::Null))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(email: &str, provider: &str) -> Account {
+        let mut account = Account::new(email.to_string(), email.to_string());
+        account.provider = Some(provider.to_string());
+        account
+    }
+
+    #[test]
+    fn find_match_by_id_then_email_and_provider() {
+        let stored = vec![account("a@example.com", "Google"), account("b@example.com", "Github")];
+
+        let same_id = stored[1].clone();
+        assert!(matches!(find_match(&stored, &same_id), Match::Found(1)));
+        assert!(matches!(find_match(&stored, &account("A@example.com", "Google")), Match::Found(0)));
+        assert!(matches!(find_match(&stored, &account("a@example.com", "Github")), Match::None));
+
+        let mut conflicting = stored[1].clone();
+        conflicting.email = "a@example.com".to_string();
+        conflicting.provider = Some("Google".to_string());
+        assert!(matches!(find_match(&stored, &conflicting), Match::Ambiguous(_, _)));
+    }
+
+    #[test]
+    fn placeholder_email_never_matches_by_email() {
+        let stored = vec![account("unknown@kiro.dev", "Google")];
+        assert!(matches!(find_match(&stored, &account("unknown@kiro.dev", "Google")), Match::None));
+    }
+
+    #[test]
+    fn keep_newest_token_only_replaces_older_tokens() {
+        let mut existing = account("a@example.com", "Google");
+        existing.refresh_token = Some("old".to_string());
+        existing.expires_at = Some("2026/01/01 00:00:00".to_string());
+        let mut accounts = vec![existing];
+
+        let mut newer = account("a@example.com", "Google");
+        newer.refresh_token = Some("new".to_string());
+        newer.expires_at = Some("2026/02/01 00:00:00".to_string());
+        let options = ImportOptions { mode: ImportMode::KeepNewestToken, ..Default::default() };
+        let report = merge_accounts(&mut accounts, vec![newer], &options).unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(accounts[0].refresh_token.as_deref(), Some("new"));
+        assert_eq!(accounts.len(), 1);
+    }
+}
@@ -0,0 +1,196 @@
+// 账号查询：按标签、分组、provider、状态、订阅类型、过期时间筛选，支持排序和分页
+// 批量命令（删除、打标签、分组）也用同一个查询选定账号
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::account::{Account, AccountState};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortField {
+    /// 保持列表原有顺序（新添加的在前）
+    #[default]
+    Position,
+    AddedAt,
+    Email,
+    Label,
+    Provider,
+    Status,
+    ExpiresAt,
+    LastSyncedAt,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AccountQuery {
+    /// 必须包含全部这些标签
+    pub tags: Vec<String>,
+    pub group: Option<String>,
+    /// 只要没有分组的账号
+    pub ungrouped: bool,
+    pub provider: Option<String>,
+    /// 状态为其中任意一个
+    pub status: Vec<AccountState>,
+    /// 订阅类型/名称包含该字符串（不区分大小写），如 "PRO+"
+    pub subscription_type: Option<String>,
+    /// token 在多少分钟内过期（已过期的也算）
+    pub expires_within_minutes: Option<i64>,
+    /// email / label 包含该字符串（不区分大小写）
+    pub search: Option<String>,
+    pub sort_by: SortField,
+    pub descending: bool,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountQueryResult {
+    /// 筛选后、分页前的总数
+    pub total: usize,
+    pub accounts: Vec<Account>,
+}
+
+impl AccountQuery {
+    pub fn matches(&self, account: &Account) -> bool {
+        if !self.tags.iter().all(|t| account.tags.iter().any(|at| at.eq_ignore_ascii_case(t))) {
+            return false;
+        }
+        if let Some(group) = &self.group {
+            if account.group.as_deref() != Some(group.as_str()) {
+                return false;
+            }
+        }
+        if self.ungrouped && account.group.is_some() {
+            return false;
+        }
+        if let Some(provider) = &self.provider {
+            if !account.provider.as_deref().is_some_and(|p| p.eq_ignore_ascii_case(provider)) {
+                return false;
+            }
+        }
+        if !self.status.is_empty() && !self.status.contains(&account.status.state) {
+            return false;
+        }
+        if let Some(sub) = &self.subscription_type {
+            let sub = sub.to_lowercase();
+            if !subscription_names(account).iter().any(|n| n.to_lowercase().contains(&sub)) {
+                return false;
+            }
+        }
+        if let Some(minutes) = self.expires_within_minutes {
+            let deadline = Local::now() + chrono::Duration::minutes(minutes);
+            match account.expires_at_time() {
+                Some(t) if t <= deadline => {}
+                _ => return false,
+            }
+        }
+        if let Some(search) = &self.search {
+            let search = search.to_lowercase();
+            if !account.email.to_lowercase().contains(&search) && !account.label.to_lowercase().contains(&search) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 筛选 + 排序 + 分页
+    pub fn run(&self, accounts: &[Account]) -> AccountQueryResult {
+        let mut matched: Vec<&Account> = accounts.iter().filter(|a| self.matches(a)).collect();
+        if self.sort_by != SortField::Position {
+            // 稳定排序，相同值保持原有顺序；降序在比较时处理，缺少值的账号始终排在最后
+            matched.sort_by(|a, b| self.compare(a, b));
+        } else if self.descending {
+            matched.reverse();
+        }
+
+        let total = matched.len();
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let accounts = matched.into_iter()
+            .skip(self.offset)
+            .take(limit)
+            .cloned()
+            .collect();
+        AccountQueryResult { total, accounts }
+    }
+
+    fn compare(&self, a: &Account, b: &Account) -> Ordering {
+        let descending = self.descending;
+        let directed = |ord: Ordering| if descending { ord.reverse() } else { ord };
+        match self.sort_by {
+            SortField::Position => Ordering::Equal,
+            // 没有值的排在最后
+            SortField::AddedAt => cmp_none_last(parse_time(&a.added_at), parse_time(&b.added_at), descending),
+            SortField::Email => directed(a.email.to_lowercase().cmp(&b.email.to_lowercase())),
+            SortField::Label => directed(a.label.cmp(&b.label)),
+            SortField::Provider => cmp_none_last(a.provider.as_deref(), b.provider.as_deref(), descending),
+            SortField::Status => directed(a.status.state.as_str().cmp(b.status.state.as_str())),
+            SortField::ExpiresAt => cmp_none_last(a.expires_at_time(), b.expires_at_time(), descending),
+            SortField::LastSyncedAt => cmp_none_last(
+                a.last_synced_at.as_deref().and_then(parse_time),
+                b.last_synced_at.as_deref().and_then(parse_time),
+                descending,
+            ),
+        }
+    }
+}
+
+/// 只有两边都有值时才按方向比较，None 不论升降序都排在最后
+fn cmp_none_last<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// usage 响应里的订阅类型和名称
+fn subscription_names(account: &Account) -> Vec<&str> {
+    let info = account.usage_data.as_ref().and_then(|u| u.get("subscriptionInfo"));
+    ["type", "subscriptionTitle"].iter()
+        .filter_map(|key| info.and_then(|i| i.get(key)).and_then(|v| v.as_str()))
+        .collect()
+}
+
+/// 账号里的时间有两种格式：RFC 3339 和 "%Y/%m/%d %H:%M:%S"（本地时间）
+pub fn parse_time(value: &str) -> Option<DateTime<Local>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Some(t.with_timezone(&Local));
+    }
+    chrono::NaiveDateTime::parse_from_str(value, "%Y/%m/%d %H:%M:%S")
+        .ok()
+        .and_then(|t| t.and_local_timezone(Local).single())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(label: &str, expires_at: Option<&str>) -> Account {
+        let mut account = Account::new(format!("{}@example.com", label), label.to_string());
+        account.expires_at = expires_at.map(str::to_string);
+        account
+    }
+
+    fn labels(query: &AccountQuery, accounts: &[Account]) -> Vec<String> {
+        query.run(accounts).accounts.into_iter().map(|a| a.label).collect()
+    }
+
+    #[test]
+    fn missing_values_sort_last_in_both_directions() {
+        let accounts = vec![
+            account("none", None),
+            account("late", Some("2030/01/02 00:00:00")),
+            account("early", Some("2030/01/01 00:00:00")),
+        ];
+        let mut query = AccountQuery { sort_by: SortField::ExpiresAt, ..Default::default() };
+        assert_eq!(labels(&query, &accounts), vec!["early", "late", "none"]);
+
+        query.descending = true;
+        assert_eq!(labels(&query, &accounts), vec!["late", "early", "none"]);
+    }
+}
@@ -3,6 +3,9 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
 
+use crate::error::{usage_error, AppError, AppResult};
+use tracing::debug;
+
 // ============================================================
 // User 和 AuthState
 // ============================================================
@@ -38,9 +41,7 @@ impl AuthState {
 // API 常量
 // ============================================================
 
-pub const DESKTOP_AUTH_API: &str = "https://prod.us-east-1.auth.desktop.kiro.dev";
-const DESKTOP_USAGE_API: &str = "https://codewhisperer.us-east-1.amazonaws.com";
-const PROFILE_ARN: &str = "arn:aws:codewhisperer:us-east-1:699475941385:profile/EHGA3GRVQMUK";
+pub const PROFILE_ARN: &str = "arn:aws:codewhisperer:us-east-1:699475941385:profile/EHGA3GRVQMUK";
 
 // ============================================================
 // 桌面端 API 响应结构
@@ -124,136 +125,78 @@ pub struct BonusInfo {
 // ============================================================
 
 /// 使用桌面端 API 刷新 Token（只需要 RefreshToken）
-pub async fn refresh_token_desktop(refresh_token: &str) -> Result<DesktopRefreshResponse, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create client: {}", e))?;
+pub async fn refresh_token_desktop(refresh_token: &str) -> AppResult<DesktopRefreshResponse> {
+    let client = crate::http_client::client(std::time::Duration::from_secs(30))?;
     
     let body = serde_json::json!({
         "refreshToken": refresh_token
     });
     
-    // 重试机制
-    let mut last_error = String::new();
-    for attempt in 0..3 {
-        if attempt > 0 {
-            tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
-        }
-        
-        match client
-            .post(format!("{}/refreshToken", DESKTOP_AUTH_API))
+    let url = format!("{}/refreshToken", crate::endpoints::desktop_auth());
+    let response = crate::retry::send_once(|_| {
+        client
+            .post(&url)
             .header("Content-Type", "application/json")
             .header("Accept", "application/json")
             .json(&body)
-            .send()
-            .await
-        {
-            Ok(response) => {
-                let status = response.status();
-                let text = response.text().await.unwrap_or_default();
-                
-                // println!("\n[Desktop] RefreshToken Response:");
-                // println!("Status: {}", status);
-                // // 格式化打印 JSON
-                // match serde_json::from_str::<serde_json::Value>(&text) {
-                //     Ok(json) => {
-                //         match serde_json::to_string_pretty(&json) {
-                //             Ok(pretty) => println!("{}", pretty),
-                //             Err(_) => println!("{}", text),
-                //         }
-                //     }
-                //     Err(_) => println!("{}", text),
-                // }
-                
-                if !status.is_success() {
-                    if status.as_u16() == 401 {
-                        return Err("RefreshToken 已过期或无效".to_string());
-                    }
-                    return Err(format!("RefreshToken failed ({})", status));
-                }
-                
-                return serde_json::from_str(&text)
-                    .map_err(|e| format!("Parse failed: {}", e));
-            }
-            Err(e) => {
-                last_error = format!("网络错误: {}", e);
-                continue;
-            }
+    })
+    .await
+    .map_err(|e| AppError::network("网络错误", e))?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+
+    if !status.is_success() {
+        if status.as_u16() == 401 {
+            return Err(AppError::AuthExpired("RefreshToken 已过期或无效".to_string()));
         }
+        return Err(AppError::from_response("RefreshToken", status, &text));
     }
-    
-    Err(last_error)
+
+    serde_json::from_str(&text)
+        .map_err(|e| AppError::Parse(format!("Parse failed: {}", e)))
 }
 
-/// 使用桌面端 API 获取配额和用户信息
-pub async fn get_usage_limits_desktop(access_token: &str) -> Result<DesktopUsageResponse, String> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("Failed to create client: {}", e))?;
+/// 使用桌面端 API 获取配额和用户信息，按 profile ARN 所在区域请求（没有时用默认 profile）
+pub async fn get_usage_limits_desktop(access_token: &str, profile_arn: Option<&str>) -> AppResult<DesktopUsageResponse> {
+    let client = crate::http_client::client(std::time::Duration::from_secs(30))?;
+    let profile_arn = profile_arn.unwrap_or(PROFILE_ARN);
     
     let url = format!(
         "{}/getUsageLimits?isEmailRequired=true&origin=AI_EDITOR&profileArn={}",
-        DESKTOP_USAGE_API,
-        urlencoding::encode(PROFILE_ARN)
+        crate::endpoints::codewhisperer(Some(profile_arn)),
+        urlencoding::encode(profile_arn)
     );
 
-    // println!("\n[7] GET USAGE LIMITS REQUEST");
-    // println!("URL: {}", url);
-    // println!("Token: {}", access_token);
-    
-    // 重试机制
-    let mut last_error = String::new();
-    for attempt in 0..3 {
-        if attempt > 0 {
-            tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
-        }
-        
-        match client
+    let response = crate::retry::send_idempotent(|_| {
+        client
             .get(&url)
             .header("Authorization", format!("Bearer {}", access_token))
             .header("Accept", "application/json")
-            .send()
-            .await
-        {
-            Ok(response) => {
-                let status = response.status();
-                let text = response.text().await.unwrap_or_default();
-                
-                println!("\n[Social] GET USAGE LIMITS RESPONSE");
-                println!("Status: {}", status);
-                // 格式化打印 JSON
-                match serde_json::from_str::<serde_json::Value>(&text) {
-                    Ok(json) => {
-                        match serde_json::to_string_pretty(&json) {
-                            Ok(pretty) => println!("{}", pretty),
-                            Err(_) => println!("{}", text),
-                        }
-                    }
-                    Err(_) => println!("{}", text),
-                }
-                println!();
-                
-                if !status.is_success() {
-                    // 解析错误响应，提取 reason 字段
-                    if let Ok(error_json) = serde_json::from_str::<serde_json::Value>(&text) {
-                        if let Some(reason) = error_json.get("reason").and_then(|r| r.as_str()) {
-                            return Err(format!("BANNED:{}", reason));
-                        }
-                    }
-                    return Err(format!("GetUsageLimits failed ({})", status));
-                }
-                
-                return serde_json::from_str(&text)
-                    .map_err(|e| format!("Parse failed: {}", e));
-            }
-            Err(e) => {
-                last_error = format!("网络错误: {}", e);
-                continue;
+    })
+    .await
+    .map_err(|e| AppError::network("网络错误", e))?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+
+    debug!("GetUsageLimits status: {}", status);
+    // 格式化打印 JSON
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(json) => {
+            match serde_json::to_string_pretty(&json) {
+                Ok(pretty) => debug!("{}", pretty),
+                Err(_) => debug!("{}", text),
             }
         }
+        Err(_) => debug!("{}", text),
     }
-    
-    Err(last_error)
+
+    if !status.is_success() {
+        // 响应中的 reason 字段表示账号被暂停
+        return Err(usage_error(status, &text));
+    }
+
+    serde_json::from_str(&text)
+        .map_err(|e| AppError::Parse(format!("Parse failed: {}", e)))
 }
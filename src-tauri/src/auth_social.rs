@@ -1,4 +1,5 @@
-use crate::auth::{DesktopRefreshResponse, DESKTOP_AUTH_API};
+use crate::auth::DesktopRefreshResponse;
+use crate::error::{AppError, AppResult};
 
 /// 生成PKCE code_verifier（32字节，base64url）
 pub fn generate_code_verifier_social() -> String {
@@ -28,8 +29,8 @@ pub async fn exchange_social_code_for_token(
     code_verifier: &str,
     redirect_uri: &str,
     machineid: &str,
-) -> Result<DesktopRefreshResponse, String> {
-    let client = reqwest::Client::new();
+) -> AppResult<DesktopRefreshResponse> {
+    let client = crate::http_client::client(std::time::Duration::from_secs(30))?;
     let body = serde_json::json!({
         "code": code,
         "code_verifier": code_verifier,
@@ -39,25 +40,26 @@ pub async fn exchange_social_code_for_token(
     let kiro_ide_version = "0.6.18";
     let user_agent = format!("KiroIDE-{}-{}", kiro_ide_version, machineid);
 
-    let response = client
-        .post(format!("{}/oauth/token", DESKTOP_AUTH_API))
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .header("user-agent", user_agent)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("OAuth token request failed: {}", e))?;
+    let response = crate::retry::send_once(|_| {
+        client
+            .post(format!("{}/oauth/token", crate::endpoints::desktop_auth()))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .header("user-agent", &user_agent)
+            .json(&body)
+    })
+    .await
+    .map_err(|e| AppError::network("OAuth token request failed", e))?;
 
     let status = response.status();
     let body = response.text().await.unwrap_or_default();
 
     if !status.is_success() {
-        return Err(format!("OAuth token exchange failed ({}): {}", status, body));
+        return Err(AppError::from_response("OAuth token exchange", status, &body));
     }
 
     let token_resp: DesktopRefreshResponse = serde_json::from_str(&body)
-        .map_err(|e| format!("Failed to parse OAuth token response: {}", e))?;
+        .map_err(|e| AppError::Parse(format!("Failed to parse OAuth token response: {}", e)))?;
 
     Ok(token_resp)
 }
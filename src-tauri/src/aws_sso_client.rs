@@ -5,10 +5,15 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+use crate::error::{AppError, AppResult};
+use crate::{endpoints, http_client, retry};
+use tracing::info;
+
 /// AWS SSO OIDC 客户端
 pub struct AWSSSOClient {
     region: String,
     base_url: String,
+    portal_url: String,
     client: Client,
 }
 
@@ -51,18 +56,17 @@ pub struct TokenResponse {
 }
 
 impl AWSSSOClient {
-    pub fn new(region: &str) -> Self {
-        let base_url = format!("https://oidc.{}.amazonaws.com", region);
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
-
-        Self {
+    pub fn new(region: &str) -> AppResult<Self> {
+        let base_url = endpoints::oidc(region);
+        let portal_url = endpoints::sso_portal(region);
+        let client = http_client::client(Duration::from_secs(30))?;
+
+        Ok(Self {
             region: region.to_string(),
             base_url,
+            portal_url,
             client,
-        }
+        })
     }
 
     /// 刷新 Token（Builder ID 账号刷新时使用）
@@ -71,7 +75,7 @@ impl AWSSSOClient {
         client_id: &str,
         client_secret: &str,
         refresh_token: &str,
-    ) -> Result<TokenResponse, String> {
+    ) -> AppResult<TokenResponse> {
         let url = format!("{}/token", self.base_url);
 
         let body = serde_json::json!({
@@ -81,34 +85,64 @@ impl AWSSSOClient {
             "refreshToken": refresh_token
         });
 
-        println!("\n[AWS SSO] Refresh Token");
+        info!("Refresh Token");
 
-        let resp = self.client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Token refresh request failed: {}", e))?;
+        let resp = retry::send_once(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .json(&body)
+        })
+        .await
+        .map_err(|e| AppError::network("Token refresh request failed", e))?;
 
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
 
         if !status.is_success() {
             if status.as_u16() == 401 {
-                return Err("RefreshToken 已过期或无效".to_string());
+                return Err(AppError::AuthExpired("RefreshToken 已过期或无效".to_string()));
+            }
+            // 客户端注册 (clientSecret) 过期后 OIDC 返回 invalid_client
+            if text.contains("invalid_client") {
+                return Err(AppError::AuthExpired("客户端注册已过期或无效，请重新注册".to_string()));
             }
-            return Err(format!("Token refresh failed ({}): {}", status, text));
+            return Err(AppError::from_response("Token refresh", status, &text));
         }
 
-        println!("Token refreshed successfully");
+        info!("Token refreshed successfully");
 
         serde_json::from_str(&text)
-            .map_err(|e| format!("Failed to parse token response: {}", e))
+            .map_err(|e| AppError::Parse(format!("Failed to parse token response: {}", e)))
+    }
+
+    /// 注销 SSO 会话 (sso:Logout)，该会话签发的 access token 和 refresh token 一并失效
+    pub async fn logout(&self, access_token: &str) -> AppResult<()> {
+        let url = format!("{}/logout", self.portal_url);
+
+        info!("Logout (region: {})", self.region);
+
+        let resp = retry::send_idempotent(|_| {
+            self.client
+                .post(&url)
+                .header("x-amz-sso_bearer_token", access_token)
+        })
+        .await
+        .map_err(|e| AppError::network("Logout request failed", e))?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let text = resp.text().await.unwrap_or_default();
+        if status.as_u16() == 401 {
+            return Err(AppError::AuthExpired("AccessToken 已过期或无效".to_string()));
+        }
+        Err(AppError::from_response("Logout", status, &text))
     }
 
     /// 注册支持设备授权的客户端
-    pub async fn register_device_client(&self, issuer_url: &str) -> Result<ClientRegistration, String> {
+    pub async fn register_device_client(&self, issuer_url: &str) -> AppResult<ClientRegistration> {
         let url = format!("{}/client/register", self.base_url);
         
         let body = serde_json::json!({
@@ -125,26 +159,27 @@ impl AWSSSOClient {
             "issuerUrl": issuer_url
         });
 
-        println!("\n[AWS SSO] Register Device Client (region: {})", self.region);
+        info!("Register Device Client (region: {})", self.region);
 
-        let resp = self.client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Device client registration failed: {}", e))?;
+        let resp = retry::send_once(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .json(&body)
+        })
+        .await
+        .map_err(|e| AppError::network("Device client registration failed", e))?;
 
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
 
         if !status.is_success() {
-            return Err(format!("Device client registration failed ({}): {}", status, text));
+            return Err(AppError::from_response("Device client registration", status, &text));
         }
 
-        println!("Device client registered successfully");
+        info!("Device client registered successfully");
         serde_json::from_str(&text)
-            .map_err(|e| format!("Failed to parse client registration: {}", e))
+            .map_err(|e| AppError::Parse(format!("Failed to parse client registration: {}", e)))
     }
 
     /// 发起设备授权请求
@@ -153,7 +188,7 @@ impl AWSSSOClient {
         client_id: &str,
         client_secret: &str,
         start_url: &str,
-    ) -> Result<DeviceAuthorizationResponse, String> {
+    ) -> AppResult<DeviceAuthorizationResponse> {
         let url = format!("{}/device_authorization", self.base_url);
 
         let body = serde_json::json!({
@@ -162,26 +197,27 @@ impl AWSSSOClient {
             "startUrl": start_url
         });
 
-        println!("\n[AWS SSO] Start Device Authorization");
+        info!("Start Device Authorization");
 
-        let resp = self.client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Device authorization failed: {}", e))?;
+        let resp = retry::send_once(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .json(&body)
+        })
+        .await
+        .map_err(|e| AppError::network("Device authorization failed", e))?;
 
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
 
         if !status.is_success() {
-            return Err(format!("Device authorization failed ({}): {}", status, text));
+            return Err(AppError::from_response("Device authorization", status, &text));
         }
 
-        println!("Device authorization started");
+        info!("Device authorization started");
         serde_json::from_str(&text)
-            .map_err(|e| format!("Failed to parse device authorization: {}", e))
+            .map_err(|e| AppError::Parse(format!("Failed to parse device authorization: {}", e)))
     }
 
     /// 轮询设备授权状态获取 Token
@@ -190,7 +226,7 @@ impl AWSSSOClient {
         client_id: &str,
         client_secret: &str,
         device_code: &str,
-    ) -> Result<DevicePollResult, String> {
+    ) -> AppResult<DevicePollResult> {
         let url = format!("{}/token", self.base_url);
 
         let body = serde_json::json!({
@@ -200,20 +236,21 @@ impl AWSSSOClient {
             "deviceCode": device_code
         });
 
-        let resp = self.client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Device token poll failed: {}", e))?;
+        let resp = retry::send_once(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/json")
+                .json(&body)
+        })
+        .await
+        .map_err(|e| AppError::network("Device token poll failed", e))?;
 
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
 
         if status.is_success() {
             let token: TokenResponse = serde_json::from_str(&text)
-                .map_err(|e| format!("Failed to parse token: {}", e))?;
+                .map_err(|e| AppError::Parse(format!("Failed to parse token: {}", e)))?;
             return Ok(DevicePollResult::Success(token));
         }
 
@@ -224,10 +261,10 @@ impl AWSSSOClient {
                 "slow_down" => Ok(DevicePollResult::SlowDown),
                 "expired_token" => Ok(DevicePollResult::Expired),
                 "access_denied" => Ok(DevicePollResult::Denied),
-                _ => Err(format!("Device auth error: {}", err.error)),
+                _ => Err(AppError::from_response("Device token poll", status, &text)),
             }
         } else {
-            Err(format!("Device token poll failed ({}): {}", status, text))
+            Err(AppError::from_response("Device token poll", status, &text))
         }
     }
 }
@@ -2,6 +2,7 @@
 
 use crate::commands::app_settings_cmd::get_browser_path;
 use serde::Serialize;
+use tracing::info;
 
 /// 打开浏览器访问指定 URL
 /// 如果用户配置了自定义浏览器路径，则使用自定义浏览器
@@ -111,7 +112,7 @@ fn open_with_custom_browser(browser_path: &str, url: &str) -> Result<(), String>
     };
     args.push(url);
 
-    println!("[Browser] Opening with custom browser: {} {:?}", exe_path, args);
+    info!("Opening with custom browser: {} {:?}", exe_path, args);
 
     std::process::Command::new(exe_path)
         .args(&args)
@@ -123,7 +124,7 @@ fn open_with_custom_browser(browser_path: &str, url: &str) -> Result<(), String>
 
 /// 使用系统默认浏览器打开 URL
 fn open_with_default_browser(url: &str) -> Result<(), String> {
-    println!("[Browser] Opening with default browser: {}", url);
+    info!("Opening with default browser: {}", url);
 
     #[cfg(target_os = "windows")]
     {
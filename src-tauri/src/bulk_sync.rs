@@ -0,0 +1,194 @@
+// 批量同步账号（刷新 token + usage）
+// 同时同步的账号数有上限，每个账号的进度通过 "sync-progress" 事件通知前端，结束后返回汇总
+// 取消只影响还在排队的账号：已发出的刷新请求可能已经轮换了 refresh token，必须等结果写回，否则新 token 会丢失
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Semaphore;
+
+use crate::account::AccountState;
+use crate::state::AppState;
+use crate::token_manager::TokenManager;
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncOutcome {
+    Succeeded,
+    Failed,
+    /// refresh token 失效或客户端注册过期，需要重新登录
+    NeedsReauth,
+    /// 开始前被取消
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncPhase {
+    Started,
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProgressEvent {
+    pub job_id: String,
+    pub account_id: String,
+    pub email: String,
+    pub phase: SyncPhase,
+    /// phase 为 finished 时才有
+    pub outcome: Option<SyncOutcome>,
+    pub error: Option<String>,
+    /// 已结束的账号数（含本账号）
+    pub completed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    pub account_id: String,
+    pub email: String,
+    pub outcome: SyncOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSummary {
+    pub job_id: String,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub needs_reauth: usize,
+    pub cancelled: usize,
+    pub results: Vec<SyncResult>,
+}
+
+/// 进行中的批次，由 main.rs 注册为 Tauri 托管状态
+pub struct SyncJobs {
+    cancel_flags: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl SyncJobs {
+    pub fn new() -> Self {
+        Self { cancel_flags: Mutex::new(HashMap::new()) }
+    }
+
+    fn register(&self, job_id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.cancel_flags.lock().unwrap().insert(job_id.to_string(), flag.clone());
+        flag
+    }
+
+    fn finish(&self, job_id: &str) {
+        self.cancel_flags.lock().unwrap().remove(job_id);
+    }
+
+    /// 取消指定批次，不指定时取消全部；返回取消的批次数
+    pub fn cancel(&self, job_id: Option<&str>) -> usize {
+        let flags = self.cancel_flags.lock().unwrap();
+        let mut cancelled = 0;
+        for (id, flag) in flags.iter() {
+            if job_id.is_none_or(|j| j == id) {
+                flag.store(true, Ordering::SeqCst);
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+}
+
+/// 同步指定账号，等全部结束（或取消）后返回汇总
+pub async fn run(app: AppHandle, job_id: String, ids: Vec<String>, concurrency: usize) -> SyncSummary {
+    let accounts: Vec<(String, String)> = {
+        let state = app.state::<AppState>();
+        let store = state.store.lock().unwrap();
+        ids.iter()
+            .filter_map(|id| store.accounts.iter().find(|a| &a.id == id))
+            .map(|a| (a.id.clone(), a.email.clone()))
+            .collect()
+    };
+    let total = accounts.len();
+    info!("Job {}: syncing {} account(s), concurrency {}", job_id, total, concurrency);
+
+    let jobs = app.state::<SyncJobs>();
+    let cancelled = jobs.register(&job_id);
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let completed = Arc::new(Mutex::new(0usize));
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (account_id, email) in accounts {
+        let app = app.clone();
+        let job_id = job_id.clone();
+        let semaphore = semaphore.clone();
+        let cancelled = cancelled.clone();
+        let completed = completed.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok();
+            let mut event = SyncProgressEvent {
+                job_id,
+                account_id: account_id.clone(),
+                email: email.clone(),
+                phase: SyncPhase::Started,
+                outcome: None,
+                error: None,
+                completed: 0,
+                total,
+            };
+
+            let (outcome, error) = if cancelled.load(Ordering::SeqCst) {
+                (SyncOutcome::Cancelled, None)
+            } else {
+                let _ = app.emit("sync-progress", event.clone());
+                let state = app.state::<AppState>();
+                match TokenManager::sync_by_id(&state.store, &account_id).await {
+                    Ok(account) if account.status.state == AccountState::NeedsReauth => {
+                        (SyncOutcome::NeedsReauth, account.status.reason)
+                    }
+                    // token 已刷新，但账号被封禁或 usage 查询失败，都不算同步成功
+                    Ok(account) if account.status.state == AccountState::Banned || account.last_error.is_some() => {
+                        let error = account.last_error.map(|e| e.message).or(account.status.reason);
+                        (SyncOutcome::Failed, error)
+                    }
+                    Ok(_) => (SyncOutcome::Succeeded, None),
+                    Err(e) if e.is_auth() => (SyncOutcome::NeedsReauth, Some(e.to_string())),
+                    Err(e) => (SyncOutcome::Failed, Some(e.to_string())),
+                }
+            };
+
+            event.phase = SyncPhase::Finished;
+            event.outcome = Some(outcome);
+            event.error = error.clone();
+            event.completed = {
+                let mut completed = completed.lock().unwrap();
+                *completed += 1;
+                *completed
+            };
+            let _ = app.emit("sync-progress", event);
+            SyncResult { account_id, email, outcome, error }
+        });
+    }
+
+    let mut summary = SyncSummary { job_id: job_id.clone(), total, ..Default::default() };
+    while let Some(joined) = tasks.join_next().await {
+        let Ok(result) = joined else { continue };
+        match result.outcome {
+            SyncOutcome::Succeeded => summary.succeeded += 1,
+            SyncOutcome::Failed => summary.failed += 1,
+            SyncOutcome::NeedsReauth => summary.needs_reauth += 1,
+            SyncOutcome::Cancelled => summary.cancelled += 1,
+        }
+        summary.results.push(result);
+    }
+    jobs.finish(&job_id);
+
+    info!(
+        "Job {} done: {} succeeded, {} failed, {} needs reauth, {} cancelled",
+        job_id, summary.succeeded, summary.failed, summary.needs_reauth, summary.cancelled
+    );
+    summary
+}
@@ -6,7 +6,9 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use uuid::Uuid;
 
-const CODEWHISPERER_API: &str = "https://codewhisperer.us-east-1.amazonaws.com";
+use crate::error::{usage_error, AppResult};
+use crate::{endpoints, http_client, retry};
+use tracing::{debug, warn};
 
 /// CodeWhisperer 限额响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,19 +95,24 @@ pub struct BonusInfo {
 pub struct CodeWhispererClient {
     client: Client,
     machine_id: String,
+    endpoint: String,
 }
 
 impl CodeWhispererClient {
-    pub fn new(machine_id: &str) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()
-            .expect("Failed to create HTTP client");
+    pub fn new(machine_id: &str) -> AppResult<Self> {
+        let client = http_client::client(Duration::from_secs(30))?;
 
-        Self {
+        Ok(Self {
             client,
             machine_id: machine_id.to_string(),
-        }
+            endpoint: endpoints::codewhisperer(None),
+        })
+    }
+
+    /// 使用 profile ARN 所在区域的接口
+    pub fn for_profile(mut self, profile_arn: Option<&str>) -> Self {
+        self.endpoint = endpoints::codewhisperer(profile_arn);
+        self
     }
 
     /// 生成 invocation ID
@@ -114,10 +121,10 @@ impl CodeWhispererClient {
     }
 
     /// 获取限额信息 (用于 IdC/BuilderId token)
-    pub async fn get_usage_limits(&self, access_token: &str) -> Result<CodeWhispererUsageResponse, String> {
+    pub async fn get_usage_limits(&self, access_token: &str) -> AppResult<CodeWhispererUsageResponse> {
         let url = format!(
             "{}/getUsageLimits?isEmailRequired=true&origin=AI_EDITOR&resourceType=AGENTIC_REQUEST",
-            CODEWHISPERER_API
+            self.endpoint
         );
 
         let kiro_version = "0.6.18";
@@ -127,46 +134,40 @@ impl CodeWhispererClient {
             kiro_version, self.machine_id
         );
 
-        println!("\n[CodeWhisperer] GET USAGE LIMITS");
-        println!("URL: {}", url);
-
-        let response = self.client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", access_token))
-            .header("x-amz-user-agent", &x_amz_user_agent)
-            .header("user-agent", &user_agent)
-            .header("amz-sdk-invocation-id", Self::generate_invocation_id())
-            .header("amz-sdk-request", "attempt=1; max=1")
-            .header("Connection", "close")
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+        debug!("GetUsageLimits: {}", url);
+
+        // 同一次调用的重试共用 invocation id，amz-sdk-request 带上实际的尝试次数
+        let invocation_id = Self::generate_invocation_id();
+        let response = retry::send_idempotent(|attempt| {
+            self.client
+                .get(&url)
+                .header("Authorization", format!("Bearer {}", access_token))
+                .header("x-amz-user-agent", &x_amz_user_agent)
+                .header("user-agent", &user_agent)
+                .header("amz-sdk-invocation-id", &invocation_id)
+                .header("amz-sdk-request", attempt.sdk_request_header())
+                .header("Connection", "close")
+        })
+        .await?;
 
         let status = response.status();
         let text = response.text().await.unwrap_or_default();
 
-        println!("Status: {}", status);
+        debug!("GetUsageLimits status: {}", status);
 
         if !status.is_success() {
-            println!("Error: {}", text);
-            // 解析错误响应，提取 reason 字段
-            if let Ok(error_json) = serde_json::from_str::<serde_json::Value>(&text) {
-                if let Some(reason) = error_json.get("reason").and_then(|r| r.as_str()) {
-                    // 返回特殊格式: "BANNED:REASON" 便于上层识别
-                    return Err(format!("BANNED:{}", reason));
-                }
-            }
-            return Err(format!("GetUsageLimits failed ({}): {}", status, text));
+            warn!("GetUsageLimits failed ({}): {}", status, text);
+            // 响应中的 reason 字段表示账号被暂停
+            return Err(usage_error(status, &text));
         }
 
         // 打印响应
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(&text) {
             if let Ok(pretty) = serde_json::to_string_pretty(&json) {
-                println!("{}", pretty);
+                debug!("{}", pretty);
             }
         }
 
-        serde_json::from_str(&text)
-            .map_err(|e| format!("Parse failed: {}", e))
+        Ok(serde_json::from_str(&text)?)
     }
 }
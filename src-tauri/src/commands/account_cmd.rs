@@ -2,12 +2,21 @@
 
 use tauri::State;
 use crate::state::AppState;
-use crate::account::Account;
-use crate::auth::{User, refresh_token_desktop, get_usage_limits_desktop};
+use crate::account::{Account, AccountFacets, AccountStatus, StorageInfo};
+use crate::account_export::{self, ExportFile, ExportProfile};
+use crate::account_import::{ImportOptions, ImportReport};
+use crate::account_query::{AccountQuery, AccountQueryResult};
+use crate::account_backup::AccountBackup;
+use crate::auth::{User, get_usage_limits_desktop};
 use crate::codewhisperer_client::CodeWhispererClient;
-use crate::providers::{AuthProvider, SocialProvider, IdcProvider, RefreshMetadata};
+use crate::error::{AppError, AppResult};
+use crate::jwt::{self, TokenClaims};
+use crate::providers::AuthMethod;
+use crate::providers::web_oauth::KiroWebPortalClient;
+use crate::token_manager::TokenManager;
 use crate::kiro::get_machine_id;
 use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerifyAccountResponse {
@@ -25,161 +34,151 @@ pub struct VerifyAccountResponse {
 
 #[tauri::command]
 pub fn get_accounts(state: State<AppState>) -> Vec<Account> {
-    state.store.lock().unwrap().get_all()
+    let mut store = state.store.lock().unwrap();
+    // 其他实例修改过账号时先合并
+    if let Err(e) = store.reload_if_changed() {
+        warn!("Failed to reload accounts: {}", e);
+    }
+    store.get_all()
+}
+
+/// 账号详情：账号本身以及 id_token / access token 解码出的 claims（token 不是 JWT 时为空）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountDetail {
+    pub account: Account,
+    pub id_token_claims: Option<TokenClaims>,
+    pub access_token_claims: Option<TokenClaims>,
 }
 
 #[tauri::command]
-pub fn delete_account(state: State<AppState>, id: String) -> bool {
+pub fn get_account_detail(state: State<AppState>, id: String) -> AppResult<AccountDetail> {
+    let store = state.store.lock().unwrap();
+    let account = store.accounts.iter().find(|a| a.id == id).cloned().ok_or_else(|| AppError::not_found("Account not found"))?;
+    Ok(AccountDetail {
+        id_token_claims: account.id_token.as_deref().and_then(jwt::decode_claims),
+        access_token_claims: account.access_token.as_deref().and_then(jwt::decode_claims),
+        account,
+    })
+}
+
+#[tauri::command]
+pub fn delete_account(state: State<AppState>, id: String) -> AppResult<bool> {
     state.store.lock().unwrap().delete(&id)
 }
 
+/// 批量删除：按 id 列表或查询条件
 #[tauri::command]
-pub fn delete_accounts(state: State<AppState>, ids: Vec<String>) -> usize {
-    state.store.lock().unwrap().delete_many(&ids)
+pub fn delete_accounts(state: State<AppState>, ids: Option<Vec<String>>, query: Option<AccountQuery>) -> AppResult<usize> {
+    let mut store = state.store.lock().unwrap();
+    let ids = store.resolve_ids(ids, query.as_ref())?;
+    store.delete_many(&ids)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignOutResult {
+    pub account_id: String,
+    pub email: String,
+    /// 服务端会话已注销（或本来就已失效）
+    pub signed_out: bool,
+    pub deleted: bool,
+    pub error: Option<AppError>,
 }
 
+/// 注销并删除：先在服务端注销会话使 refresh token 失效，成功后再删除本地记录
+/// 注销失败的账号保留，除非 force 为 true
 #[tauri::command]
-pub async fn sync_account(state: State<'_, AppState>, id: String) -> Result<Account, String> {
-    let account = {
+pub async fn sign_out_and_delete_accounts(
+    state: State<'_, AppState>,
+    ids: Option<Vec<String>>,
+    query: Option<AccountQuery>,
+    force: Option<bool>,
+) -> AppResult<Vec<SignOutResult>> {
+    let accounts: Vec<Account> = {
         let store = state.store.lock().unwrap();
-        store.accounts.iter().find(|a| a.id == id).cloned()
-    }.ok_or("Account not found")?;
-
-    let provider_str = account.provider.as_deref().unwrap_or("Google");
-    let refresh_token_str = account.refresh_token.as_ref().ok_or("No refresh token")?;
-    
-    println!("[sync_account] Refreshing {} account", provider_str);
-    
-    // 根据 provider 选择刷新接口
-    // 注意：Web OAuth 的 refresh_token 也是 aor 开头的 RefreshToken Cookie，可以用 Desktop API
-    let (new_access_token, new_refresh_token, expires_in, new_profile_arn, new_id_token, new_sso_session_id) = 
-        if provider_str == "BuilderId" {
-            // BuilderId -> AWS OIDC
-            let metadata = RefreshMetadata {
-                client_id: account.client_id.clone(),
-                client_secret: account.client_secret.clone(),
-                region: account.region.clone(),
-                ..Default::default()
-            };
-            let idc_provider = IdcProvider::new("BuilderId", metadata.region.as_deref().unwrap_or("us-east-1"), None);
-            let auth_result = idc_provider.refresh_token(refresh_token_str, metadata).await?;
-            (auth_result.access_token, Some(auth_result.refresh_token), auth_result.expires_in, None, auth_result.id_token, auth_result.sso_session_id)
-        } else {
-            // Google/Github (Desktop OAuth 或 Web OAuth) -> Desktop API
-            // Web OAuth 的 refresh_token 是 RefreshToken Cookie (aor开头)，跟 Desktop OAuth 相同
-            let metadata = RefreshMetadata {
-                profile_arn: account.profile_arn.clone(),
-                ..Default::default()
-            };
-            let social_provider = SocialProvider::new(provider_str);
-            let auth_result = social_provider.refresh_token(refresh_token_str, metadata).await?;
-            (auth_result.access_token, Some(auth_result.refresh_token), auth_result.expires_in, auth_result.profile_arn, None, None)
-        };
-    
-    // 获取 usage 数据
-    let (usage_data, is_banned): (serde_json::Value, bool) = if provider_str == "BuilderId" {
-        let machine_id = get_machine_id();
-        let cw_client = CodeWhispererClient::new(&machine_id);
-        let usage_call = cw_client.get_usage_limits(&new_access_token).await;
-        let (usage, banned) = match &usage_call {
-            Ok(u) => (Some(u.clone()), false),
-            Err(e) if e.starts_with("BANNED:") => (None, true),
-            Err(_) => (None, false),
-        };
-        (serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null), banned)
-    } else {
-        let usage_call = get_usage_limits_desktop(&new_access_token).await;
-        let (usage, banned) = match &usage_call {
-            Ok(u) => (Some(u.clone()), false),
-            Err(e) if e.starts_with("BANNED:") => (None, true),
-            Err(_) => (None, false),
-        };
-        (serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null), banned)
+        let ids = store.resolve_ids(ids, query.as_ref())?;
+        store.accounts.iter().filter(|a| ids.contains(&a.id)).cloned().collect()
     };
+    let force = force.unwrap_or(false);
 
-    let expires_at = chrono::Local::now() + chrono::Duration::seconds(expires_in);
-    let expires_at_str = expires_at.format("%Y/%m/%d %H:%M:%S").to_string();
+    let mut results = Vec::new();
+    for account in accounts {
+        // 没有 refresh token 的账号没有可注销的会话
+        let outcome = match account.refresh_token {
+            Some(_) => TokenManager::sign_out(&account).await,
+            None => Ok(()),
+        };
+        let signed_out = outcome.is_ok();
+        results.push(SignOutResult {
+            account_id: account.id,
+            email: account.email,
+            signed_out,
+            deleted: signed_out || force,
+            error: outcome.err(),
+        });
+    }
 
-    // 更新账号
-    let mut store = state.store.lock().unwrap();
-    if let Some(a) = store.accounts.iter_mut().find(|a| a.id == id) {
-        a.access_token = Some(new_access_token);
-        if let Some(rt) = new_refresh_token {
-            a.refresh_token = Some(rt);
-        }
-        if let Some(arn) = new_profile_arn {
-            a.profile_arn = Some(arn);
-        }
-        if let Some(id_token) = new_id_token {
-            a.id_token = Some(id_token);
-        }
-        if let Some(session_id) = new_sso_session_id {
-            a.sso_session_id = Some(session_id);
-        }
-        a.expires_at = Some(expires_at_str);
-        a.usage_data = Some(usage_data);
-        a.status = if is_banned { "已封禁".to_string() } else { "正常".to_string() };
-        
-        let result = a.clone();
-        store.save_to_file();
-        return Ok(result);
+    let deleted: Vec<String> = results.iter().filter(|r| r.deleted).map(|r| r.account_id.clone()).collect();
+    if !deleted.is_empty() {
+        state.store.lock().unwrap().delete_many(&deleted)?;
     }
+    Ok(results)
+}
 
-    Err("Account not found after update".to_string())
+/// 按标签、分组、provider、状态等筛选账号，支持排序和分页
+#[tauri::command]
+pub fn query_accounts(state: State<AppState>, query: AccountQuery) -> AccountQueryResult {
+    state.store.lock().unwrap().query(&query)
 }
 
-/// 只刷新 token，不获取 usage（启动时快速刷新用）
+/// 批量增删标签，返回有变化的账号数
 #[tauri::command]
-pub async fn refresh_account_token(state: State<'_, AppState>, id: String) -> Result<Account, String> {
-    let account = {
-        let store = state.store.lock().unwrap();
-        store.accounts.iter().find(|a| a.id == id).cloned()
-    }.ok_or("Account not found")?;
+pub fn update_account_tags(
+    state: State<AppState>,
+    ids: Option<Vec<String>>,
+    query: Option<AccountQuery>,
+    add: Option<Vec<String>>,
+    remove: Option<Vec<String>>,
+) -> AppResult<usize> {
+    let mut store = state.store.lock().unwrap();
+    let ids = store.resolve_ids(ids, query.as_ref())?;
+    store.update_tags(&ids, &add.unwrap_or_default(), &remove.unwrap_or_default())
+}
 
-    let provider_str = account.provider.as_deref().unwrap_or("Google");
-    let refresh_token_str = account.refresh_token.as_ref().ok_or("No refresh token")?;
-    
-    println!("[refresh_token] Refreshing {} token only", provider_str);
-    
-    let (new_access_token, new_refresh_token, expires_in) = 
-        if provider_str == "BuilderId" {
-            let metadata = RefreshMetadata {
-                client_id: account.client_id.clone(),
-                client_secret: account.client_secret.clone(),
-                region: account.region.clone(),
-                ..Default::default()
-            };
-            let idc_provider = IdcProvider::new("BuilderId", metadata.region.as_deref().unwrap_or("us-east-1"), None);
-            let auth_result = idc_provider.refresh_token(refresh_token_str, metadata).await?;
-            (auth_result.access_token, Some(auth_result.refresh_token), auth_result.expires_in)
-        } else {
-            let metadata = RefreshMetadata {
-                profile_arn: account.profile_arn.clone(),
-                ..Default::default()
-            };
-            let social_provider = SocialProvider::new(provider_str);
-            let auth_result = social_provider.refresh_token(refresh_token_str, metadata).await?;
-            (auth_result.access_token, Some(auth_result.refresh_token), auth_result.expires_in)
-        };
+/// 批量设置分组（group 为空表示移出分组），返回有变化的账号数
+#[tauri::command]
+pub fn set_accounts_group(
+    state: State<AppState>,
+    ids: Option<Vec<String>>,
+    query: Option<AccountQuery>,
+    group: Option<String>,
+) -> AppResult<usize> {
+    let mut store = state.store.lock().unwrap();
+    let ids = store.resolve_ids(ids, query.as_ref())?;
+    store.set_group(&ids, group)
+}
 
-    let expires_at = chrono::Local::now() + chrono::Duration::seconds(expires_in);
-    let expires_at_str = expires_at.format("%Y/%m/%d %H:%M:%S").to_string();
+/// 所有标签和分组及其账号数
+#[tauri::command]
+pub fn get_account_facets(state: State<AppState>) -> AccountFacets {
+    state.store.lock().unwrap().facets()
+}
 
-    let mut store = state.store.lock().unwrap();
-    if let Some(a) = store.accounts.iter_mut().find(|a| a.id == id) {
-        a.access_token = Some(new_access_token);
-        if let Some(rt) = new_refresh_token {
-            a.refresh_token = Some(rt);
-        }
-        a.expires_at = Some(expires_at_str);
-        
-        let result = a.clone();
-        store.save_to_file();
-        println!("[refresh_token] {} token refreshed", provider_str);
-        return Ok(result);
-    }
+#[tauri::command]
+pub async fn sync_account(state: State<'_, AppState>, id: String) -> AppResult<Account> {
+    TokenManager::sync_by_id(&state.store, &id).await
+}
+
+/// 只刷新 token，不获取 usage（启动时快速刷新用）
+#[tauri::command]
+pub async fn refresh_account_token(state: State<'_, AppState>, id: String) -> AppResult<Account> {
+    refresh_token_by_id(&state, &id).await
+}
 
-    Err("Account not found after update".to_string())
+/// 只刷新 token（不查 usage），命令和后台刷新调度共用
+pub async fn refresh_token_by_id(state: &AppState, id: &str) -> AppResult<Account> {
+    TokenManager::refresh_by_id(&state.store, id).await
 }
 
 #[tauri::command]
@@ -193,74 +192,89 @@ pub async fn verify_account(
     client_id: Option<String>,
     client_secret: Option<String>,
     region: Option<String>,
-) -> Result<VerifyAccountResponse, String> {
-    // 判断是否是 IdC 账号
-    let is_idc = provider == "BuilderId" || provider == "Enterprise";
-    
-    let (new_access_token, new_refresh_token, quota, used, subscription_type) = if is_idc {
-        // IdC 账号使用 AWS OIDC 刷新
-        // 优先使用传入的参数，否则从数据库查找
-        let (cid, csec, reg) = if client_id.is_some() && client_secret.is_some() {
-            (client_id, client_secret, region)
-        } else {
-            // 从数据库查找
-            let store = state.store.lock().unwrap();
-            store.accounts.iter().find(|a| {
-                a.refresh_token.as_ref() == Some(&refresh_token)
-            }).map(|a| (
-                a.client_id.clone(),
-                a.client_secret.clone(),
-                a.region.clone(),
-            )).unwrap_or((None, None, None))
-        };
-        
-        let cid = cid.ok_or("IdC 账号缺少 client_id，请重新添加账号")?;
-        let csec = csec.ok_or("IdC 账号缺少 client_secret，请重新添加账号")?;
-        
-        let metadata = RefreshMetadata {
-            client_id: Some(cid),
-            client_secret: Some(csec),
-            region: reg.clone(),
-            ..Default::default()
-        };
-        
-        let region_str = reg.as_deref().unwrap_or("us-east-1");
-        let idc_provider = IdcProvider::new(&provider, region_str, None);
-        let auth_result = idc_provider.refresh_token(&refresh_token, metadata).await?;
-        
-        // 使用 CodeWhisperer API 获取 usage
-        let machine_id = get_machine_id();
-        let cw_client = CodeWhispererClient::new(&machine_id);
-        let usage = cw_client.get_usage_limits(&auth_result.access_token).await?;
-        
-        let (q, u) = usage.usage_breakdown_list.as_ref()
-            .and_then(|list| list.first())
-            .map(|b| (b.usage_limit, b.current_usage))
-            .unwrap_or((None, None));
-        
-        (auth_result.access_token, auth_result.refresh_token, q, u, usage.subscription_info.and_then(|s| s.subscription_type))
-    } else {
-        // Social 账号使用 Desktop API 刷新
-        let refresh_result = refresh_token_desktop(&refresh_token).await?;
-        let usage = get_usage_limits_desktop(&refresh_result.access_token).await?;
-        
-        let (q, u) = usage.usage_breakdown_list.as_ref()
-            .and_then(|list| list.first())
-            .map(|b| (b.usage_limit, b.current_usage))
-            .unwrap_or((None, None));
-        
-        (refresh_result.access_token, refresh_result.refresh_token, q, u, usage.subscription_info.and_then(|s| s.subscription_type))
+) -> AppResult<VerifyAccountResponse> {
+    // 优先用账号库中的同一账号（带 authMethod、客户端注册信息），否则按传入参数构造
+    let stored_id = {
+        let store = state.store.lock().unwrap();
+        store.accounts.iter().find(|a| a.refresh_token.as_ref() == Some(&refresh_token)).map(|a| a.id.clone())
+    };
+    // 已存账号和后台刷新共用刷新锁，拿到锁后用账号库中最新的 refresh token（等待期间可能已被轮换）
+    let _guard = match &stored_id {
+        Some(id) => Some(TokenManager::lock_account(id).await),
+        None => None,
+    };
+    let mut account = match &stored_id {
+        Some(id) => TokenManager::stored(&state.store, id)?,
+        None => {
+            let mut account = Account::new(String::new(), String::new());
+            account.refresh_token = Some(refresh_token.clone());
+            account
+        }
+    };
+    account.provider = Some(provider);
+    if client_id.is_some() && client_secret.is_some() {
+        account.client_id = client_id;
+        account.client_secret = client_secret;
+        account.region = region.or(account.region);
+    }
+
+    if TokenManager::auth_method(&account) == AuthMethod::Idc {
+        account.client_id.as_ref().ok_or("IdC 账号缺少 client_id，请重新添加账号")?;
+        account.client_secret.as_ref().ok_or("IdC 账号缺少 client_secret，请重新添加账号")?;
+    }
+
+    let auth_result = TokenManager::refresh(&account).await?;
+    let (new_access_token, new_refresh_token) = (auth_result.access_token.clone(), auth_result.refresh_token.clone());
+
+    // 按实际使用的刷新方式（Web OAuth 可能退回桌面端）选 usage 接口
+    let used_method = AuthMethod::parse(&auth_result.auth_method).unwrap_or_else(|| TokenManager::auth_method(&account));
+    let profile_arn = auth_result.profile_arn.as_deref().or(account.profile_arn.as_deref());
+    let (quota, used, subscription_type) = match used_method {
+        AuthMethod::Idc => {
+            // 使用 CodeWhisperer API 获取 usage
+            let machine_id = get_machine_id();
+            let cw_client = CodeWhispererClient::new(&machine_id)?.for_profile(profile_arn);
+            let usage = cw_client.get_usage_limits(&new_access_token).await?;
+            let (q, u) = usage.usage_breakdown_list.as_ref()
+                .and_then(|list| list.first())
+                .map(|b| (b.usage_limit, b.current_usage))
+                .unwrap_or((None, None));
+            (q, u, usage.subscription_info.and_then(|s| s.subscription_type))
+        }
+        AuthMethod::Social => {
+            let usage = get_usage_limits_desktop(&new_access_token, profile_arn).await?;
+            let (q, u) = usage.usage_breakdown_list.as_ref()
+                .and_then(|list| list.first())
+                .map(|b| (b.usage_limit, b.current_usage))
+                .unwrap_or((None, None));
+            (q, u, usage.subscription_info.and_then(|s| s.subscription_type))
+        }
+        AuthMethod::WebOAuth => {
+            let usage = KiroWebPortalClient::new()?.get_user_usage_and_limits(
+                &new_access_token,
+                auth_result.csrf_token.as_deref().unwrap_or(""),
+                &new_refresh_token,
+                account.provider.as_deref().unwrap_or("Google"),
+            ).await?;
+            let (q, u) = usage.usage_breakdown_list.as_ref()
+                .and_then(|list| list.first())
+                .map(|b| (b.usage_limit, b.current_usage))
+                .unwrap_or((None, None));
+            (q, u, usage.subscription_info.and_then(|s| s.subscription_type))
+        }
     };
     
     // 更新数据库中的 token
     {
         let mut store = state.store.lock().unwrap();
-        if let Some(account) = store.accounts.iter_mut().find(|a| {
-            a.refresh_token.as_ref() == Some(&refresh_token)
-        }) {
-            account.access_token = Some(new_access_token.clone());
-            account.refresh_token = Some(new_refresh_token.clone());
-            store.save_to_file();
+        let updated = store.accounts.iter_mut()
+            .find(|a| Some(&a.id) == stored_id.as_ref())
+            .map(|stored| {
+                TokenManager::apply(stored, &auth_result);
+                stored.clone()
+            });
+        if let Some(updated) = updated {
+            TokenManager::save_refreshed(store, account.refresh_token.as_deref(), &updated)?;
         }
     }
     
@@ -278,59 +292,66 @@ pub async fn add_account_by_social(
     state: State<'_, AppState>,
     refresh_token: String,
     provider: Option<String>,
-) -> Result<Account, String> {
-    println!("Adding account by refresh (desktop API)");
-    
-    let refresh_result = refresh_token_desktop(&refresh_token).await?;
-    let access_token = refresh_result.access_token;
-    let new_refresh_token = refresh_result.refresh_token;
-    
-    let usage_call = get_usage_limits_desktop(&access_token).await;
-    let (usage_result, ban_reason) = match &usage_call {
-        Ok(usage) => (Some(usage.clone()), None),
-        Err(e) if e.starts_with("BANNED:") => (None, Some(e.strip_prefix("BANNED:").unwrap_or("UNKNOWN").to_string())),
-        Err(_) => (None, None),
-    };
+) -> AppResult<Account> {
+    info!("Adding account by refresh (desktop API)");
+    
+    let mut pending = Account::new(String::new(), String::new());
+    pending.refresh_token = Some(refresh_token);
+    pending.provider = provider.clone();
+    pending.auth_method = Some("social".to_string());
+    let auth_result = TokenManager::refresh(&pending).await?;
+    let access_token = auth_result.access_token.clone();
+    
+    let usage_call = get_usage_limits_desktop(&access_token, auth_result.profile_arn.as_deref()).await;
+    let usage_result = usage_call.as_ref().ok().cloned();
     let usage_data = serde_json::to_value(&usage_result).unwrap_or(serde_json::Value::Null);
-    let is_banned = ban_reason.is_some();
+    let status = AccountStatus::from_usage_result(&usage_call);
     
+    // usage 里没有的身份信息从 token claims 补
+    let claims = TokenManager::claims(&auth_result).unwrap_or_default();
     let email = usage_result.as_ref()
         .and_then(|u| u.user_info.as_ref())
         .and_then(|u| u.email.clone())
+        .or_else(|| claims.email.clone())
         .unwrap_or_else(|| "unknown@kiro.dev".to_string());
     let user_id = usage_result.as_ref()
         .and_then(|u| u.user_info.as_ref())
-        .and_then(|u| u.user_id.clone());
-    
-    let idp = provider.unwrap_or_else(|| {
-        if email.contains("gmail") { "Google".to_string() }
-        else if email.contains("github") { "Github".to_string() }
-        else { "Google".to_string() }
-    });
+        .and_then(|u| u.user_id.clone())
+        .or_else(|| claims.subject.clone());
     
     let mut store = state.store.lock().unwrap();
     
+    // provider：调用方指定 > token claims > 同邮箱的已有 Social 账号 > Google
+    let idp = provider
+        .or(claims.provider)
+        .or_else(|| store.accounts.iter()
+            .find(|a| a.email == email && matches!(a.provider.as_deref(), Some("Google") | Some("Github")))
+            .and_then(|a| a.provider.clone()))
+        .unwrap_or_else(|| "Google".to_string());
+    
     // 按 email + provider 去重
     let account = if let Some(existing) = store.accounts.iter_mut().find(|a| a.email == email && a.provider.as_deref() == Some(&idp)) {
-        existing.access_token = Some(access_token.clone());
-        existing.refresh_token = Some(new_refresh_token);
+        TokenManager::apply(existing, &auth_result);
         existing.user_id = user_id;
         existing.usage_data = Some(usage_data);
-        existing.status = if is_banned { "已封禁".to_string() } else { "正常".to_string() };
+        if let Some(status) = status {
+            existing.set_status(status);
+        }
         existing.clone()
     } else {
         let mut account = Account::new(email.clone(), format!("Kiro {} 账号", idp));
-        account.access_token = Some(access_token.clone());
-        account.refresh_token = Some(new_refresh_token);
         account.provider = Some(idp.clone());
+        TokenManager::apply(&mut account, &auth_result);
         account.user_id = user_id;
         account.usage_data = Some(usage_data);
-        account.status = if is_banned { "已封禁".to_string() } else { "正常".to_string() };
+        if let Some(status) = status {
+            account.status = status;
+        }
         store.accounts.insert(0, account.clone());
         account
     };
     
-    store.save_to_file();
+    store.save_to_file()?;
     drop(store);
     
     let user = User {
@@ -346,9 +367,35 @@ pub async fn add_account_by_social(
     Ok(account)
 }
 
+/// 导入账号（JSON 数组或 accounts.json），options 指定合并模式和 dry-run
+#[tauri::command]
+pub fn import_accounts(state: State<AppState>, json: String, options: Option<ImportOptions>) -> AppResult<ImportReport> {
+    state.store.lock().unwrap().import_from_json(&json, &options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_account_storage_info(state: State<AppState>) -> StorageInfo {
+    state.store.lock().unwrap().storage_info()
+}
+
+/// 切换账号存储后端 ("json" / "sqlite")，下次启动沿用
+#[tauri::command]
+pub fn set_account_storage_backend(state: State<AppState>, backend: String) -> AppResult<StorageInfo> {
+    let info = state.store.lock().unwrap().set_backend(&backend)?;
+    crate::commands::app_settings_cmd::set_account_storage(&info.backend)?;
+    Ok(info)
+}
+
+/// 列出 accounts.json 的滚动备份（最新的在前）
 #[tauri::command]
-pub fn import_accounts(state: State<AppState>, json: String) -> Result<usize, String> {
-    state.store.lock().unwrap().import_from_json(&json)
+pub fn list_accounts_backups(state: State<AppState>) -> AppResult<Vec<AccountBackup>> {
+    state.store.lock().unwrap().list_backups()
+}
+
+/// 从备份恢复账号，返回恢复后的账号数量
+#[tauri::command]
+pub fn restore_accounts_backup(state: State<AppState>, file_name: String) -> AppResult<usize> {
+    state.store.lock().unwrap().restore_backup(&file_name)
 }
 
 #[tauri::command]
@@ -369,9 +416,32 @@ pub fn export_accounts(state: State<AppState>, ids: Option<Vec<String>>) -> Stri
     }
 }
 
+/// 按导出配置导出账号，ids 为空时导出全部
+/// ide-token 只能选一个账号，encrypted 需要密码
+#[tauri::command]
+pub fn export_accounts_profile(
+    state: State<AppState>,
+    ids: Option<Vec<String>>,
+    profile: ExportProfile,
+    password: Option<String>,
+) -> AppResult<Vec<ExportFile>> {
+    let store = state.store.lock().unwrap();
+    if store.vault_status().locked {
+        return Err(AppError::Other("账号库已锁定，请先解锁".to_string()));
+    }
+    let selected: Vec<Account> = match ids {
+        Some(id_list) if !id_list.is_empty() => store.accounts.iter()
+            .filter(|a| id_list.contains(&a.id))
+            .cloned()
+            .collect(),
+        _ => store.accounts.clone(),
+    };
+    Ok(account_export::export(&selected, profile, password.as_deref())?)
+}
+
 /// 添加本地 Kiro IDE 账号
 #[tauri::command]
-pub async fn add_local_kiro_account(state: State<'_, AppState>) -> Result<Account, String> {
+pub async fn add_local_kiro_account(state: State<'_, AppState>) -> AppResult<Account> {
     use crate::kiro::{get_kiro_local_token, get_client_registration};
     
     let local_token = get_kiro_local_token()
@@ -416,81 +486,65 @@ pub async fn add_account_by_idc(
     client_id: String,
     client_secret: String,
     region: Option<String>,
-) -> Result<Account, String> {
+) -> AppResult<Account> {
     let region = region.unwrap_or_else(|| "us-east-1".to_string());
-    let metadata = RefreshMetadata {
-        client_id: Some(client_id.clone()),
-        client_secret: Some(client_secret.clone()),
-        region: Some(region.clone()),
-        ..Default::default()
-    };
-    
-    let idc_provider = IdcProvider::new("BuilderId", &region, None);
-    let auth_result = idc_provider.refresh_token(&refresh_token, metadata).await?;
+    let mut pending = Account::new(String::new(), String::new());
+    pending.refresh_token = Some(refresh_token);
+    pending.provider = Some("BuilderId".to_string());
+    pending.auth_method = Some("IdC".to_string());
+    pending.client_id = Some(client_id.clone());
+    pending.client_secret = Some(client_secret.clone());
+    pending.region = Some(region.clone());
+    let auth_result = TokenManager::refresh(&pending).await?;
     
     let machine_id = get_machine_id();
-    let cw_client = CodeWhispererClient::new(&machine_id);
+    let cw_client = CodeWhispererClient::new(&machine_id)?;
     let usage_call = cw_client.get_usage_limits(&auth_result.access_token).await;
-    let (usage, is_banned) = match &usage_call {
-        Ok(u) => (Some(u.clone()), false),
-        Err(e) if e.starts_with("BANNED:") => (None, true),
-        Err(_) => (None, false),
-    };
+    let usage = usage_call.as_ref().ok().cloned();
     let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
+    let status = AccountStatus::from_usage_result(&usage_call);
     
+    let claims = TokenManager::claims(&auth_result).unwrap_or_default();
     let email = usage.as_ref()
         .and_then(|u| u.user_info.as_ref())
         .and_then(|u| u.email.clone())
+        .or(claims.email)
         .unwrap_or_else(|| "builderid@kiro.dev".to_string());
     let user_id = usage.as_ref()
         .and_then(|u| u.user_info.as_ref())
-        .and_then(|u| u.user_id.clone());
-    
-    use sha2::{Digest, Sha256};
-    let start_url = "https://view.awsapps.com/start";
-    let mut hasher = Sha256::new();
-    hasher.update(start_url.as_bytes());
-    let client_id_hash = hex::encode(hasher.finalize());
-    
-    let expires_at = chrono::Local::now() + chrono::Duration::seconds(auth_result.expires_in);
+        .and_then(|u| u.user_id.clone())
+        .or(claims.subject);
     
     let mut store = state.store.lock().unwrap();
     
     // 按 email + provider 去重
     let account = if let Some(existing) = store.accounts.iter_mut().find(|a| a.email == email && a.provider.as_deref() == Some("BuilderId")) {
-        existing.access_token = Some(auth_result.access_token);
-        existing.refresh_token = Some(auth_result.refresh_token);
-        existing.user_id = user_id;
-        existing.expires_at = Some(expires_at.format("%Y/%m/%d %H:%M:%S").to_string());
         existing.client_id = Some(client_id);
         existing.client_secret = Some(client_secret);
-        existing.region = Some(region);
-        existing.client_id_hash = Some(client_id_hash);
-        existing.id_token = auth_result.id_token;
-        existing.sso_session_id = auth_result.sso_session_id;
+        existing.auth_method = Some("IdC".to_string());
+        TokenManager::apply(existing, &auth_result);
+        existing.user_id = user_id;
         existing.usage_data = Some(usage_data);
-        existing.status = if is_banned { "已封禁".to_string() } else { "正常".to_string() };
+        if let Some(status) = status {
+            existing.set_status(status);
+        }
         existing.clone()
     } else {
         let mut account = Account::new(email.clone(), "Kiro BuilderId 账号".to_string());
-        account.access_token = Some(auth_result.access_token);
-        account.refresh_token = Some(auth_result.refresh_token);
         account.provider = Some("BuilderId".to_string());
-        account.user_id = user_id;
-        account.expires_at = Some(expires_at.format("%Y/%m/%d %H:%M:%S").to_string());
         account.client_id = Some(client_id);
         account.client_secret = Some(client_secret);
-        account.region = Some(region);
-        account.client_id_hash = Some(client_id_hash);
-        account.id_token = auth_result.id_token;
-        account.sso_session_id = auth_result.sso_session_id;
+        TokenManager::apply(&mut account, &auth_result);
+        account.user_id = user_id;
         account.usage_data = Some(usage_data);
-        account.status = if is_banned { "已封禁".to_string() } else { "正常".to_string() };
+        if let Some(status) = status {
+            account.status = status;
+        }
         store.accounts.insert(0, account.clone());
         account
     };
     
-    store.save_to_file();
+    store.save_to_file()?;
     
     Ok(account)
 }
@@ -506,7 +560,7 @@ pub fn update_account(
     // BuilderId SSO 字段
     client_id: Option<String>,
     client_secret: Option<String>,
-) -> Result<Account, String> {
+) -> AppResult<Account> {
     let mut store = state.store.lock().unwrap();
     
     // 先找到索引，避免借用冲突
@@ -524,15 +578,19 @@ pub fn update_account(
         }
         // BuilderId SSO 字段
         if let Some(cid) = client_id {
+            // 换了客户端注册，原来记录的过期时间不再适用
+            if store.accounts[idx].client_id.as_ref() != Some(&cid) {
+                store.accounts[idx].client_secret_expires_at = None;
+            }
             store.accounts[idx].client_id = Some(cid);
         }
         if let Some(csec) = client_secret {
             store.accounts[idx].client_secret = Some(csec);
         }
         let result = store.accounts[idx].clone();
-        store.save_to_file();
+        store.save_to_file()?;
         Ok(result)
     } else {
-        Err("账号不存在".to_string())
+        Err(AppError::not_found("账号不存在"))
     }
 }
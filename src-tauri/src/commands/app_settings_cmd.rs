@@ -3,6 +3,12 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::endpoints::{self, EndpointSettings};
+use crate::error::{AppError, AppResult};
+use crate::fs_util::{write_atomic, FileLock};
+use crate::http_client::{self, NetworkSettings, ProxyMode};
+use tracing::{debug, warn};
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettings {
@@ -10,13 +16,29 @@ pub struct AppSettings {
     pub lock_model: Option<bool>,
     pub locked_model: Option<String>,
     pub auto_refresh: Option<bool>,
-    pub auto_refresh_interval: Option<i32>,
+    pub auto_refresh_interval: Option<i32>,    // 旧版固定刷新间隔，已不再使用，保留以免丢失旧设置
+    // 后台刷新：在 token 过期前多少分钟刷新、同时刷新的账号数
+    pub refresh_margin_minutes: Option<u32>,
+    pub refresh_concurrency: Option<u32>,
     pub auto_change_machine_id: Option<bool>,
     pub browser_path: Option<String>,
     // 账户机器码绑定功能
     pub bind_machine_id_to_account: Option<bool>,  // 是否启用账户绑定机器码
     pub use_bound_machine_id: Option<bool>,        // 切换时使用绑定的机器码（否则随机生成）
     pub account_machine_ids: Option<std::collections::HashMap<String, String>>,  // 账户ID -> 机器码映射
+    // 账号存储后端: "json" (默认) / "sqlite"
+    pub account_storage: Option<String>,
+    // accounts.json 保留的备份数量
+    pub account_backup_count: Option<u32>,
+    // 本应用的网络设置（见 http_client）；字符串字段保存空字符串表示清除
+    pub proxy_mode: Option<String>,            // "auto" / "manual" / "none"
+    pub proxy_url: Option<String>,             // 手动代理地址，支持 http / https / socks5
+    pub no_proxy: Option<String>,              // 不走代理的主机，逗号分隔
+    pub ca_bundle_path: Option<String>,        // 额外信任的 CA 证书 (PEM)
+    pub connect_timeout_secs: Option<u32>,
+    pub request_timeout_secs: Option<u32>,
+    // 服务地址覆盖（见 endpoints），整体替换
+    pub endpoints: Option<EndpointSettings>,
 }
 
 fn get_app_settings_path() -> PathBuf {
@@ -32,149 +54,231 @@ fn get_app_settings_path() -> PathBuf {
         .join("app-settings.json")
 }
 
-fn get_app_settings_inner() -> Result<AppSettings, String> {
+fn get_app_settings_inner() -> AppResult<AppSettings> {
     let path = get_app_settings_path();
     if !path.exists() {
         return Ok(AppSettings::default());
     }
     let content = std::fs::read_to_string(&path)
-        .map_err(|e| format!("读取设置失败: {}", e))?;
+        .map_err(|e| AppError::io("读取设置失败", e))?;
     serde_json::from_str(&content)
-        .map_err(|e| format!("解析设置失败: {}", e))
+        .map_err(|e| AppError::Parse(format!("解析设置失败: {}", e)))
 }
 
-fn save_app_settings_inner(updates: AppSettings) -> Result<(), String> {
+/// 持有进程间锁完成 读取-修改-写入，始终基于磁盘上的最新内容修改
+/// 避免多个实例（或外部脚本）同时保存时互相覆盖
+fn update_app_settings<F: FnOnce(&mut AppSettings)>(apply: F) -> AppResult<()> {
     let path = get_app_settings_path();
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
-    
-    // 读取现有设置，合并更新
+    let _lock = FileLock::acquire(&path)?;
+
     let mut current = get_app_settings_inner().unwrap_or_default();
-    
-    // 只更新传入的非 None 字段
+    apply(&mut current);
+
+    let content = serde_json::to_string_pretty(&current)
+        .map_err(|e| AppError::Parse(format!("序列化失败: {}", e)))?;
+    write_atomic(&path, content.as_bytes())?;
+    http_client::reload_settings();
+    endpoints::reload_settings();
+    Ok(())
+}
+
+/// 只更新传入的非 None 字段
+fn merge_app_settings(current: &mut AppSettings, updates: AppSettings) {
     if updates.theme.is_some() { current.theme = updates.theme; }
     if updates.lock_model.is_some() { current.lock_model = updates.lock_model; }
     if updates.locked_model.is_some() { current.locked_model = updates.locked_model; }
     if updates.auto_refresh.is_some() { current.auto_refresh = updates.auto_refresh; }
     if updates.auto_refresh_interval.is_some() { current.auto_refresh_interval = updates.auto_refresh_interval; }
+    if updates.refresh_margin_minutes.is_some() { current.refresh_margin_minutes = updates.refresh_margin_minutes; }
+    if updates.refresh_concurrency.is_some() { current.refresh_concurrency = updates.refresh_concurrency; }
     if updates.auto_change_machine_id.is_some() { current.auto_change_machine_id = updates.auto_change_machine_id; }
     if updates.browser_path.is_some() { current.browser_path = updates.browser_path; }
     if updates.bind_machine_id_to_account.is_some() { current.bind_machine_id_to_account = updates.bind_machine_id_to_account; }
     if updates.use_bound_machine_id.is_some() { current.use_bound_machine_id = updates.use_bound_machine_id; }
     if updates.account_machine_ids.is_some() { current.account_machine_ids = updates.account_machine_ids; }
-    
-    let content = serde_json::to_string_pretty(&current)
-        .map_err(|e| format!("序列化失败: {}", e))?;
-    std::fs::write(&path, content)
-        .map_err(|e| format!("写入失败: {}", e))?;
-    Ok(())
+    if updates.account_storage.is_some() { current.account_storage = updates.account_storage; }
+    if updates.account_backup_count.is_some() { current.account_backup_count = updates.account_backup_count; }
+    if updates.proxy_mode.is_some() { current.proxy_mode = updates.proxy_mode; }
+    if updates.proxy_url.is_some() { current.proxy_url = updates.proxy_url; }
+    if updates.no_proxy.is_some() { current.no_proxy = updates.no_proxy; }
+    if updates.ca_bundle_path.is_some() { current.ca_bundle_path = updates.ca_bundle_path; }
+    if updates.connect_timeout_secs.is_some() { current.connect_timeout_secs = updates.connect_timeout_secs; }
+    if updates.request_timeout_secs.is_some() { current.request_timeout_secs = updates.request_timeout_secs; }
+    if updates.endpoints.is_some() { current.endpoints = updates.endpoints; }
+}
+
+fn save_app_settings_inner(updates: AppSettings) -> AppResult<()> {
+    // 网络设置有误时拒绝保存，否则之后所有请求都会退回直连
+    let network_changed = updates.proxy_mode.is_some() || updates.proxy_url.is_some() || updates.no_proxy.is_some()
+        || updates.ca_bundle_path.is_some() || updates.connect_timeout_secs.is_some() || updates.request_timeout_secs.is_some();
+    if network_changed {
+        let mut preview = get_app_settings_inner().unwrap_or_default();
+        merge_app_settings(&mut preview, updates.clone());
+        ProxyMode::parse(preview.proxy_mode.as_deref())?;
+        http_client::validate(&network_settings(&preview))?;
+    }
+    if let Some(endpoints) = &updates.endpoints {
+        endpoints.validate()?;
+    }
+
+    // 合并更新到现有设置
+    update_app_settings(|current| merge_app_settings(current, updates))
 }
 
 #[tauri::command]
-pub async fn get_app_settings() -> Result<AppSettings, String> {
+pub async fn get_app_settings() -> AppResult<AppSettings> {
     tokio::task::spawn_blocking(get_app_settings_inner)
         .await
-        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| AppError::Other(format!("Task failed: {}", e)))?
 }
 
 #[tauri::command]
-pub async fn save_app_settings(settings: AppSettings) -> Result<(), String> {
+pub async fn save_app_settings(settings: AppSettings) -> AppResult<()> {
     tokio::task::spawn_blocking(move || save_app_settings_inner(settings))
         .await
-        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| AppError::Other(format!("Task failed: {}", e)))?
 }
 
 /// 获取自定义浏览器路径（供打开浏览器时使用）
 pub fn get_browser_path() -> Option<String> {
     let path = get_app_settings_path();
-    println!("[Settings] App settings path: {:?}", path);
+    debug!("App settings path: {:?}", path);
     
     let result = get_app_settings_inner();
-    println!("[Settings] get_app_settings_inner result: {:?}", result);
+    debug!("get_app_settings_inner result: {:?}", result);
     
     let browser_path = result.ok().and_then(|s| s.browser_path).filter(|p| !p.is_empty());
-    println!("[Settings] browser_path: {:?}", browser_path);
+    debug!("browser_path: {:?}", browser_path);
     
     browser_path
 }
 
+/// 获取账号存储后端（供 AccountStore 启动时使用）
+pub fn get_account_storage() -> String {
+    get_app_settings_inner()
+        .ok()
+        .and_then(|s| s.account_storage)
+        .unwrap_or_else(|| "json".to_string())
+}
+
+/// 获取 accounts.json 备份保留数量（默认 10）
+pub fn get_account_backup_count() -> usize {
+    get_app_settings_inner()
+        .ok()
+        .and_then(|s| s.account_backup_count)
+        .map(|n| n.max(1) as usize)
+        .unwrap_or(10)
+}
+
+/// 后台刷新设置：(是否启用, 提前刷新分钟数, 并发数)，默认 (true, 5, 3)
+pub fn get_refresh_settings() -> (bool, u32, usize) {
+    let settings = get_app_settings_inner().unwrap_or_default();
+    (
+        settings.auto_refresh.unwrap_or(true),
+        settings.refresh_margin_minutes.unwrap_or(5).max(1),
+        settings.refresh_concurrency.unwrap_or(3).clamp(1, 16) as usize,
+    )
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(|v| v.to_string())
+}
+
+fn network_settings(settings: &AppSettings) -> NetworkSettings {
+    NetworkSettings {
+        // 无法识别的模式按 auto 处理，保存时已校验
+        proxy_mode: ProxyMode::parse(settings.proxy_mode.as_deref()).unwrap_or_default(),
+        proxy_url: non_empty(&settings.proxy_url),
+        no_proxy: non_empty(&settings.no_proxy),
+        ca_bundle_path: non_empty(&settings.ca_bundle_path),
+        connect_timeout_secs: settings.connect_timeout_secs.filter(|&s| s > 0).map(u64::from),
+        request_timeout_secs: settings.request_timeout_secs.filter(|&s| s > 0).map(u64::from),
+    }
+}
+
+/// 网络设置（供 http_client 创建客户端时使用）
+pub fn get_network_settings() -> NetworkSettings {
+    network_settings(&get_app_settings_inner().unwrap_or_default())
+}
+
+/// 服务地址覆盖（供 endpoints 解析地址时使用）
+pub fn get_endpoint_settings() -> EndpointSettings {
+    match get_app_settings_inner() {
+        Ok(settings) => settings.endpoints.unwrap_or_default(),
+        Err(e) => {
+            warn!("Failed to read endpoint settings, using defaults: {}", e);
+            EndpointSettings::default()
+        }
+    }
+}
+
+/// 保存账号存储后端设置
+pub fn set_account_storage(backend: &str) -> AppResult<()> {
+    save_app_settings_inner(AppSettings {
+        account_storage: Some(backend.to_string()),
+        ..Default::default()
+    })
+}
+
 // ============================================================
 // 账号绑定机器码功能
 // ============================================================
 
 /// 绑定机器码到账号
-fn bind_machine_id_inner(account_id: String, machine_id: String) -> Result<(), String> {
-    let path = get_app_settings_path();
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
-    
-    let mut current = get_app_settings_inner().unwrap_or_default();
-    let mut map = current.account_machine_ids.unwrap_or_default();
-    map.insert(account_id, machine_id);
-    current.account_machine_ids = Some(map);
-    
-    let content = serde_json::to_string_pretty(&current)
-        .map_err(|e| format!("序列化失败: {}", e))?;
-    std::fs::write(&path, content)
-        .map_err(|e| format!("写入失败: {}", e))?;
-    Ok(())
+fn bind_machine_id_inner(account_id: String, machine_id: String) -> AppResult<()> {
+    update_app_settings(|current| {
+        current.account_machine_ids
+            .get_or_insert_with(Default::default)
+            .insert(account_id, machine_id);
+    })
 }
 
 /// 解绑账号的机器码
-fn unbind_machine_id_inner(account_id: String) -> Result<(), String> {
-    let path = get_app_settings_path();
-    let mut current = get_app_settings_inner().unwrap_or_default();
-    
-    if let Some(ref mut map) = current.account_machine_ids {
-        map.remove(&account_id);
-    }
-    
-    let content = serde_json::to_string_pretty(&current)
-        .map_err(|e| format!("序列化失败: {}", e))?;
-    std::fs::write(&path, content)
-        .map_err(|e| format!("写入失败: {}", e))?;
-    Ok(())
+fn unbind_machine_id_inner(account_id: String) -> AppResult<()> {
+    update_app_settings(|current| {
+        if let Some(ref mut map) = current.account_machine_ids {
+            map.remove(&account_id);
+        }
+    })
 }
 
 /// 获取账号绑定的机器码
-fn get_bound_machine_id_inner(account_id: String) -> Result<Option<String>, String> {
+fn get_bound_machine_id_inner(account_id: String) -> AppResult<Option<String>> {
     let current = get_app_settings_inner().unwrap_or_default();
     Ok(current.account_machine_ids
         .and_then(|map| map.get(&account_id).cloned()))
 }
 
 /// 获取所有账号绑定的机器码
-fn get_all_bound_machine_ids_inner() -> Result<std::collections::HashMap<String, String>, String> {
+fn get_all_bound_machine_ids_inner() -> AppResult<std::collections::HashMap<String, String>> {
     let current = get_app_settings_inner().unwrap_or_default();
     Ok(current.account_machine_ids.unwrap_or_default())
 }
 
 #[tauri::command]
-pub async fn bind_machine_id_to_account(account_id: String, machine_id: String) -> Result<(), String> {
+pub async fn bind_machine_id_to_account(account_id: String, machine_id: String) -> AppResult<()> {
     tokio::task::spawn_blocking(move || bind_machine_id_inner(account_id, machine_id))
         .await
-        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| AppError::Other(format!("Task failed: {}", e)))?
 }
 
 #[tauri::command]
-pub async fn unbind_machine_id_from_account(account_id: String) -> Result<(), String> {
+pub async fn unbind_machine_id_from_account(account_id: String) -> AppResult<()> {
     tokio::task::spawn_blocking(move || unbind_machine_id_inner(account_id))
         .await
-        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| AppError::Other(format!("Task failed: {}", e)))?
 }
 
 #[tauri::command]
-pub async fn get_bound_machine_id(account_id: String) -> Result<Option<String>, String> {
+pub async fn get_bound_machine_id(account_id: String) -> AppResult<Option<String>> {
     tokio::task::spawn_blocking(move || get_bound_machine_id_inner(account_id))
         .await
-        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| AppError::Other(format!("Task failed: {}", e)))?
 }
 
 #[tauri::command]
-pub async fn get_all_bound_machine_ids() -> Result<std::collections::HashMap<String, String>, String> {
+pub async fn get_all_bound_machine_ids() -> AppResult<std::collections::HashMap<String, String>> {
     tokio::task::spawn_blocking(get_all_bound_machine_ids_inner)
         .await
-        .map_err(|e| format!("Task failed: {}", e))?
+        .map_err(|e| AppError::Other(format!("Task failed: {}", e)))?
 }
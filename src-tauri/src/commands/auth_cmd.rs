@@ -2,12 +2,16 @@
 
 use tauri::{Emitter, State};
 use crate::state::AppState;
-use crate::account::Account;
+use crate::account::{Account, AccountStatus};
 use crate::auth::{User, get_usage_limits_desktop};
 use crate::auth_social;
 use crate::codewhisperer_client::CodeWhispererClient;
-use crate::providers::{AuthMethod, AuthProvider, get_provider_config, create_social_provider, create_idc_provider};
+use crate::error::{AppError, AppResult};
+use crate::providers::{AuthMethod, AuthProvider, AuthResult, get_provider_config, enterprise_provider_config, create_social_provider, create_idc_provider};
 use crate::kiro::get_machine_id;
+use crate::ide_token_sync;
+use crate::token_manager::{is_placeholder_email, TokenManager};
+use tracing::info;
 
 #[tauri::command]
 pub fn get_current_user(state: State<AppState>) -> Option<User> {
@@ -26,13 +30,21 @@ pub async fn kiro_login(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     provider: String,
-) -> Result<String, String> {
-    let config = get_provider_config(&provider)
-        .ok_or_else(|| format!("Unsupported provider: {}", provider))?;
+    // Enterprise (IAM Identity Center) 专用
+    start_url: Option<String>,
+    region: Option<String>,
+) -> AppResult<String> {
+    let config = if provider == "Enterprise" {
+        enterprise_provider_config(start_url, region)?
+    } else {
+        get_provider_config(&provider)
+            .ok_or_else(|| format!("Unsupported provider: {}", provider))?
+    };
 
     match config.auth_method {
         AuthMethod::Social => login_social(app_handle, state, &config).await,
         AuthMethod::Idc => login_idc(app_handle, state, &config).await,
+        AuthMethod::WebOAuth => Err(AppError::Other("Web OAuth 登录请使用 web_oauth_login".to_string())),
     }
 }
 
@@ -40,7 +52,7 @@ async fn login_social(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     config: &crate::providers::ProviderConfig,
-) -> Result<String, String> {
+) -> AppResult<String> {
     let social_provider = create_social_provider(config);
     let provider_id = social_provider.get_provider_id().to_string();
     let auth_method = social_provider.get_auth_method();
@@ -48,8 +60,10 @@ async fn login_social(
     let auth_result = social_provider.login().await?;
     
     // 获取 usage，失败不影响登录（账号可能被暂停但仍可保存）
-    let usage = get_usage_limits_desktop(&auth_result.access_token).await.ok();
+    let usage_call = get_usage_limits_desktop(&auth_result.access_token, auth_result.profile_arn.as_deref()).await;
+    let usage = usage_call.as_ref().ok().cloned();
     let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
+    let status = AccountStatus::from_usage_result(&usage_call);
 
     // 优先从 usage 获取 email，否则用默认值
     let email = usage.as_ref()
@@ -63,38 +77,26 @@ async fn login_social(
     let mut store = state.store.lock().unwrap();
     
     // 按 email + provider 去重
-    let account = if let Some(existing) = store.accounts.iter_mut().find(|a| a.email == email && a.provider.as_deref() == Some(&provider_id)) {
-        // 更新现有账号
-        existing.access_token = Some(auth_result.access_token.clone());
-        existing.refresh_token = Some(auth_result.refresh_token.clone());
-        existing.user_id = user_id;
-        existing.expires_at = Some(auth_result.expires_at.clone());
-        existing.profile_arn = auth_result.profile_arn;
-        existing.label = format!("Kiro {} 账号", provider_id);
-        // 不覆盖 csrfToken，保留 Web OAuth 的
-        existing.usage_data = Some(usage_data);
-        existing.status = "正常".to_string();
-        existing.clone()
-    } else {
-        // 新建账号
-        let mut account = Account::new(email.clone(), format!("Kiro {} 账号", provider_id));
-        account.access_token = Some(auth_result.access_token.clone());
-        account.refresh_token = Some(auth_result.refresh_token.clone());
-        account.provider = Some(provider_id.clone());
+    let account = store.find_or_insert(
+        |a| a.email == email && a.provider.as_deref() == Some(&provider_id),
+        || Account::new(email.clone(), String::new()),
+    );
+    TokenManager::apply_login(account, &auth_result);
+    account.label = format!("Kiro {} 账号", provider_id);
+    if user_id.is_some() {
         account.user_id = user_id;
-        account.expires_at = Some(auth_result.expires_at.clone());
-        account.profile_arn = auth_result.profile_arn;
-        account.csrf_token = auth_result.csrf_token;
-        account.usage_data = Some(usage_data);
-        store.accounts.insert(0, account.clone());
-        account
-    };
+    }
+    account.usage_data = Some(usage_data);
+    if let Some(status) = status {
+        account.set_status(status);
+    }
+    let account = account.clone();
     
-    store.save_to_file();
+    store.save_to_file()?;
     drop(store);
 
     update_auth_state(&state, &email, &provider_id, &auth_result.access_token, &auth_result.refresh_token);
-    println!("\n[{}] LOGIN SUCCESS: {}", auth_method, account.email);
+    info!("{} login success: {}", auth_method, account.email);
 
     let _ = app_handle.emit("login-success", account.id.clone());
     Ok(format!("{} login completed for {}", auth_method, provider_id))
@@ -104,7 +106,7 @@ async fn login_idc(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     config: &crate::providers::ProviderConfig,
-) -> Result<String, String> {
+) -> AppResult<String> {
     let idc_provider = create_idc_provider(config);
     let provider_id = idc_provider.get_provider_id().to_string();
     let auth_method = idc_provider.get_auth_method();
@@ -112,71 +114,135 @@ async fn login_idc(
     let auth_result = idc_provider.login().await?;
 
     let machine_id = get_machine_id();
-    let cw_client = CodeWhispererClient::new(&machine_id);
+    let cw_client = CodeWhispererClient::new(&machine_id)?;
     let usage_call = cw_client.get_usage_limits(&auth_result.access_token).await;
-    let (usage, is_banned) = match &usage_call {
-        Ok(u) => (Some(u.clone()), false),
-        Err(e) if e.starts_with("BANNED:") => (None, true),
-        Err(_) => (None, false),
-    };
+    let usage = usage_call.as_ref().ok().cloned();
     let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
+    let status = AccountStatus::from_usage_result(&usage_call);
 
+    // usage 里没有的身份信息从 id_token claims 补
+    let claims = TokenManager::claims(&auth_result).unwrap_or_default();
     let email = usage.as_ref()
         .and_then(|u| u.user_info.as_ref())
         .and_then(|ui| ui.email.clone())
+        .or(claims.email)
         .unwrap_or_else(|| "user@builder.id".to_string());
     let user_id = usage.as_ref()
         .and_then(|u| u.user_info.as_ref())
-        .and_then(|ui| ui.user_id.clone());
+        .and_then(|ui| ui.user_id.clone())
+        .or(claims.subject);
 
     let mut store = state.store.lock().unwrap();
     
-    // 按 email + provider 去重
-    let account = if let Some(existing) = store.accounts.iter_mut().find(|a| a.email == email && a.provider.as_deref() == Some(&provider_id)) {
-        existing.access_token = Some(auth_result.access_token.clone());
-        existing.refresh_token = Some(auth_result.refresh_token.clone());
-        existing.user_id = user_id;
-        existing.expires_at = Some(auth_result.expires_at.clone());
-        existing.client_id_hash = auth_result.client_id_hash;
-        existing.client_id = auth_result.client_id;
-        existing.client_secret = auth_result.client_secret;
-        existing.region = auth_result.region;
-        existing.sso_session_id = auth_result.sso_session_id;
-        existing.id_token = auth_result.id_token;
-        existing.profile_arn = auth_result.profile_arn;
-        existing.usage_data = Some(usage_data);
-        existing.status = if is_banned { "已封禁".to_string() } else { "正常".to_string() };
-        existing.clone()
-    } else {
-        let mut account = Account::new(email.clone(), format!("Kiro {} 账号", provider_id));
-        account.access_token = Some(auth_result.access_token.clone());
-        account.refresh_token = Some(auth_result.refresh_token.clone());
-        account.provider = Some(provider_id.clone());
+    // 按 email + provider 去重，Enterprise 账号还要区分组织 (start URL)
+    let account = store.find_or_insert(
+        |a| {
+            a.email == email && a.provider.as_deref() == Some(&provider_id)
+                && (provider_id != "Enterprise" || a.start_url == auth_result.start_url)
+        },
+        || Account::new(email.clone(), format!("Kiro {} 账号", provider_id)),
+    );
+    TokenManager::apply_login(account, &auth_result);
+    if user_id.is_some() {
         account.user_id = user_id;
-        account.expires_at = Some(auth_result.expires_at.clone());
-        account.client_id_hash = auth_result.client_id_hash;
-        account.client_id = auth_result.client_id;
-        account.client_secret = auth_result.client_secret;
-        account.region = auth_result.region;
-        account.sso_session_id = auth_result.sso_session_id;
-        account.id_token = auth_result.id_token;
-        account.profile_arn = auth_result.profile_arn;
-        account.usage_data = Some(usage_data);
-        account.status = if is_banned { "已封禁".to_string() } else { "正常".to_string() };
-        store.accounts.insert(0, account.clone());
-        account
-    };
+    }
+    account.usage_data = Some(usage_data);
+    if let Some(status) = status {
+        account.set_status(status);
+    }
+    let account = account.clone();
     
-    store.save_to_file();
+    store.save_to_file()?;
     drop(store);
 
     update_auth_state(&state, &email, &provider_id, &auth_result.access_token, &auth_result.refresh_token);
-    println!("\n[{}] LOGIN SUCCESS: {}", auth_method, account.email);
+    info!("{} login success: {}", auth_method, account.email);
 
     let _ = app_handle.emit("login-success", account.id.clone());
     Ok(format!("{} login completed for {}", auth_method, email))
 }
 
+/// IdC 客户端注册过期后重新注册并走一遍设备授权
+/// 更新的是原账号：id、标签、分组等信息保留，授权的必须是同一个用户
+#[tauri::command]
+pub async fn reregister_idc_account(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> AppResult<Account> {
+    let account = {
+        let store = state.store.lock().unwrap();
+        store.accounts.iter().find(|a| a.id == id).cloned()
+    }.ok_or_else(|| AppError::not_found("Account not found"))?;
+    if TokenManager::auth_method(&account) != AuthMethod::Idc {
+        return Err(AppError::Other("只有 IdC 账号需要重新注册客户端".to_string()));
+    }
+
+    info!("Re-registering client for {}", account.email);
+    let auth_result = TokenManager::provider_for(&account, AuthMethod::Idc).login().await?;
+
+    let cw_client = CodeWhispererClient::new(&get_machine_id())?;
+    let usage_call = cw_client.get_usage_limits(&auth_result.access_token).await;
+    let usage = usage_call.as_ref().ok().cloned();
+    let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
+    let status = AccountStatus::from_usage_result(&usage_call);
+
+    // 必须能确认是同一个用户：email 或 user id 至少一项明确匹配
+    let claims = TokenManager::claims(&auth_result).unwrap_or_default();
+    let user_info = usage.as_ref().and_then(|u| u.user_info.as_ref());
+    let email = user_info.and_then(|ui| ui.email.clone()).or(claims.email);
+    let user_ids: Vec<String> = user_info.and_then(|ui| ui.user_id.clone())
+        .into_iter()
+        .chain(claims.subject)
+        .collect();
+
+    let email_matches = match &email {
+        Some(email) if !is_placeholder_email(&account.email) => {
+            if !email.eq_ignore_ascii_case(&account.email) {
+                return Err(AppError::Other(format!("授权的账号 {} 与原账号 {} 不一致", email, account.email)));
+            }
+            true
+        }
+        _ => false,
+    };
+    let user_id_matches = match &account.user_id {
+        Some(user_id) if !user_ids.is_empty() => {
+            if !user_ids.contains(user_id) {
+                return Err(AppError::Other(format!("授权的用户与原账号 {} 不一致", account.email)));
+            }
+            true
+        }
+        _ => false,
+    };
+    if !email_matches && !user_id_matches {
+        return Err(AppError::Other(format!("无法确认授权的用户就是 {}，请删除账号后重新登录", account.email)));
+    }
+
+    // 持有刷新锁再替换 token，避免用旧客户端注册的刷新把错误状态写回来
+    let _refresh_guard = TokenManager::lock_account(&id).await;
+    let mut store = state.store.lock().unwrap();
+    let updated = {
+        let existing = store.accounts.iter_mut().find(|a| a.id == id)
+            .ok_or_else(|| AppError::not_found("Account not found after update"))?;
+        existing.sso_session_id = auth_result.sso_session_id.clone();
+        TokenManager::apply_login(existing, &auth_result);
+        existing.usage_data = Some(usage_data);
+        if let Some(status) = status {
+            existing.set_status(status);
+        }
+        existing.clone()
+    };
+    store.save_to_file()?;
+    drop(store);
+
+    // IDE 正在使用这个账号时，新的 refresh token 只能配合新的客户端注册使用
+    ide_token_sync::push_registration_if_active(&updated);
+
+    info!("Client re-registered for {}", updated.email);
+    let _ = app_handle.emit("login-success", updated.id.clone());
+    Ok(updated)
+}
+
 fn update_auth_state(state: &State<'_, AppState>, email: &str, provider: &str, access_token: &str, refresh_token: &str) {
     let user = User {
         id: uuid::Uuid::new_v4().to_string(),
@@ -197,14 +263,14 @@ pub async fn handle_kiro_social_callback(
     state: State<'_, AppState>,
     code: String,
     callback_state: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let pending = {
         let lock = state.pending_login.lock().unwrap();
         lock.clone().ok_or("No pending login found")?
     };
     
     if pending.state != callback_state {
-        return Err("State mismatch".to_string());
+        return Err(AppError::Other("State mismatch".to_string()));
     }
     
     let redirect_uri = "kiro://app/callback";
@@ -212,8 +278,23 @@ pub async fn handle_kiro_social_callback(
         &code, &pending.code_verifier, redirect_uri, &pending.machineid,
     ).await?;
     
-    let usage = get_usage_limits_desktop(&token_response.access_token).await.ok();
+    let expires_at = chrono::Local::now() + chrono::Duration::seconds(token_response.expires_in);
+    let auth_result = AuthResult {
+        access_token: token_response.access_token,
+        refresh_token: token_response.refresh_token,
+        expires_at: expires_at.format("%Y/%m/%d %H:%M:%S").to_string(),
+        expires_in: token_response.expires_in,
+        provider: pending.provider.clone(),
+        auth_method: "social".to_string(),
+        profile_arn: Some(token_response.profile_arn).filter(|arn| !arn.is_empty()),
+        csrf_token: token_response.csrf_token,
+        ..Default::default()
+    };
+
+    let usage_call = get_usage_limits_desktop(&auth_result.access_token, auth_result.profile_arn.as_deref()).await;
+    let usage = usage_call.as_ref().ok().cloned();
     let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
+    let status = AccountStatus::from_usage_result(&usage_call);
     
     let email = usage.as_ref()
         .and_then(|u| u.user_info.as_ref())
@@ -225,31 +306,26 @@ pub async fn handle_kiro_social_callback(
 
     let mut store = state.store.lock().unwrap();
     
-    let account = if let Some(existing) = store.accounts.iter_mut().find(|a| a.email == email) {
-        existing.access_token = Some(token_response.access_token.clone());
-        existing.refresh_token = Some(token_response.refresh_token.clone());
-        existing.provider = Some(pending.provider.clone());
-        existing.user_id = user_id;
-        existing.usage_data = Some(usage_data);
-        existing.status = "正常".to_string();
-        existing.clone()
-    } else {
-        let mut account = Account::new(email.clone(), format!("Kiro {} 账号", pending.provider));
-        account.access_token = Some(token_response.access_token.clone());
-        account.refresh_token = Some(token_response.refresh_token.clone());
-        account.provider = Some(pending.provider.clone());
+    let account = store.find_or_insert(
+        |a| a.email == email,
+        || Account::new(email.clone(), format!("Kiro {} 账号", pending.provider)),
+    );
+    TokenManager::apply_login(account, &auth_result);
+    if user_id.is_some() {
         account.user_id = user_id;
-        account.usage_data = Some(usage_data);
-        store.accounts.insert(0, account.clone());
-        account
-    };
+    }
+    account.usage_data = Some(usage_data);
+    if let Some(status) = status {
+        account.set_status(status);
+    }
+    let account = account.clone();
     
-    store.save_to_file();
+    store.save_to_file()?;
     drop(store);
     
-    update_auth_state(&state, &email, &pending.provider, &token_response.access_token, &token_response.refresh_token);
+    update_auth_state(&state, &email, &pending.provider, &auth_result.access_token, &auth_result.refresh_token);
     let _ = app_handle.emit("login-success", account.id);
-    println!("Social callback login completed: {}", email);
+    info!("Social callback login completed: {}", email);
     Ok(())
 }
 
@@ -263,15 +339,17 @@ pub async fn add_kiro_account(
     idp: String,
     _quota: Option<i32>,
     _used: Option<i32>,
-) -> Result<Account, String> {
-    println!("Adding Kiro account: email={}, idp={}", email, idp);
+) -> AppResult<Account> {
+    info!("Adding Kiro account: email={}, idp={}", email, idp);
     
-    let usage = if !access_token.is_empty() {
-        get_usage_limits_desktop(&access_token).await.ok()
+    let usage_call = if !access_token.is_empty() {
+        Some(get_usage_limits_desktop(&access_token, None).await)
     } else {
         None
     };
+    let usage = usage_call.as_ref().and_then(|call| call.as_ref().ok()).cloned();
     let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
+    let status = usage_call.as_ref().and_then(AccountStatus::from_usage_result);
     
     let final_email = usage.as_ref()
         .and_then(|u| u.user_info.as_ref())
@@ -295,30 +373,36 @@ pub async fn add_kiro_account(
     *state.auth.user.lock().unwrap() = Some(user);
     *state.pending_login.lock().unwrap() = None;
     
+    // 手动添加的 token 不知道过期时间，按 1 小时计，之后由刷新更新
+    let expires_at = chrono::Local::now() + chrono::Duration::hours(1);
+    let auth_result = AuthResult {
+        access_token,
+        refresh_token,
+        expires_at: expires_at.format("%Y/%m/%d %H:%M:%S").to_string(),
+        expires_in: 3600,
+        provider: idp.clone(),
+        auth_method: "social".to_string(),
+        csrf_token: Some(csrf_token).filter(|t| !t.is_empty()),
+        ..Default::default()
+    };
+
     let mut store = state.store.lock().unwrap();
     
-    let account = if let Some(existing) = store.accounts.iter_mut().find(|a| a.email == final_email) {
-        existing.access_token = Some(access_token);
-        existing.refresh_token = Some(refresh_token);
-        existing.provider = Some(idp);
-        existing.user_id = user_id;
-        existing.csrf_token = Some(csrf_token);
-        existing.usage_data = Some(usage_data);
-        existing.status = "正常".to_string();
-        existing.clone()
-    } else {
-        let mut account = Account::new(final_email.clone(), format!("Kiro {} 账号", idp));
-        account.access_token = Some(access_token);
-        account.refresh_token = Some(refresh_token);
-        account.provider = Some(idp);
+    let account = store.find_or_insert(
+        |a| a.email == final_email,
+        || Account::new(final_email.clone(), format!("Kiro {} 账号", idp)),
+    );
+    TokenManager::apply_login(account, &auth_result);
+    if user_id.is_some() {
         account.user_id = user_id;
-        account.csrf_token = Some(csrf_token);
-        account.usage_data = Some(usage_data);
-        store.accounts.insert(0, account.clone());
-        account
-    };
+    }
+    account.usage_data = Some(usage_data);
+    if let Some(status) = status {
+        account.set_status(status);
+    }
+    let account = account.clone();
     
-    store.save_to_file();
+    store.save_to_file()?;
     
     Ok(account)
 }
@@ -0,0 +1,11 @@
+// IDE token 同步命令
+
+use tauri::AppHandle;
+
+use crate::ide_token_sync::{self, IdeActiveAccount};
+
+/// 重新读取 IDE token 并与账号库对齐，返回 IDE 当前使用的账号
+#[tauri::command]
+pub fn get_ide_active_account(app: AppHandle) -> IdeActiveAccount {
+    ide_token_sync::reconcile(&app)
+}
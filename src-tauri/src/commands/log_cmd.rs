@@ -0,0 +1,19 @@
+// 日志命令
+
+use crate::error::AppResult;
+use crate::logging::{self, LogExcerpt};
+
+const DEFAULT_LOG_LIMIT: usize = 500;
+const MAX_LOG_LIMIT: usize = 5000;
+
+/// 读取最近的日志（已脱敏），用于反馈问题
+/// limit 默认 500 条，level 默认 info（只返回该级别及更严重的日志）
+#[tauri::command]
+pub fn read_logs(limit: Option<usize>, level: Option<String>) -> AppResult<LogExcerpt> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).clamp(1, MAX_LOG_LIMIT);
+    let min_level = match level.as_deref().filter(|l| !l.trim().is_empty()) {
+        Some(l) => logging::parse_level(l)?,
+        None => tracing::Level::INFO,
+    };
+    logging::read_recent(limit, min_level)
+}
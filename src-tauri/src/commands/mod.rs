@@ -3,13 +3,18 @@
 pub mod account_cmd;
 pub mod app_settings_cmd;
 pub mod auth_cmd;
+pub mod ide_token_cmd;
 
 pub mod kiro_settings_cmd;
+pub mod log_cmd;
 pub mod machine_guid_cmd;
 pub mod mcp_cmd;
 pub mod powers_cmd;
 pub mod proxy_cmd;
+pub mod refresh_cmd;
 pub mod sso_import_cmd;
+pub mod sync_cmd;
 pub mod steering_cmd;
 pub mod update_cmd;
+pub mod vault_cmd;
 pub mod web_oauth_cmd;
@@ -0,0 +1,16 @@
+// 后台刷新调度命令
+
+use tauri::State;
+
+use crate::refresh_scheduler::{RefreshScheduler, SchedulerStatus};
+
+#[tauri::command]
+pub fn get_refresh_scheduler_status(scheduler: State<RefreshScheduler>) -> SchedulerStatus {
+    scheduler.status()
+}
+
+/// 前端在窗口可见性、网络状态或刷新设置变化时调用
+#[tauri::command]
+pub fn set_refresh_activity(scheduler: State<RefreshScheduler>, background: Option<bool>, online: Option<bool>) {
+    scheduler.set_activity(background, online);
+}
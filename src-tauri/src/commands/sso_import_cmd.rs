@@ -4,11 +4,13 @@
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use crate::state::AppState;
-use crate::account::Account;
+use crate::account::{Account, AccountStatus};
 use crate::kiro::get_machine_id;
 use crate::codewhisperer_client::CodeWhispererClient;
+use crate::error::{AppError, AppResult};
+use crate::endpoints;
+use tracing::info;
 
-const PORTAL_BASE: &str = "https://portal.sso.us-east-1.amazonaws.com";
 const START_URL: &str = "https://view.awsapps.com/start";
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +26,8 @@ pub struct SsoImportResult {
 struct RegisterClientResponse {
     client_id: String,
     client_secret: String,
+    #[serde(default)]
+    client_secret_expires_at: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,17 +76,16 @@ pub async fn import_from_sso_token(
     bearer_token: String,
     region: Option<String>,
     state: State<'_, AppState>,
-) -> Result<SsoImportResult, String> {
+) -> AppResult<SsoImportResult> {
     let region = region.unwrap_or_else(|| "us-east-1".to_string());
-    let oidc_base = format!("https://oidc.{}.amazonaws.com", region);
+    let oidc_base = endpoints::oidc(&region);
+    // x-amz-sso_authn 来自 view.awsapps.com，门户固定在 us-east-1
+    let portal_base = endpoints::sso_portal(endpoints::DEFAULT_REGION);
     
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
+    let client = crate::http_client::client(std::time::Duration::from_secs(30))?;
 
     // Step 1: 注册 OIDC 客户端
-    println!("[SSO Import] Step 1: 注册 OIDC 客户端...");
+    info!("Step 1: 注册 OIDC 客户端...");
     let scopes = vec![
         "codewhisperer:analysis",
         "codewhisperer:completions", 
@@ -99,125 +102,137 @@ pub async fn import_from_sso_token(
         "issuerUrl": START_URL
     });
     
-    let reg_res = client
-        .post(format!("{}/client/register", oidc_base))
-        .header("Content-Type", "application/json")
-        .json(&reg_body)
-        .send()
-        .await
-        .map_err(|e| format!("注册客户端请求失败: {}", e))?;
+    let reg_res = crate::retry::send_once(|_| {
+        client
+            .post(format!("{}/client/register", oidc_base))
+            .header("Content-Type", "application/json")
+            .json(&reg_body)
+    })
+    .await
+    .map_err(|e| AppError::network("注册客户端请求失败", e))?;
     
     if !reg_res.status().is_success() {
+        let status = reg_res.status();
         let text = reg_res.text().await.unwrap_or_default();
-        return Err(format!("注册客户端失败: {}", text));
+        return Err(AppError::from_response("RegisterClient", status, &text));
     }
     
     let reg_data: RegisterClientResponse = reg_res.json().await
-        .map_err(|e| format!("解析注册响应失败: {}", e))?;
+        .map_err(|e| AppError::Parse(format!("解析注册响应失败: {}", e)))?;
     
     let client_id = reg_data.client_id;
     let client_secret = reg_data.client_secret;
-    println!("[SSO Import] 客户端已注册: {}...", &client_id[..20.min(client_id.len())]);
+    let client_secret_expires_at = reg_data.client_secret_expires_at
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|t| t.to_rfc3339());
+    info!("客户端已注册: {}...", &client_id[..20.min(client_id.len())]);
 
     // Step 2: 发起设备授权
-    println!("[SSO Import] Step 2: 发起设备授权...");
+    info!("Step 2: 发起设备授权...");
     let dev_body = serde_json::json!({
         "clientId": client_id,
         "clientSecret": client_secret,
         "startUrl": START_URL
     });
     
-    let dev_res = client
-        .post(format!("{}/device_authorization", oidc_base))
-        .header("Content-Type", "application/json")
-        .json(&dev_body)
-        .send()
-        .await
-        .map_err(|e| format!("设备授权请求失败: {}", e))?;
+    let dev_res = crate::retry::send_once(|_| {
+        client
+            .post(format!("{}/device_authorization", oidc_base))
+            .header("Content-Type", "application/json")
+            .json(&dev_body)
+    })
+    .await
+    .map_err(|e| AppError::network("设备授权请求失败", e))?;
     
     if !dev_res.status().is_success() {
+        let status = dev_res.status();
         let text = dev_res.text().await.unwrap_or_default();
-        return Err(format!("设备授权失败: {}", text));
+        return Err(AppError::from_response("StartDeviceAuthorization", status, &text));
     }
     
     let dev_data: DeviceAuthResponse = dev_res.json().await
-        .map_err(|e| format!("解析设备授权响应失败: {}", e))?;
+        .map_err(|e| AppError::Parse(format!("解析设备授权响应失败: {}", e)))?;
     
     let device_code = dev_data.device_code;
     let user_code = dev_data.user_code;
     let interval = dev_data.interval.unwrap_or(1);
-    println!("[SSO Import] 设备码已获取, user_code: {}", user_code);
+    info!("设备码已获取, user_code: {}", user_code);
 
     // Step 3: 验证 Bearer Token
-    println!("[SSO Import] Step 3: 验证 Bearer Token...");
-    let who_res = client
-        .get(format!("{}/token/whoAmI", PORTAL_BASE))
-        .header("Authorization", format!("Bearer {}", bearer_token))
-        .header("Accept", "application/json")
-        .send()
-        .await
-        .map_err(|e| format!("验证 Token 请求失败: {}", e))?;
+    info!("Step 3: 验证 Bearer Token...");
+    let who_res = crate::retry::send_idempotent(|_| {
+        client
+            .get(format!("{}/token/whoAmI", portal_base))
+            .header("Authorization", format!("Bearer {}", bearer_token))
+            .header("Accept", "application/json")
+    })
+    .await
+    .map_err(|e| AppError::network("验证 Token 请求失败", e))?;
     
     if !who_res.status().is_success() {
         let status = who_res.status();
         let text = who_res.text().await.unwrap_or_default();
-        return Err(format!("Token 验证失败 ({}): {}", status, text));
+        return Err(AppError::from_response("WhoAmI", status, &text));
     }
-    println!("[SSO Import] Bearer Token 验证通过");
+    info!("Bearer Token 验证通过");
 
     // Step 4: 获取设备会话令牌
-    println!("[SSO Import] Step 4: 获取设备会话令牌...");
-    let sess_res = client
-        .post(format!("{}/session/device", PORTAL_BASE))
-        .header("Authorization", format!("Bearer {}", bearer_token))
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({}))
-        .send()
-        .await
-        .map_err(|e| format!("获取设备会话请求失败: {}", e))?;
+    info!("Step 4: 获取设备会话令牌...");
+    let sess_res = crate::retry::send_once(|_| {
+        client
+            .post(format!("{}/session/device", portal_base))
+            .header("Authorization", format!("Bearer {}", bearer_token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({}))
+    })
+    .await
+    .map_err(|e| AppError::network("获取设备会话请求失败", e))?;
     
     if !sess_res.status().is_success() {
+        let status = sess_res.status();
         let text = sess_res.text().await.unwrap_or_default();
-        return Err(format!("获取设备会话失败: {}", text));
+        return Err(AppError::from_response("CreateDeviceSessionToken", status, &text));
     }
     
     let sess_data: DeviceSessionResponse = sess_res.json().await
-        .map_err(|e| format!("解析设备会话响应失败: {}", e))?;
+        .map_err(|e| AppError::Parse(format!("解析设备会话响应失败: {}", e)))?;
     
     let device_session_token = sess_data.token;
-    println!("[SSO Import] 设备会话令牌已获取");
+    info!("设备会话令牌已获取");
 
     // Step 5: 接受用户代码
-    println!("[SSO Import] Step 5: 接受用户代码...");
+    info!("Step 5: 接受用户代码...");
     let accept_body = serde_json::json!({
         "userCode": user_code,
         "userSessionId": device_session_token
     });
     
-    let accept_res = client
-        .post(format!("{}/device_authorization/accept_user_code", oidc_base))
-        .header("Content-Type", "application/json")
-        .header("Referer", "https://view.awsapps.com/")
-        .json(&accept_body)
-        .send()
-        .await
-        .map_err(|e| format!("接受用户代码请求失败: {}", e))?;
+    let accept_res = crate::retry::send_once(|_| {
+        client
+            .post(format!("{}/device_authorization/accept_user_code", oidc_base))
+            .header("Content-Type", "application/json")
+            .header("Referer", "https://view.awsapps.com/")
+            .json(&accept_body)
+    })
+    .await
+    .map_err(|e| AppError::network("接受用户代码请求失败", e))?;
     
     if !accept_res.status().is_success() {
+        let status = accept_res.status();
         let text = accept_res.text().await.unwrap_or_default();
-        return Err(format!("接受用户代码失败: {}", text));
+        return Err(AppError::from_response("AcceptUserCode", status, &text));
     }
     
     let accept_data: AcceptUserCodeResponse = accept_res.json().await
-        .map_err(|e| format!("解析接受用户代码响应失败: {}", e))?;
+        .map_err(|e| AppError::Parse(format!("解析接受用户代码响应失败: {}", e)))?;
     
     let device_context = accept_data.device_context;
-    println!("[SSO Import] 用户代码已接受");
+    info!("用户代码已接受");
 
     // Step 6: 批准授权
     if let Some(ref ctx) = device_context {
         if let Some(ref ctx_id) = ctx.device_context_id {
-            println!("[SSO Import] Step 6: 批准授权...");
+            info!("Step 6: 批准授权...");
             let approve_body = serde_json::json!({
                 "deviceContext": {
                     "deviceContextId": ctx_id,
@@ -227,32 +242,34 @@ pub async fn import_from_sso_token(
                 "userSessionId": device_session_token
             });
             
-            let approve_res = client
-                .post(format!("{}/device_authorization/associate_token", oidc_base))
-                .header("Content-Type", "application/json")
-                .header("Referer", "https://view.awsapps.com/")
-                .json(&approve_body)
-                .send()
-                .await
-                .map_err(|e| format!("批准授权请求失败: {}", e))?;
+            let approve_res = crate::retry::send_once(|_| {
+                client
+                    .post(format!("{}/device_authorization/associate_token", oidc_base))
+                    .header("Content-Type", "application/json")
+                    .header("Referer", "https://view.awsapps.com/")
+                    .json(&approve_body)
+            })
+            .await
+            .map_err(|e| AppError::network("批准授权请求失败", e))?;
             
             if !approve_res.status().is_success() {
+                let status = approve_res.status();
                 let text = approve_res.text().await.unwrap_or_default();
-                return Err(format!("批准授权失败: {}", text));
+                return Err(AppError::from_response("ApproveAuthorization", status, &text));
             }
-            println!("[SSO Import] 授权已批准");
+            info!("授权已批准");
         }
     }
 
     // Step 7: 轮询获取 Token
-    println!("[SSO Import] Step 7: 轮询获取 Token...");
+    info!("Step 7: 轮询获取 Token...");
     let start_time = std::time::Instant::now();
     let timeout = std::time::Duration::from_secs(120);
     let mut current_interval = interval;
     
     let token_data = loop {
         if start_time.elapsed() > timeout {
-            return Err("授权超时，请重试".to_string());
+            return Err(AppError::Other("授权超时，请重试".to_string()));
         }
         
         tokio::time::sleep(std::time::Duration::from_secs(current_interval)).await;
@@ -264,20 +281,21 @@ pub async fn import_from_sso_token(
             "deviceCode": device_code
         });
         
-        let token_res = client
-            .post(format!("{}/token", oidc_base))
-            .header("Content-Type", "application/json")
-            .json(&token_body)
-            .send()
-            .await
-            .map_err(|e| format!("获取 Token 请求失败: {}", e))?;
+        let token_res = crate::retry::send_once(|_| {
+            client
+                .post(format!("{}/token", oidc_base))
+                .header("Content-Type", "application/json")
+                .json(&token_body)
+        })
+        .await
+        .map_err(|e| AppError::network("获取 Token 请求失败", e))?;
         
         let status = token_res.status();
         let text = token_res.text().await.unwrap_or_default();
         
         if status.is_success() {
             let data: TokenResponse = serde_json::from_str(&text)
-                .map_err(|e| format!("解析 Token 响应失败: {}", e))?;
+                .map_err(|e| AppError::Parse(format!("解析 Token 响应失败: {}", e)))?;
             break data;
         }
         
@@ -289,20 +307,19 @@ pub async fn import_from_sso_token(
                         current_interval += 5;
                         continue;
                     }
-                    Some(e) => return Err(format!("Token 获取失败: {}", e)),
-                    None => return Err(format!("Token 获取失败: {}", text)),
+                    _ => return Err(AppError::from_response("CreateToken", status, &text)),
                 }
             }
         }
         
-        return Err(format!("Token 获取失败 ({}): {}", status, text));
+        return Err(AppError::from_response("CreateToken", status, &text));
     };
     
-    println!("[SSO Import] Token 获取成功!");
+    info!("Token 获取成功!");
 
     // Step 8: 获取用量信息并添加账号
     let machine_id = get_machine_id();
-    let cw_client = CodeWhispererClient::new(&machine_id);
+    let cw_client = CodeWhispererClient::new(&machine_id)?;
     
     let usage = cw_client.get_usage_limits(&token_data.access_token).await.ok();
     let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
@@ -336,19 +353,23 @@ pub async fn import_from_sso_token(
         existing.refresh_token = Some(token_data.refresh_token);
         existing.client_id = Some(client_id);
         existing.client_secret = Some(client_secret);
+        existing.client_secret_expires_at = client_secret_expires_at;
         existing.client_id_hash = Some(client_id_hash);
+        existing.auth_method = Some("IdC".to_string());
         existing.region = Some(region);
         existing.expires_at = Some(expires_at.to_rfc3339());
         existing.usage_data = Some(usage_data);
-        existing.status = "正常".to_string();
+        existing.set_status(AccountStatus::active());
         existing.user_id = user_id;
     } else {
         let mut account = Account::new(email.clone(), email.clone());
         account.provider = Some("BuilderId".to_string());
+        account.auth_method = Some("IdC".to_string());
         account.access_token = Some(token_data.access_token);
         account.refresh_token = Some(token_data.refresh_token);
         account.client_id = Some(client_id);
         account.client_secret = Some(client_secret);
+        account.client_secret_expires_at = client_secret_expires_at;
         account.client_id_hash = Some(client_id_hash);
         account.region = Some(region);
         account.expires_at = Some(expires_at.to_rfc3339());
@@ -357,7 +378,7 @@ pub async fn import_from_sso_token(
         store.accounts.insert(0, account);
     }
     
-    store.save_to_file();
+    store.save_to_file()?;
     
     Ok(SsoImportResult {
         success: true,
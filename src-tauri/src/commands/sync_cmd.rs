@@ -0,0 +1,34 @@
+// 批量同步命令
+
+use tauri::{AppHandle, State};
+
+use crate::account_query::AccountQuery;
+use crate::bulk_sync::{self, SyncJobs, SyncSummary};
+use crate::commands::app_settings_cmd::get_refresh_settings;
+use crate::error::AppResult;
+use crate::state::AppState;
+
+/// 按 id 列表或查询条件批量同步账号，进度见 "sync-progress" 事件
+/// concurrency 不指定时沿用后台刷新的并发设置；job_id 由前端生成时可在结束前用 cancel_sync 取消
+#[tauri::command]
+pub async fn sync_accounts(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    ids: Option<Vec<String>>,
+    query: Option<AccountQuery>,
+    concurrency: Option<usize>,
+    job_id: Option<String>,
+) -> AppResult<SyncSummary> {
+    let ids = state.store.lock().unwrap().resolve_ids(ids, query.as_ref())?;
+    let concurrency = concurrency
+        .map(|n| n.clamp(1, 16))
+        .unwrap_or_else(|| get_refresh_settings().2);
+    let job_id = job_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    Ok(bulk_sync::run(app, job_id, ids, concurrency).await)
+}
+
+/// 取消批量同步：未开始的账号不再同步，进行中的会等它完成；不指定 job_id 时取消全部
+#[tauri::command]
+pub fn cancel_sync(jobs: State<SyncJobs>, job_id: Option<String>) -> usize {
+    jobs.cancel(job_id.as_deref())
+}
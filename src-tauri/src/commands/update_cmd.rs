@@ -1,7 +1,6 @@
-// 更新检查命令 - 支持代理
+// 更新检查命令
 
 use serde::{Deserialize, Serialize};
-use reqwest::Proxy;
 
 const UPDATE_URL: &str = "https://github.com/yunfengnobug/kiro-account-manager/releases/latest/download/latest.json";
 
@@ -22,62 +21,6 @@ pub struct UpdateCheckResult {
     pub download_url: Option<String>,
 }
 
-/// 获取 Kiro IDE 设置中的代理
-fn get_proxy_from_kiro_settings() -> Option<String> {
-    #[cfg(target_os = "windows")]
-    let path = std::env::var("APPDATA").ok().map(|appdata| {
-        std::path::PathBuf::from(appdata).join("Kiro").join("User").join("settings.json")
-    });
-    
-    #[cfg(target_os = "macos")]
-    let path = std::env::var("HOME").ok().map(|home| {
-        std::path::PathBuf::from(home)
-            .join("Library")
-            .join("Application Support")
-            .join("Kiro")
-            .join("User")
-            .join("settings.json")
-    });
-    
-    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
-    let path: Option<std::path::PathBuf> = None;
-    
-    path.and_then(|p| {
-        if p.exists() {
-            std::fs::read_to_string(&p).ok()
-        } else {
-            None
-        }
-    })
-    .and_then(|content| {
-        serde_json::from_str::<serde_json::Value>(&content).ok()
-    })
-    .and_then(|json| {
-        json.get("http.proxy")
-            .and_then(|v| v.as_str())
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-    })
-}
-
-/// 构建 HTTP 客户端（支持代理）
-fn build_http_client() -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30));
-    
-    // 尝试从 Kiro 设置获取代理
-    if let Some(proxy_url) = get_proxy_from_kiro_settings() {
-        println!("[Update] 使用代理: {}", proxy_url);
-        let proxy = Proxy::all(&proxy_url)
-            .map_err(|e| format!("代理配置错误: {}", e))?;
-        builder = builder.proxy(proxy);
-    } else {
-        println!("[Update] 未配置代理，直连");
-    }
-    
-    builder.build().map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
-}
-
 /// 获取当前平台的下载 URL
 fn get_platform_download_url(platforms: &serde_json::Value) -> Option<String> {
     #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
@@ -106,10 +49,10 @@ fn get_platform_download_url(platforms: &serde_json::Value) -> Option<String> {
 pub async fn check_update() -> Result<UpdateCheckResult, String> {
     let current_version = env!("CARGO_PKG_VERSION").to_string();
     
-    let client = build_http_client()?;
+    // 代理、CA 证书按应用网络设置
+    let client = crate::http_client::client(std::time::Duration::from_secs(30)).map_err(|e| e.to_string())?;
     
-    let response = client.get(UPDATE_URL)
-        .send()
+    let response = crate::retry::send_idempotent(|_| client.get(UPDATE_URL))
         .await
         .map_err(|e| format!("请求更新信息失败: {}", e))?;
     
@@ -0,0 +1,52 @@
+// 账号库加密命令
+
+use tauri::State;
+use crate::error::AppResult;
+use crate::state::AppState;
+use crate::vault::VaultStatus;
+
+#[tauri::command]
+pub fn get_vault_status(state: State<AppState>) -> VaultStatus {
+    state.store.lock().unwrap().vault_status()
+}
+
+/// 设置主密码并加密现有账号
+#[tauri::command]
+pub fn enable_vault(state: State<AppState>, passphrase: String) -> AppResult<VaultStatus> {
+    let mut store = state.store.lock().unwrap();
+    store.enable_vault(&passphrase)?;
+    Ok(store.vault_status())
+}
+
+#[tauri::command]
+pub fn unlock_vault(state: State<AppState>, passphrase: String) -> AppResult<VaultStatus> {
+    let mut store = state.store.lock().unwrap();
+    store.unlock_vault(&passphrase)?;
+    Ok(store.vault_status())
+}
+
+#[tauri::command]
+pub fn lock_vault(state: State<AppState>) -> AppResult<VaultStatus> {
+    let mut store = state.store.lock().unwrap();
+    store.lock_vault()?;
+    Ok(store.vault_status())
+}
+
+/// 关闭加密，账号恢复明文存储
+#[tauri::command]
+pub fn disable_vault(state: State<AppState>, passphrase: String) -> AppResult<VaultStatus> {
+    let mut store = state.store.lock().unwrap();
+    store.disable_vault(&passphrase)?;
+    Ok(store.vault_status())
+}
+
+#[tauri::command]
+pub fn change_vault_passphrase(
+    state: State<AppState>,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> AppResult<VaultStatus> {
+    let mut store = state.store.lock().unwrap();
+    store.change_vault_passphrase(&old_passphrase, &new_passphrase)?;
+    Ok(store.vault_status())
+}
@@ -3,9 +3,12 @@
 use std::sync::{Mutex, OnceLock};
 use tauri::{AppHandle, Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder};
 use crate::state::AppState;
-use crate::account::Account;
+use crate::account::{Account, SyncOperation};
 use crate::auth::User;
+use crate::error::{AppError, AppResult};
 use crate::providers::web_oauth::{WebOAuthProvider, WebOAuthInitResult};
+use crate::token_manager::TokenManager;
+use tracing::{debug, info, warn};
 
 static PENDING_LOGIN: OnceLock<Mutex<Option<WebOAuthInitResult>>> = OnceLock::new();
 
@@ -14,20 +17,19 @@ fn get_pending_login() -> &'static Mutex<Option<WebOAuthInitResult>> {
 }
 
 #[tauri::command]
-pub async fn web_oauth_initiate(provider: String) -> Result<WebOAuthInitResponse, String> {
-    println!("\n========== web_oauth_initiate START ==========");
-    println!("Provider: {}", provider);
+pub async fn web_oauth_initiate(provider: String) -> AppResult<WebOAuthInitResponse> {
+    info!("Initiating {} login", provider);
     
     if provider != "Google" && provider != "Github" {
-        return Err(format!("Unsupported provider: {}. Use 'Google' or 'Github'", provider));
+        return Err(AppError::Other(format!("Unsupported provider: {}. Use 'Google' or 'Github'", provider)));
     }
 
     let web_provider = WebOAuthProvider::new(&provider);
     
     match web_provider.initiate_login().await {
         Ok(init_result) => {
-            println!("Authorize URL: {}", init_result.authorize_url);
-            println!("State: {}", init_result.state);
+            info!("Authorize URL: {}", init_result.authorize_url);
+            debug!("State: {}", init_result.state);
             
             let response = WebOAuthInitResponse {
                 authorize_url: init_result.authorize_url.clone(),
@@ -35,12 +37,11 @@ pub async fn web_oauth_initiate(provider: String) -> Result<WebOAuthInitResponse
             };
             
             *get_pending_login().lock().unwrap() = Some(init_result);
-            println!("========== web_oauth_initiate SUCCESS ==========\n");
             
             Ok(response)
         },
         Err(e) => {
-            println!("initiate_login FAILED: {}", e);
+            warn!("initiate_login failed: {}", e);
             Err(e)
         }
     }
@@ -57,8 +58,8 @@ pub async fn web_oauth_complete(
     app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     callback_url: String,
-) -> Result<String, String> {
-    println!("[WebOAuth] web_oauth_complete: callback_url={}", &callback_url[..80.min(callback_url.len())]);
+) -> AppResult<String> {
+    info!("Completing login from callback");
     
     let url = url::Url::parse(&callback_url)
         .map_err(|e| format!("Invalid callback URL: {}", e))?;
@@ -90,7 +91,7 @@ pub async fn web_oauth_complete(
         .ok_or("No csrf_token from ExchangeToken")?;
     let refresh_token = &auth_result.refresh_token;
 
-    let portal_client = crate::providers::web_oauth::KiroWebPortalClient::new();
+    let portal_client = crate::providers::web_oauth::KiroWebPortalClient::new()?;
     let user_info = portal_client.get_user_info(
         &auth_result.access_token,
         csrf_token,
@@ -113,38 +114,22 @@ pub async fn web_oauth_complete(
 
     let mut store = state.store.lock().unwrap();
     
-    let account = if let Some(existing) = store.accounts.iter_mut().find(|a| a.email == email) {
-        // 更新现有账号
-        existing.access_token = Some(auth_result.access_token.clone());
-        existing.refresh_token = Some(auth_result.refresh_token.clone());
-        existing.provider = Some(provider.clone());
-        existing.user_id = user_id;
-        existing.expires_at = Some(auth_result.expires_at.clone());
-        existing.profile_arn = auth_result.profile_arn.clone();
-        existing.csrf_token = auth_result.csrf_token.clone();
-        existing.usage_data = Some(usage_data);
-        existing.status = "正常".to_string();
-        existing.clone()
-    } else {
-        // 新建账号
-        let mut account = Account::new(email.clone(), format!("Kiro {} (Web OAuth)", provider));
-        account.access_token = Some(auth_result.access_token.clone());
-        account.refresh_token = Some(auth_result.refresh_token.clone());
-        account.provider = Some(provider.clone());
+    let account = store.find_or_insert(
+        |a| a.email == email,
+        || Account::new(email.clone(), format!("Kiro {} (Web OAuth)", provider)),
+    );
+    TokenManager::apply_login(account, &auth_result);
+    if user_id.is_some() {
         account.user_id = user_id;
-        account.expires_at = Some(auth_result.expires_at.clone());
-        account.profile_arn = auth_result.profile_arn.clone();
-        account.csrf_token = auth_result.csrf_token.clone();
-        account.usage_data = Some(usage_data);
-        store.accounts.insert(0, account.clone());
-        account
-    };
+    }
+    account.usage_data = Some(usage_data);
+    let account = account.clone();
     
-    store.save_to_file();
+    store.save_to_file()?;
     drop(store);
 
     update_auth_state_web(&state, &email, provider, &auth_result.access_token, &auth_result.refresh_token);
-    println!("[WebOAuth] LOGIN SUCCESS: email={}, provider={}", account.email, provider);
+    info!("LOGIN SUCCESS: email={}, provider={}", account.email, provider);
 
     let _ = app_handle.emit("login-success", account.id.clone());
     Ok(format!("Web OAuth login completed for {}", provider))
@@ -154,62 +139,44 @@ pub async fn web_oauth_complete(
 pub async fn web_oauth_refresh(
     state: State<'_, AppState>,
     account_id: String,
-) -> Result<Account, String> {
-    let account = {
-        let store = state.store.lock().unwrap();
-        store.accounts.iter()
-            .find(|a| a.id == account_id)
-            .cloned()
-            .ok_or("Account not found")?
-    };
+) -> AppResult<Account> {
+    let _guard = TokenManager::lock_account(&account_id).await;
+    let account = TokenManager::stored(&state.store, &account_id)?;
 
     // Web OAuth 账号必须有 csrfToken
     if account.csrf_token.is_none() {
-        return Err("This account is not a Web OAuth account (no csrfToken)".to_string());
+        return Err(AppError::Other("This account is not a Web OAuth account (no csrfToken)".to_string()));
     }
 
-    let access_token = account.access_token.as_ref().ok_or("No access_token found")?;
-    let csrf_token = account.csrf_token.as_ref().ok_or("No csrf_token found")?;
-    let provider = account.provider.as_ref().ok_or("No provider found")?;
-    
-    let refresh_token = account.refresh_token.as_ref().ok_or("No refresh_token found")?;
-    let web_provider = WebOAuthProvider::new(provider);
-    let auth_result = web_provider.refresh_token_impl(access_token, csrf_token, refresh_token).await?;
-
-    let new_csrf = auth_result.csrf_token.clone();
-    
-    let portal_client = crate::providers::web_oauth::KiroWebPortalClient::new();
-    let idp = match provider.as_str() {
-        "Github" => "Github",
-        other => other,
-    };
-    let usage = portal_client.get_user_usage_and_limits(
-        &auth_result.access_token,
-        new_csrf.as_deref().unwrap_or(""),
-        refresh_token,
-        idp,
-    ).await.ok();
-    let usage_data = serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null);
+    let mut account = account;
+    if account.auth_method.is_none() {
+        // 旧账号没有记录认证方式，调用这个命令说明是 Web OAuth 登录的
+        account.auth_method = Some("web_oauth".to_string());
+    }
+    let auth_result = TokenManager::refresh(&account).await
+        .map_err(|e| state.store.lock().unwrap().record_refresh_error(&account_id, e))?;
+    let (usage_data, status, usage_error) = TokenManager::fetch_usage(&account, &auth_result).await;
 
     let mut store = state.store.lock().unwrap();
     if let Some(a) = store.accounts.iter_mut().find(|a| a.id == account_id) {
-        a.access_token = Some(auth_result.access_token);
-        a.refresh_token = Some(auth_result.refresh_token);
-        a.csrf_token = auth_result.csrf_token;
-        a.expires_at = Some(auth_result.expires_at);
+        a.auth_method = account.auth_method.clone();
+        TokenManager::apply(a, &auth_result);
         a.usage_data = Some(usage_data);
-        a.status = "正常".to_string();
-        if auth_result.profile_arn.is_some() {
-            a.profile_arn = auth_result.profile_arn;
+        if let Some(status) = status {
+            a.set_status(status);
+        }
+        match usage_error {
+            None => a.record_sync_success(),
+            Some(e) => a.record_error(SyncOperation::Usage, &e),
         }
         
         let result = a.clone();
-        store.save_to_file();
-        println!("[WebOAuth] Account refreshed: {}", result.email);
+        TokenManager::save_refreshed(store, account.refresh_token.as_deref(), &result)?;
+        info!("Account refreshed: {}", result.email);
         return Ok(result);
     }
 
-    Err("Account not found after refresh".to_string())
+    Err(AppError::not_found("Account not found after refresh"))
 }
 
 fn update_auth_state_web(
@@ -235,22 +202,20 @@ fn update_auth_state_web(
 pub async fn web_oauth_login(
     app_handle: AppHandle,
     provider: String,
-) -> Result<WebOAuthLoginResponse, String> {
-    println!("\n========== web_oauth_login START ==========");
-    println!("Provider: {}", provider);
+) -> AppResult<WebOAuthLoginResponse> {
+    info!("Opening {} login window", provider);
     
     if provider != "Google" && provider != "Github" {
-        return Err(format!("Unsupported provider: {}. Use 'Google' or 'Github'", provider));
+        return Err(AppError::Other(format!("Unsupported provider: {}. Use 'Google' or 'Github'", provider)));
     }
 
     let web_provider = WebOAuthProvider::new(&provider);
     let init_result = web_provider.initiate_login().await?;
     
-    println!("Authorize URL: {}", init_result.authorize_url);
-    println!("State: {}", init_result.state);
+    info!("Authorize URL: {}", init_result.authorize_url);
+    debug!("State: {}", init_result.state);
     
     *get_pending_login().lock().unwrap() = Some(init_result.clone());
-    println!("Saved init_result to PENDING_LOGIN, state: {}", init_result.state);
     
     let window_label = format!("oauth_{}", provider.to_lowercase());
     
@@ -260,6 +225,7 @@ pub async fn web_oauth_login(
     
     let app_handle_clone = app_handle.clone();
     let window_label_clone = window_label.clone();
+    let redirect_uri = init_result.redirect_uri.clone();
     
     let _window = WebviewWindowBuilder::new(
         &app_handle,
@@ -272,10 +238,10 @@ pub async fn web_oauth_login(
     .incognito(true)
     .on_navigation(move |url| {
         let url_str = url.as_str();
-        println!("[WebView] Navigation: {}", url_str);
+        debug!("Navigation: {}", url_str);
         
-        if url_str.starts_with("https://app.kiro.dev/signin/oauth") && url_str.contains("code=") {
-            println!("[WebView] Callback URL detected! Emitting event...");
+        if url_str.starts_with(&redirect_uri) && url_str.contains("code=") {
+            info!("Callback URL detected! Emitting event...");
             let _ = app_handle_clone.emit("web-oauth-callback", url_str.to_string());
             
             if let Some(win) = app_handle_clone.get_webview_window(&window_label_clone) {
@@ -288,7 +254,6 @@ pub async fn web_oauth_login(
     .build()
     .map_err(|e| format!("Failed to create auth window: {}", e))?;
     
-    println!("========== web_oauth_login WINDOW OPENED ==========\n");
     
     Ok(WebOAuthLoginResponse {
         window_label,
@@ -4,6 +4,7 @@
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tracing::{debug, info, warn};
 
 /// OAuth 回调结果
 #[derive(Debug, Clone)]
@@ -55,12 +56,12 @@ pub fn register_waiter(state: &str) -> DeepLinkCallbackWaiter {
 
 /// 处理 deep link URL（由 main.rs 调用）
 pub fn handle_deep_link(url: &str) -> bool {
-    println!("[DeepLink] Processing URL: {}", url);
+    debug!("Processing URL: {}", url);
     
     let storage = match PENDING_SENDER.get() {
         Some(s) => s,
         None => {
-            println!("[DeepLink] No pending sender");
+            warn!("No pending sender");
             return false;
         }
     };
@@ -69,7 +70,7 @@ pub fn handle_deep_link(url: &str) -> bool {
     let (expected_state, tx) = match guard.take() {
         Some(s) => s,
         None => {
-            println!("[DeepLink] No pending waiter");
+            warn!("No pending waiter");
             return false;
         }
     };
@@ -78,7 +79,7 @@ pub fn handle_deep_link(url: &str) -> bool {
     let parsed = match url::Url::parse(url) {
         Ok(u) => u,
         Err(e) => {
-            println!("[DeepLink] URL parse error: {}", e);
+            warn!("URL parse error: {}", e);
             let _ = tx.send(Err(format!("Invalid URL: {}", e)));
             return false;
         }
@@ -86,7 +87,7 @@ pub fn handle_deep_link(url: &str) -> bool {
 
     // 检查是否是 kiro:// 协议
     if parsed.scheme() != "kiro" {
-        println!("[DeepLink] Not kiro:// scheme");
+        warn!("Not kiro:// scheme");
         *guard = Some((expected_state, tx)); // 放回去
         return false;
     }
@@ -99,7 +100,7 @@ pub fn handle_deep_link(url: &str) -> bool {
         let desc = params.get("error_description")
             .map(|s| s.to_string())
             .unwrap_or_else(|| "Unknown error".to_string());
-        println!("[DeepLink] OAuth error: {} - {}", error, desc);
+        warn!("OAuth error: {} - {}", error, desc);
         let _ = tx.send(Err(format!("OAuth error: {} - {}", error, desc)));
         return true;
     }
@@ -107,7 +108,7 @@ pub fn handle_deep_link(url: &str) -> bool {
     let code = match params.get("code") {
         Some(c) => c.to_string(),
         None => {
-            println!("[DeepLink] Missing code parameter");
+            warn!("Missing code parameter");
             let _ = tx.send(Err("Missing code parameter".to_string()));
             return true;
         }
@@ -116,7 +117,7 @@ pub fn handle_deep_link(url: &str) -> bool {
     let state = match params.get("state") {
         Some(s) => s.to_string(),
         None => {
-            println!("[DeepLink] Missing state parameter");
+            warn!("Missing state parameter");
             let _ = tx.send(Err("Missing state parameter".to_string()));
             return true;
         }
@@ -124,12 +125,12 @@ pub fn handle_deep_link(url: &str) -> bool {
 
     // 验证 state
     if state != expected_state {
-        println!("[DeepLink] State mismatch: expected {}, got {}", expected_state, state);
+        warn!("State mismatch: expected {}, got {}", expected_state, state);
         let _ = tx.send(Err("State mismatch - possible CSRF attack".to_string()));
         return true;
     }
 
-    println!("[DeepLink] Callback success, code: {}...", &code[..20.min(code.len())]);
+    info!("Callback success");
     let _ = tx.send(Ok(OAuthCallbackResult { code, state }));
     true
 }
@@ -0,0 +1,150 @@
+// 服务地址配置
+// 默认指向生产环境；可用环境变量或应用设置 (endpoints) 覆盖，用于指向本地 mock 做集成测试，或使用其它区域
+// 优先级：环境变量 > 应用设置 > 默认值
+// 地址中的 {region} 会替换为实际区域（OIDC / SSO 门户为登录区域，CodeWhisperer 为 profile ARN 所在区域）
+
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+use crate::commands::app_settings_cmd::get_endpoint_settings;
+
+pub const DEFAULT_REGION: &str = "us-east-1";
+
+/// 应用设置中的地址覆盖，首次解析地址时读取，设置保存后清空
+static SETTINGS: RwLock<Option<EndpointSettings>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    /// Kiro 桌面端认证服务（Social 登录 / 刷新 / 注销）
+    DesktopAuth,
+    /// CodeWhisperer 接口（usage 查询）
+    CodeWhisperer,
+    /// Kiro Web 门户（Web OAuth 的 CBOR 接口）
+    WebPortal,
+    /// AWS SSO OIDC
+    Oidc,
+    /// AWS SSO 门户
+    SsoPortal,
+}
+
+impl Endpoint {
+    fn env_var(self) -> &'static str {
+        match self {
+            Self::DesktopAuth => "KIRO_DESKTOP_AUTH_ENDPOINT",
+            Self::CodeWhisperer => "KIRO_CODEWHISPERER_ENDPOINT",
+            Self::WebPortal => "KIRO_WEB_PORTAL_ENDPOINT",
+            Self::Oidc => "KIRO_OIDC_ENDPOINT",
+            Self::SsoPortal => "KIRO_SSO_PORTAL_ENDPOINT",
+        }
+    }
+
+    fn default_url(self, region: &str) -> String {
+        match self {
+            Self::DesktopAuth => "https://prod.us-east-1.auth.desktop.kiro.dev".to_string(),
+            // us-east-1 以外的 profile 走 Amazon Q 的区域接口
+            Self::CodeWhisperer if region == DEFAULT_REGION => "https://codewhisperer.us-east-1.amazonaws.com".to_string(),
+            Self::CodeWhisperer => format!("https://q.{}.amazonaws.com", region),
+            Self::WebPortal => "https://app.kiro.dev".to_string(),
+            Self::Oidc => format!("https://oidc.{}.amazonaws.com", region),
+            Self::SsoPortal => format!("https://portal.sso.{}.amazonaws.com", region),
+        }
+    }
+
+    fn configured(self, settings: &EndpointSettings) -> Option<&String> {
+        match self {
+            Self::DesktopAuth => settings.desktop_auth.as_ref(),
+            Self::CodeWhisperer => settings.codewhisperer.as_ref(),
+            Self::WebPortal => settings.web_portal.as_ref(),
+            Self::Oidc => settings.oidc.as_ref(),
+            Self::SsoPortal => settings.sso_portal.as_ref(),
+        }
+    }
+}
+
+/// 应用设置中的服务地址覆盖，未设置的使用默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointSettings {
+    pub desktop_auth: Option<String>,
+    pub codewhisperer: Option<String>,
+    pub web_portal: Option<String>,
+    pub oidc: Option<String>,
+    pub sso_portal: Option<String>,
+}
+
+impl EndpointSettings {
+    /// 保存设置前检查地址格式
+    pub fn validate(&self) -> Result<(), String> {
+        let all = [&self.desktop_auth, &self.codewhisperer, &self.web_portal, &self.oidc, &self.sso_portal];
+        for url in all.into_iter().flatten().filter(|u| !u.trim().is_empty()) {
+            let parsed = url::Url::parse(&url.replace("{region}", DEFAULT_REGION))
+                .map_err(|e| format!("服务地址无效 ({}): {}", url, e))?;
+            if parsed.scheme() != "http" && parsed.scheme() != "https" {
+                return Err(format!("服务地址只支持 http / https: {}", url));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn settings() -> EndpointSettings {
+    if let Some(settings) = SETTINGS.read().unwrap().as_ref() {
+        return settings.clone();
+    }
+    let settings = get_endpoint_settings();
+    *SETTINGS.write().unwrap() = Some(settings.clone());
+    settings
+}
+
+/// 应用设置保存后调用，下次解析地址时重新读取
+pub fn reload_settings() {
+    *SETTINGS.write().unwrap() = None;
+}
+
+/// 解析服务地址，去掉末尾的 /
+pub fn url(endpoint: Endpoint, region: &str) -> String {
+    let template = std::env::var(endpoint.env_var())
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .or_else(|| endpoint.configured(&settings()).filter(|v| !v.trim().is_empty()).cloned());
+    let url = match template {
+        Some(template) => template.trim().replace("{region}", region),
+        None => endpoint.default_url(region),
+    };
+    url.trim_end_matches('/').to_string()
+}
+
+pub fn desktop_auth() -> String {
+    url(Endpoint::DesktopAuth, DEFAULT_REGION)
+}
+
+pub fn web_portal() -> String {
+    url(Endpoint::WebPortal, DEFAULT_REGION)
+}
+
+/// Web OAuth 登录的回调地址
+pub fn web_oauth_redirect_uri() -> String {
+    format!("{}/signin/oauth", web_portal())
+}
+
+pub fn oidc(region: &str) -> String {
+    url(Endpoint::Oidc, region)
+}
+
+pub fn sso_portal(region: &str) -> String {
+    url(Endpoint::SsoPortal, region)
+}
+
+/// profile ARN 所在区域的 CodeWhisperer 接口，没有 ARN 时用 us-east-1
+pub fn codewhisperer(profile_arn: Option<&str>) -> String {
+    url(Endpoint::CodeWhisperer, profile_arn.and_then(region_of_profile_arn).unwrap_or(DEFAULT_REGION))
+}
+
+/// arn:aws:codewhisperer:{region}:{account}:profile/{id}
+pub fn region_of_profile_arn(arn: &str) -> Option<&str> {
+    let mut parts = arn.split(':');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some("arn"), Some(_), Some("codewhisperer"), Some(region)) if !region.is_empty() => Some(region),
+        _ => None,
+    }
+}
@@ -0,0 +1,225 @@
+// 统一错误类型
+// API 客户端、Provider、TokenManager 和账号相关命令都返回 AppError，
+// 前端收到的是 {code, message, details}，按 code 判断错误种类，不再解析错误文本
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// 连接失败、超时等网络层错误
+    Network(String),
+    /// access token / refresh token 已过期或无效，或客户端注册过期，需要重新登录
+    AuthExpired(String),
+    /// 授权码或 refresh token 被服务端拒绝 (invalid_grant)
+    InvalidGrant(String),
+    /// 账号被暂停或封禁
+    AccountSuspended { reason: String },
+    RateLimited { message: String, retry_after_secs: Option<u64> },
+    /// 响应无法解析
+    Parse(String),
+    Io(String),
+    NotFound(String),
+    /// 其它 HTTP 错误（5xx、未归类的 4xx）
+    Http { status: u16, message: String },
+    Other(String),
+}
+
+impl AppError {
+    /// 稳定的错误代码，前端据此判断
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Network(_) => "network",
+            Self::AuthExpired(_) => "auth-expired",
+            Self::InvalidGrant(_) => "invalid-grant",
+            Self::AccountSuspended { .. } => "account-suspended",
+            Self::RateLimited { .. } => "rate-limited",
+            Self::Parse(_) => "parse",
+            Self::Io(_) => "io",
+            Self::NotFound(_) => "not-found",
+            Self::Http { .. } => "http",
+            Self::Other(_) => "other",
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            Self::AccountSuspended { reason } => Some(json!({ "reason": reason })),
+            Self::RateLimited { retry_after_secs: Some(secs), .. } => Some(json!({ "retryAfterSecs": secs })),
+            Self::Http { status, .. } => Some(json!({ "status": status })),
+            _ => None,
+        }
+    }
+
+    /// 需要重新登录
+    pub fn is_auth(&self) -> bool {
+        matches!(self, Self::AuthExpired(_) | Self::InvalidGrant(_))
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        matches!(self, Self::Http { status, .. } if *status >= 500)
+    }
+
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Self::NotFound(what.into())
+    }
+
+    /// 发送请求失败，context 为接口名
+    pub fn network(context: &str, error: reqwest::Error) -> Self {
+        Self::Network(format!("{}: {}", context, error))
+    }
+
+    /// 文件或数据库读写失败，context 说明在做什么
+    pub fn io(context: &str, error: impl fmt::Display) -> Self {
+        Self::Io(format!("{}: {}", context, error))
+    }
+
+    /// 按 HTTP 状态码和响应内容归类失败的请求，context 为接口名，用于错误信息
+    pub fn from_response(context: &str, status: reqwest::StatusCode, body: &str) -> Self {
+        let message = format!("{} failed ({}): {}", context, status, body);
+        let error = serde_json::from_str::<Value>(body).ok()
+            .and_then(|j| j.get("error").and_then(|v| v.as_str()).map(|s| s.to_string()));
+
+        if error.as_deref() == Some("invalid_grant") || body.contains("invalid_grant") {
+            return Self::InvalidGrant(message);
+        }
+        match status.as_u16() {
+            401 | 403 => Self::AuthExpired(message),
+            404 => Self::NotFound(message),
+            429 => Self::RateLimited { message, retry_after_secs: None },
+            status => Self::Http { status, message },
+        }
+    }
+}
+
+/// GetUsageLimits 失败：只有这个接口的响应里 reason 字段表示账号被暂停，其余按 from_response 归类
+pub fn usage_error(status: reqwest::StatusCode, body: &str) -> AppError {
+    let reason = serde_json::from_str::<Value>(body).ok()
+        .and_then(|j| j.get("reason").and_then(|v| v.as_str()).map(|s| s.to_string()));
+    match reason {
+        Some(reason) => AppError::AccountSuspended { reason },
+        None => AppError::from_response("GetUsageLimits", status, body),
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AccountSuspended { reason } => write!(f, "账号已被暂停: {}", reason),
+            Self::RateLimited { message, .. } | Self::Http { message, .. } => f.write_str(message),
+            Self::Network(m) | Self::AuthExpired(m) | Self::InvalidGrant(m) | Self::Parse(m)
+            | Self::Io(m) | Self::NotFound(m) | Self::Other(m) => f.write_str(m),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+/// 序列化为 {code, message, details}
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+/// 仍返回字符串错误的函数（加密、设置校验等）经 ? 进入时归为 Other
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_decode() {
+            Self::Parse(format!("解析响应失败: {}", error))
+        } else {
+            Self::Network(format!("网络错误: {}", error))
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Parse(format!("Parse failed: {}", error))
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            Self::NotFound(error.to_string())
+        } else {
+            Self::Io(error.to_string())
+        }
+    }
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn from_response_classifies_by_status_and_body() {
+        let error = AppError::from_response("CreateToken", StatusCode::BAD_REQUEST, r#"{"error":"invalid_grant"}"#);
+        assert_eq!(error.code(), "invalid-grant");
+        assert!(error.is_auth());
+
+        assert_eq!(AppError::from_response("X", StatusCode::UNAUTHORIZED, "").code(), "auth-expired");
+        assert_eq!(AppError::from_response("X", StatusCode::NOT_FOUND, "").code(), "not-found");
+        assert_eq!(AppError::from_response("X", StatusCode::TOO_MANY_REQUESTS, "").code(), "rate-limited");
+        let error = AppError::from_response("X", StatusCode::BAD_GATEWAY, "");
+        assert!(error.is_server_error());
+        assert_eq!(error.details(), Some(json!({ "status": 502 })));
+    }
+
+    #[test]
+    fn reason_only_means_suspended_on_usage() {
+        let body = r#"{"message":"Invalid request","reason":"INVALID_CLIENT"}"#;
+        assert_eq!(AppError::from_response("RefreshToken", StatusCode::BAD_REQUEST, body).code(), "http");
+        assert_eq!(
+            usage_error(StatusCode::FORBIDDEN, body),
+            AppError::AccountSuspended { reason: "INVALID_CLIENT".to_string() }
+        );
+        assert_eq!(usage_error(StatusCode::FORBIDDEN, "{}").code(), "auth-expired");
+    }
+
+    #[test]
+    fn file_failures_are_io_errors() {
+        // 父路径是文件，无法创建目录
+        let file = std::env::temp_dir().join(format!("kiro-io-test-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&file, "").unwrap();
+        let error = crate::fs_util::write_atomic(&file.join("accounts.json"), b"[]").unwrap_err();
+        assert_eq!(error.code(), "io");
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[test]
+    fn serializes_code_message_details() {
+        let value = serde_json::to_value(AppError::AccountSuspended { reason: "TEMPORARILY_SUSPENDED".to_string() }).unwrap();
+        assert_eq!(value["code"], "account-suspended");
+        assert_eq!(value["details"]["reason"], "TEMPORARILY_SUSPENDED");
+        assert!(value["message"].as_str().unwrap().contains("TEMPORARILY_SUSPENDED"));
+    }
+}
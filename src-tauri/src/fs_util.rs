@@ -0,0 +1,111 @@
+// 文件读写工具
+
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::error::{AppError, AppResult};
+
+/// 原子写入：先写同目录临时文件并刷盘，再 rename 覆盖目标文件
+/// 写入中途崩溃时目标文件保持原样
+pub fn write_atomic(path: &Path, content: &[u8]) -> AppResult<()> {
+    let parent = path.parent().ok_or("Invalid file path")?;
+    std::fs::create_dir_all(parent)
+        .map_err(|e| AppError::io("Failed to create directory", e))?;
+
+    let file_name = path.file_name()
+        .ok_or("Invalid file path")?
+        .to_string_lossy();
+    let temp_path = parent.join(format!("{}.tmp", file_name));
+
+    let mut file = std::fs::File::create(&temp_path)
+        .map_err(|e| AppError::io("Failed to create temp file", e))?;
+    file.write_all(content)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&temp_path);
+            AppError::io("Failed to write temp file", e)
+        })?;
+    drop(file);
+
+    std::fs::rename(&temp_path, path)
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&temp_path);
+            AppError::io("Failed to rename file", e)
+        })
+}
+
+// ============================================================
+// 进程间文件锁
+// ============================================================
+
+/// 运行时加锁的等待上限：调用方多在 tokio 线程上且持有账号库的 Mutex，不能长时间阻塞
+/// 其他进程只在读写文件期间持锁，等不到说明对方卡住了，直接报错让用户重试
+const LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+/// 启动时加锁的等待上限，此时还没有其他任务在等待
+pub const STARTUP_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+/// 进程间咨询锁：锁住同目录下的 `<文件名>.lock`，Drop 时释放
+/// 锁文件与数据文件分开，rename 覆盖数据文件不会影响锁
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// 获取 path 对应的排他锁，最多等待 LOCK_TIMEOUT，超时返回错误
+    pub fn acquire(path: &Path) -> AppResult<Self> {
+        Self::acquire_within(path, LOCK_TIMEOUT)
+    }
+
+    /// 获取 path 对应的排他锁，最多等待 timeout
+    pub fn acquire_within(path: &Path, timeout: Duration) -> AppResult<Self> {
+        let parent = path.parent().ok_or("Invalid file path")?;
+        std::fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create directory", e))?;
+        let file_name = path.file_name()
+            .ok_or("Invalid file path")?
+            .to_string_lossy();
+        let lock_path = parent.join(format!("{}.lock", file_name));
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| AppError::io("Failed to open lock file", e))?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => return Ok(Self { file }),
+                Err(_) if started.elapsed() < timeout => std::thread::sleep(LOCK_RETRY_INTERVAL),
+                Err(e) => return Err(AppError::io(&format!("{} 正被其他进程占用，请稍后重试", file_name), e)),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+/// 文件的修改时间和大小，用于检测其他进程的改动（文件不存在时为 None）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+pub fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some(FileStamp {
+        modified: meta.modified().ok()?,
+        len: meta.len(),
+    })
+}
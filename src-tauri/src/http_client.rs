@@ -0,0 +1,180 @@
+// 统一的 HTTP 客户端
+// 所有请求都从这里创建 reqwest 客户端，统一应用代理、额外信任的 CA 和超时设置
+// 代理模式：
+// - auto（默认）: Kiro IDE settings.json 的 http.proxy，未配置时使用系统代理和环境变量 (HTTP_PROXY / HTTPS_PROXY / NO_PROXY)
+// - manual: 应用设置中的代理地址，支持 http:// https:// socks5:// socks5h://
+// - none: 直连
+
+use reqwest::{Certificate, Client, ClientBuilder, NoProxy, Proxy};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::commands::app_settings_cmd::get_network_settings;
+use crate::error::{AppError, AppResult};
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+/// 解析后的网络设置，首次创建客户端时读取，保存设置后清空重新读取
+static SETTINGS: RwLock<Option<NetworkSettings>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProxyMode {
+    #[default]
+    Auto,
+    Manual,
+    None,
+}
+
+impl ProxyMode {
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.trim()).filter(|v| !v.is_empty()) {
+            None | Some("auto") => Ok(Self::Auto),
+            Some("manual") => Ok(Self::Manual),
+            Some("none") => Ok(Self::None),
+            Some(other) => Err(format!("未知的代理模式: {}", other)),
+        }
+    }
+}
+
+/// 网络设置（来自应用设置，空字符串视为未设置）
+#[derive(Debug, Clone, Default)]
+pub struct NetworkSettings {
+    pub proxy_mode: ProxyMode,
+    pub proxy_url: Option<String>,
+    /// 逗号分隔的主机 / 域名 / CIDR，支持 * 和 .example.com
+    pub no_proxy: Option<String>,
+    /// PEM 格式的 CA 证书文件（可包含多个证书），在系统根证书之外额外信任
+    pub ca_bundle_path: Option<String>,
+    pub connect_timeout_secs: Option<u64>,
+    /// 覆盖各客户端自己的请求超时
+    pub request_timeout_secs: Option<u64>,
+}
+
+fn settings() -> NetworkSettings {
+    if let Some(settings) = SETTINGS.read().unwrap().as_ref() {
+        return settings.clone();
+    }
+    let settings = get_network_settings();
+    *SETTINGS.write().unwrap() = Some(settings.clone());
+    settings
+}
+
+/// 应用设置保存后调用，下次创建客户端时重新读取
+pub fn reload_settings() {
+    *SETTINGS.write().unwrap() = None;
+}
+
+/// 测试中直连本地 mock 服务，不读取应用设置和系统代理
+#[cfg(test)]
+pub fn use_direct_connection_for_tests() {
+    *SETTINGS.write().unwrap() = Some(NetworkSettings { proxy_mode: ProxyMode::None, ..Default::default() });
+}
+
+/// 按当前设置创建客户端构建器，调用方可继续添加 cookie、user agent 等配置
+/// default_timeout 为该客户端的默认请求超时
+/// 设置在运行时无法应用（如 CA 文件被删除）时返回错误，不退回直连，避免绕过配置的代理
+pub fn builder(default_timeout: Duration) -> AppResult<ClientBuilder> {
+    try_builder(&settings(), default_timeout)
+        .map_err(|e| AppError::Network(format!("网络设置无法应用: {}", e)))
+}
+
+/// 按当前设置创建客户端
+pub fn client(default_timeout: Duration) -> AppResult<Client> {
+    build(builder(default_timeout)?)
+}
+
+/// 构建调用方定制过的构建器
+pub fn build(builder: ClientBuilder) -> AppResult<Client> {
+    builder.build()
+        .map_err(|e| AppError::Network(format!("创建 HTTP 客户端失败: {}", e)))
+}
+
+/// 检查网络设置能否创建客户端，保存设置前调用
+pub fn validate(settings: &NetworkSettings) -> Result<(), String> {
+    try_builder(settings, Duration::from_secs(30))?
+        .build()
+        .map(|_| ())
+        .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))
+}
+
+fn try_builder(settings: &NetworkSettings, default_timeout: Duration) -> Result<ClientBuilder, String> {
+    let timeout = settings.request_timeout_secs.map(Duration::from_secs).unwrap_or(default_timeout);
+    let connect_timeout = settings.connect_timeout_secs.unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS);
+    let mut builder = Client::builder()
+        .timeout(timeout)
+        .connect_timeout(Duration::from_secs(connect_timeout.max(1)));
+
+    let no_proxy = settings.no_proxy.as_deref()
+        .and_then(NoProxy::from_string)
+        .or_else(NoProxy::from_env);
+    match settings.proxy_mode {
+        ProxyMode::None => builder = builder.no_proxy(),
+        ProxyMode::Manual => {
+            let url = settings.proxy_url.as_deref().ok_or("手动代理模式需要填写代理地址")?;
+            builder = builder.proxy(proxy_for(url)?.no_proxy(no_proxy));
+        }
+        ProxyMode::Auto => {
+            // 未配置时由 reqwest 读取系统代理和环境变量
+            if let Some(url) = get_proxy_from_kiro_settings() {
+                builder = builder.proxy(proxy_for(&url)?.no_proxy(no_proxy));
+            }
+        }
+    }
+
+    if let Some(path) = settings.ca_bundle_path.as_deref() {
+        for certificate in load_ca_bundle(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder)
+}
+
+fn proxy_for(url: &str) -> Result<Proxy, String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("代理地址无效 ({}): {}", url, e))?;
+    if !PROXY_SCHEMES.contains(&parsed.scheme()) {
+        return Err(format!("不支持的代理协议: {}（支持 http / https / socks5 / socks5h）", parsed.scheme()));
+    }
+    Proxy::all(url).map_err(|e| format!("代理配置错误: {}", e))
+}
+
+fn load_ca_bundle(path: &str) -> Result<Vec<Certificate>, String> {
+    let pem = std::fs::read(path).map_err(|e| format!("读取 CA 证书失败 ({}): {}", path, e))?;
+    let certificates = Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("解析 CA 证书失败 ({}): {}", path, e))?;
+    if certificates.is_empty() {
+        return Err(format!("CA 证书文件中没有证书: {}", path));
+    }
+    Ok(certificates)
+}
+
+/// 获取 Kiro IDE 设置中的代理
+pub fn get_proxy_from_kiro_settings() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let path = std::env::var("APPDATA").ok().map(|appdata| {
+        std::path::PathBuf::from(appdata).join("Kiro").join("User").join("settings.json")
+    });
+
+    #[cfg(target_os = "macos")]
+    let path = std::env::var("HOME").ok().map(|home| {
+        std::path::PathBuf::from(home)
+            .join("Library")
+            .join("Application Support")
+            .join("Kiro")
+            .join("User")
+            .join("settings.json")
+    });
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let path: Option<std::path::PathBuf> = None;
+
+    path.filter(|p| p.exists())
+        .and_then(|p| std::fs::read_to_string(&p).ok())
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|json| {
+            json.get("http.proxy")
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        })
+}
@@ -0,0 +1,345 @@
+// Kiro IDE token 文件同步
+// IDE 会自行刷新 ~/.aws/sso/cache/kiro-auth-token.json 并轮换 refresh token，账号库里的旧 token 随之失效
+// 这里监听该文件：变化时按 refresh token / access token / JWT 身份匹配已存账号，写回新的 token 和过期时间；
+// 只能按 profileArn / clientIdHash 推断时仅报告，不改动账号。结果通过 "ide-active-account" 事件告诉前端
+// 反过来，本应用刷新了 IDE 正在使用的会话后，也把新 token 写回 IDE 文件（见 push_if_active）
+
+use chrono::{SecondsFormat, Utc};
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::account::{Account, AccountState, AccountStatus};
+use crate::account_query::parse_time;
+use crate::error::AppError;
+use crate::fs_util::write_atomic;
+use crate::jwt;
+use crate::token_manager::is_placeholder_email;
+use crate::kiro::{ClientRegistration, KiroLocalToken, TOKEN_FILE_NAME};
+use crate::state::AppState;
+use tracing::{info, warn};
+
+/// IDE 一次写入会触发多个事件，合并这段时间内的事件
+const DEBOUNCE_MS: u64 = 500;
+/// 缓存目录还不存在（IDE 从未登录过）时的检查间隔
+const DIR_POLL_SECS: u64 = 30;
+
+/// 最近一次对齐的结果
+static ACTIVE: Mutex<Option<IdeActiveAccount>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MatchedBy {
+    RefreshToken,
+    AccessToken,
+    Identity,
+    ProfileArn,
+    ClientIdHash,
+}
+
+impl MatchedBy {
+    /// 能确认 IDE 登录的就是该账号本人，才允许用 IDE 的 token 覆盖账号
+    /// profileArn / clientIdHash 会被不同用户共用（所有 BuilderId 用户的 start URL hash 相同）
+    fn is_verified(self) -> bool {
+        matches!(self, Self::RefreshToken | Self::AccessToken | Self::Identity)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdeActiveAccount {
+    /// IDE 是否已登录（token 文件存在且可解析）
+    pub signed_in: bool,
+    /// IDE 当前使用的已存账号，未匹配到时为 None
+    pub account_id: Option<String>,
+    pub email: Option<String>,
+    pub matched_by: Option<MatchedBy>,
+    /// 本次是否用 IDE 的 token 更新了已存账号
+    pub updated: bool,
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    let home = std::env::var("USERPROFILE")
+        .or_else(|_| std::env::var("HOME"))
+        .ok()?;
+    Some(Path::new(&home).join(".aws").join("sso").join("cache"))
+}
+
+fn read_token() -> Option<KiroLocalToken> {
+    let content = std::fs::read_to_string(cache_dir()?.join(TOKEN_FILE_NAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 最近一次对齐的结果（还没对齐过时为空）
+pub fn active_account() -> IdeActiveAccount {
+    ACTIVE.lock().unwrap().clone().unwrap_or_default()
+}
+
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || run(app));
+}
+
+fn run(app: AppHandle) {
+    let Some(dir) = cache_dir() else {
+        warn!("Cannot find home directory, not watching");
+        return;
+    };
+    reconcile(&app);
+    while !dir.is_dir() {
+        std::thread::sleep(Duration::from_secs(DIR_POLL_SECS));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            warn!("Failed to create watcher: {}", e);
+            return;
+        }
+    };
+    // 监听目录而不是文件：IDE 和本应用都是写临时文件再 rename，文件本身的监听会丢失
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        warn!("Failed to watch {}: {}", dir.display(), e);
+        return;
+    }
+    info!("Watching {}", dir.display());
+    // 等待目录期间文件可能已经写入
+    reconcile(&app);
+
+    while let Ok(event) = rx.recv() {
+        if !is_token_event(&event) {
+            continue;
+        }
+        while rx.recv_timeout(Duration::from_millis(DEBOUNCE_MS)).is_ok() {}
+        reconcile(&app);
+    }
+}
+
+fn is_token_event(event: &notify::Result<notify::Event>) -> bool {
+    match event {
+        Ok(event) => event.paths.iter().any(|p| p.file_name().is_some_and(|n| n == TOKEN_FILE_NAME)),
+        Err(_) => false,
+    }
+}
+
+/// 读取 IDE token 并与账号库对齐，结果通过 "ide-active-account" 事件通知前端
+pub fn reconcile(app: &AppHandle) -> IdeActiveAccount {
+    let token = read_token();
+    let state = app.state::<AppState>();
+    let active = {
+        let mut store = state.store.lock().unwrap();
+        // 锁定时拿不到 token 明文，沿用上次结果，解锁后前端会重新获取
+        if store.vault_status().locked {
+            return active_account();
+        }
+        let _ = store.reload_if_changed();
+
+        match &token {
+            None => IdeActiveAccount::default(),
+            Some(token) => match find_account(&store.accounts, token) {
+                None => IdeActiveAccount { signed_in: true, ..Default::default() },
+                Some((index, matched_by)) => {
+                    let updated = matched_by.is_verified() && adopt_token(&mut store.accounts[index], token);
+                    let account = &store.accounts[index];
+                    let active = IdeActiveAccount {
+                        signed_in: true,
+                        account_id: Some(account.id.clone()),
+                        email: Some(account.email.clone()),
+                        matched_by: Some(matched_by),
+                        updated,
+                    };
+                    if updated {
+                        if let Err(e) = store.save_to_file() {
+                            warn!("Failed to save accounts: {}", e);
+                        }
+                    }
+                    active
+                }
+            },
+        }
+    };
+
+    if active.updated {
+        info!("Updated {} from IDE token ({:?})", active.email.as_deref().unwrap_or(""), active.matched_by);
+    }
+    *ACTIVE.lock().unwrap() = Some(active.clone());
+    let _ = app.emit("ide-active-account", active.clone());
+    active
+}
+
+/// 按 refresh token、access token、JWT 身份 (sub / email)、profileArn、clientIdHash 的顺序匹配
+/// profileArn 和 clientIdHash 会被多个账号共用（Social 账号共用同一个 profile，同一 start URL 的 IdC 账号共用同一个 hash），
+/// 所以只在同 provider 下有唯一候选时才算匹配，且只用于报告 IDE 当前账号
+fn find_account(accounts: &[Account], token: &KiroLocalToken) -> Option<(usize, MatchedBy)> {
+    if let Some(refresh_token) = token.refresh_token.as_deref() {
+        if let Some(index) = accounts.iter().position(|a| a.refresh_token.as_deref() == Some(refresh_token)) {
+            return Some((index, MatchedBy::RefreshToken));
+        }
+    }
+    if let Some(access_token) = token.access_token.as_deref() {
+        if let Some(index) = accounts.iter().position(|a| a.access_token.as_deref() == Some(access_token)) {
+            return Some((index, MatchedBy::AccessToken));
+        }
+    }
+
+    let same_provider = |a: &Account| token.provider.is_none() || a.provider == token.provider;
+    let claims = token.id_token.as_deref()
+        .and_then(jwt::decode_claims)
+        .or_else(|| token.access_token.as_deref().and_then(jwt::decode_claims));
+    if let Some(claims) = claims {
+        let same_identity = |a: &Account| {
+            let same_subject = claims.subject.is_some() && a.user_id == claims.subject;
+            let same_email = claims.email.as_deref()
+                .is_some_and(|email| !is_placeholder_email(email) && a.email.eq_ignore_ascii_case(email));
+            same_provider(a) && (same_subject || same_email)
+        };
+        if let Some(index) = unique_position(accounts, same_identity) {
+            return Some((index, MatchedBy::Identity));
+        }
+    }
+    if let Some(arn) = token.profile_arn.as_deref() {
+        if let Some(index) = unique_position(accounts, |a| same_provider(a) && a.profile_arn.as_deref() == Some(arn)) {
+            return Some((index, MatchedBy::ProfileArn));
+        }
+    }
+    if let Some(hash) = token.client_id_hash.as_deref() {
+        if let Some(index) = unique_position(accounts, |a| same_provider(a) && a.client_id_hash.as_deref() == Some(hash)) {
+            return Some((index, MatchedBy::ClientIdHash));
+        }
+    }
+    None
+}
+
+fn unique_position<F: Fn(&Account) -> bool>(accounts: &[Account], predicate: F) -> Option<usize> {
+    let mut matches = accounts.iter().enumerate().filter(|(_, a)| predicate(a)).map(|(i, _)| i);
+    let first = matches.next()?;
+    match matches.next() {
+        Some(_) => None,
+        None => Some(first),
+    }
+}
+
+/// 用 IDE 的 token 更新账号；本地 token 更新（过期时间更晚）时保留本地的，返回是否有修改
+fn adopt_token(account: &mut Account, token: &KiroLocalToken) -> bool {
+    let (Some(access_token), Some(refresh_token)) = (&token.access_token, &token.refresh_token) else {
+        return false;
+    };
+    if account.access_token.as_ref() == Some(access_token) && account.refresh_token.as_ref() == Some(refresh_token) {
+        return false;
+    }
+    let ide_expires_at = token.expires_at.as_deref().and_then(parse_time);
+    if let (Some(ours), Some(theirs)) = (account.expires_at_time(), ide_expires_at) {
+        if theirs < ours {
+            return false;
+        }
+    }
+
+    account.access_token = Some(access_token.clone());
+    account.refresh_token = Some(refresh_token.clone());
+    if let Some(expires_at) = ide_expires_at {
+        account.expires_at = Some(expires_at.format("%Y/%m/%d %H:%M:%S").to_string());
+    }
+    if account.profile_arn.is_none() {
+        account.profile_arn = token.profile_arn.clone();
+    }
+    // IDE 刚刷新成功，token 可用
+    if account.status.state != AccountState::Banned {
+        account.set_status(AccountStatus::active());
+    }
+    account.record_refresh_success();
+    true
+}
+
+/// 账号重新注册 IdC 客户端后，如果 IDE 正在使用它，把新的注册信息写进 IDE 的 {clientIdHash}.json
+pub fn push_registration_if_active(account: &Account) {
+    if active_account().account_id.as_deref() != Some(account.id.as_str()) {
+        return;
+    }
+    let (Some(dir), Some(hash)) = (cache_dir(), &account.client_id_hash) else {
+        return;
+    };
+    let Ok(registration) = ClientRegistration::from_account(account) else {
+        return;
+    };
+
+    let result = serde_json::to_string_pretty(&registration)
+        .map_err(AppError::from)
+        .and_then(|content| write_atomic(&dir.join(format!("{}.json", hash)), content.as_bytes()));
+    if let Err(e) = result {
+        warn!("Failed to update IDE client registration: {}", e);
+    }
+}
+
+/// 本应用刷新了账号 token 后调用：IDE 文件里还是刷新前的 refresh token，说明 IDE 用的是同一个会话，
+/// 旧 token 可能已被轮换失效，把新 token 写回去（保留文件中的其它字段）
+pub fn push_if_active(previous_refresh_token: Option<&str>, account: &Account) {
+    let Some(previous) = previous_refresh_token else { return };
+    let Some(path) = cache_dir().map(|d| d.join(TOKEN_FILE_NAME)) else { return };
+    let Some(mut value) = std::fs::read_to_string(&path).ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+    else {
+        return;
+    };
+    if value.get("refreshToken").and_then(|v| v.as_str()) != Some(previous) {
+        return;
+    }
+    let Some(fields) = value.as_object_mut() else { return };
+    fields.insert("accessToken".to_string(), serde_json::json!(account.access_token));
+    fields.insert("refreshToken".to_string(), serde_json::json!(account.refresh_token));
+    if let Some(expires_at) = account.expires_at_time() {
+        fields.insert(
+            "expiresAt".to_string(),
+            serde_json::json!(expires_at.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Millis, true)),
+        );
+    }
+
+    let result = serde_json::to_string_pretty(&value)
+        .map_err(AppError::from)
+        .and_then(|content| write_atomic(&path, content.as_bytes()));
+    match result {
+        Ok(()) => info!("Pushed refreshed token of {} to IDE", account.email),
+        Err(e) => warn!("Failed to update IDE token: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(email: &str, refresh_token: &str) -> Account {
+        let mut account = Account::new(email.to_string(), email.to_string());
+        account.provider = Some("BuilderId".to_string());
+        account.client_id_hash = Some("shared-hash".to_string());
+        account.refresh_token = Some(refresh_token.to_string());
+        account
+    }
+
+    fn ide_token(refresh_token: &str) -> KiroLocalToken {
+        serde_json::from_value(serde_json::json!({
+            "accessToken": "ide-access",
+            "refreshToken": refresh_token,
+            "provider": "BuilderId",
+            "clientIdHash": "shared-hash",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn exact_refresh_token_match_is_adopted() {
+        let accounts = vec![stored("a@example.com", "rt-a")];
+        let (index, matched_by) = find_account(&accounts, &ide_token("rt-a")).unwrap();
+        assert_eq!(index, 0);
+        assert!(matched_by.is_verified());
+    }
+
+    #[test]
+    fn shared_client_id_hash_is_reported_but_not_adopted() {
+        let accounts = vec![stored("a@example.com", "rt-a")];
+        let (_, matched_by) = find_account(&accounts, &ide_token("rt-other-user")).unwrap();
+        assert_eq!(matched_by, MatchedBy::ClientIdHash);
+        assert!(!matched_by.is_verified());
+    }
+}
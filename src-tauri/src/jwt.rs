@@ -0,0 +1,80 @@
+// JWT claims 解码
+// 只解析 payload，不验证签名：token 直接来自 AWS / Kiro 的接口，这里只用来读取身份信息和过期时间
+// Kiro 的 access token 大多是不透明字符串（aoa 开头），解码失败时返回 None
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenClaims {
+    /// sub
+    pub subject: Option<String>,
+    pub email: Option<String>,
+    /// iss
+    pub issuer: Option<String>,
+    /// exp，格式与账号的 expiresAt 一致
+    pub expires_at: Option<String>,
+    /// iat
+    pub issued_at: Option<String>,
+    /// 从 Cognito identities 或签发方推断的登录方式（Google / Github）
+    pub provider: Option<String>,
+    /// 完整的 payload
+    pub raw: serde_json::Value,
+}
+
+/// 解码 JWT 的 payload，不是 JWT 时返回 None
+pub fn decode_claims(token: &str) -> Option<TokenClaims> {
+    let mut parts = token.split('.');
+    let (_header, payload) = (parts.next()?, parts.next()?);
+    parts.next()?;
+
+    let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let raw: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    if !raw.is_object() {
+        return None;
+    }
+
+    let string = |key: &str| raw.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let time = |key: &str| {
+        raw.get(key)
+            .and_then(|v| v.as_i64())
+            .and_then(|secs| Local.timestamp_opt(secs, 0).single())
+            .map(|t| t.format("%Y/%m/%d %H:%M:%S").to_string())
+    };
+
+    // IdC 的 id_token 没有 email 时用户名通常就是邮箱
+    let email = string("email")
+        .or_else(|| string("preferred_username").filter(|u| u.contains('@')))
+        .or_else(|| string("username").filter(|u| u.contains('@')));
+
+    Some(TokenClaims {
+        subject: string("sub"),
+        email,
+        issuer: string("iss"),
+        expires_at: time("exp"),
+        issued_at: time("iat"),
+        provider: provider_of(&raw),
+        raw,
+    })
+}
+
+/// Cognito 联合登录的 id_token 带 identities[].providerName，否则看签发方
+fn provider_of(raw: &serde_json::Value) -> Option<String> {
+    let name = raw.get("identities")
+        .and_then(|v| v.as_array())
+        .and_then(|ids| ids.first())
+        .and_then(|id| id.get("providerName"))
+        .and_then(|v| v.as_str())
+        .or_else(|| raw.get("iss").and_then(|v| v.as_str()))?
+        .to_lowercase();
+
+    if name.contains("google") {
+        Some("Google".to_string())
+    } else if name.contains("github") {
+        Some("Github".to_string())
+    } else {
+        None
+    }
+}
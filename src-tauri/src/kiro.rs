@@ -3,20 +3,41 @@
 use serde::{Deserialize, Serialize};
 use rusqlite::{Connection, OpenFlags};
 
+use crate::account::Account;
+use crate::auth::PROFILE_ARN;
+use crate::error::{AppError, AppResult};
+use crate::providers::AuthMethod;
+use crate::token_manager::TokenManager;
+use tracing::info;
+
 // ===== Kiro IDE 本地 Token =====
 
+pub const TOKEN_FILE_NAME: &str = "kiro-auth-token.json";
+const DEFAULT_REGION: &str = "us-east-1";
+const DEFAULT_START_URL: &str = "https://view.awsapps.com/start";
+
+/// kiro-auth-token.json 的格式
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct KiroLocalToken {
     pub access_token: Option<String>,
     pub refresh_token: Option<String>,
+    /// UTC RFC 3339
     pub expires_at: Option<String>,
+    /// "social" / "IdC"
     pub auth_method: Option<String>,
     pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sso_session_id: Option<String>,
     // Social 专用
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub profile_arn: Option<String>,
     // IdC 专用
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub client_id_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub region: Option<String>,
 }
 
@@ -26,9 +47,155 @@ pub struct KiroLocalToken {
 pub struct ClientRegistration {
     pub client_id: String,
     pub client_secret: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<String>,
 }
 
+/// 要写入 ~/.aws/sso/cache 的一个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdeFile {
+    pub file_name: String,
+    pub content: String,
+}
+
+fn non_empty(value: &Option<String>) -> bool {
+    value.as_deref().is_some_and(|v| !v.trim().is_empty())
+}
+
+fn is_rfc3339(value: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(value).is_ok()
+}
+
+impl KiroLocalToken {
+    /// 用账号记录的真实字段（过期时间、idToken、SSO 会话等）构建，缺少 IDE 必需的字段时报错
+    pub fn from_account(account: &Account) -> Result<Self, String> {
+        let expires_at = account.expires_at_time()
+            .ok_or("账号缺少过期时间，请先刷新账号")?
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let method = TokenManager::auth_method(account);
+        let idc = method == AuthMethod::Idc;
+        // Web OAuth 登录的响应通常不带 profileArn，查询额度时用的是默认 profile，这里保持一致
+        let profile_arn = match method {
+            AuthMethod::Idc => None,
+            AuthMethod::WebOAuth => account.profile_arn.clone().or_else(|| Some(PROFILE_ARN.to_string())),
+            AuthMethod::Social => account.profile_arn.clone(),
+        };
+
+        let token = Self {
+            access_token: account.access_token.clone(),
+            refresh_token: account.refresh_token.clone(),
+            expires_at: Some(expires_at),
+            auth_method: Some(if idc { "IdC" } else { "social" }.to_string()),
+            provider: account.provider.clone()
+                .or_else(|| Some(if idc { "BuilderId" } else { "Google" }.to_string())),
+            id_token: account.id_token.clone(),
+            sso_session_id: account.sso_session_id.clone(),
+            profile_arn,
+            client_id_hash: if idc { Some(client_id_hash(account)) } else { None },
+            region: if idc {
+                Some(account.region.clone().unwrap_or_else(|| DEFAULT_REGION.to_string()))
+            } else {
+                None
+            },
+        };
+        token.validate()?;
+        Ok(token)
+    }
+
+    /// 按 IDE 读取时的要求检查字段，写入前调用
+    pub fn validate(&self) -> Result<(), String> {
+        if !non_empty(&self.access_token) {
+            return Err("缺少 accessToken".to_string());
+        }
+        if !non_empty(&self.refresh_token) {
+            return Err("缺少 refreshToken".to_string());
+        }
+        match self.expires_at.as_deref() {
+            Some(expires_at) if is_rfc3339(expires_at) => {}
+            Some(expires_at) => return Err(format!("expiresAt 不是 RFC 3339 时间: {}", expires_at)),
+            None => return Err("缺少 expiresAt".to_string()),
+        }
+        if !non_empty(&self.provider) {
+            return Err("缺少 provider".to_string());
+        }
+
+        match self.auth_method.as_deref() {
+            Some("social") => {
+                let arn = self.profile_arn.as_deref().ok_or("Social 账号缺少 profileArn，请先刷新账号")?;
+                if !arn.starts_with("arn:aws:codewhisperer:") {
+                    return Err(format!("profileArn 格式不正确: {}", arn));
+                }
+                if self.client_id_hash.is_some() || self.region.is_some() {
+                    return Err("Social 账号不应包含 clientIdHash / region".to_string());
+                }
+            }
+            Some("IdC") => {
+                let hash = self.client_id_hash.as_deref().ok_or("IdC 账号缺少 clientIdHash")?;
+                if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("clientIdHash 格式不正确: {}", hash));
+                }
+                let region = self.region.as_deref().ok_or("IdC 账号缺少 region")?;
+                if region.is_empty() || !region.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+                    return Err(format!("region 格式不正确: {}", region));
+                }
+            }
+            Some(other) => return Err(format!("不支持的 authMethod: {}", other)),
+            None => return Err("缺少 authMethod".to_string()),
+        }
+        Ok(())
+    }
+}
+
+impl ClientRegistration {
+    pub fn from_account(account: &Account) -> Result<Self, String> {
+        let registration = Self {
+            client_id: account.client_id.clone().ok_or("IdC 账号缺少 client_id")?,
+            client_secret: account.client_secret.clone().ok_or("IdC 账号缺少 client_secret")?,
+            expires_at: account.client_secret_expires_at.clone(),
+        };
+        registration.validate()?;
+        Ok(registration)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.client_id.trim().is_empty() || self.client_secret.trim().is_empty() {
+            return Err("客户端注册缺少 clientId / clientSecret".to_string());
+        }
+        if let Some(expires_at) = self.expires_at.as_deref() {
+            if !is_rfc3339(expires_at) {
+                return Err(format!("客户端注册 expiresAt 不是 RFC 3339 时间: {}", expires_at));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 旧账号没有记录 clientIdHash 时按 start URL 计算
+fn client_id_hash(account: &Account) -> String {
+    account.client_id_hash.clone().unwrap_or_else(|| {
+        use sha2::{Digest, Sha256};
+        hex::encode(Sha256::digest(account.start_url.as_deref().unwrap_or(DEFAULT_START_URL).as_bytes()))
+    })
+}
+
+/// 账号对应的 IDE 文件：kiro-auth-token.json，IdC 账号另带 {clientIdHash}.json
+pub fn ide_files(account: &Account) -> Result<Vec<IdeFile>, String> {
+    let token = KiroLocalToken::from_account(account)?;
+    let mut files = vec![IdeFile {
+        file_name: TOKEN_FILE_NAME.to_string(),
+        content: serde_json::to_string_pretty(&token).map_err(|e| format!("序列化失败: {}", e))?,
+    }];
+    if let Some(hash) = &token.client_id_hash {
+        let registration = ClientRegistration::from_account(account)?;
+        files.push(IdeFile {
+            file_name: format!("{}.json", hash),
+            content: serde_json::to_string_pretty(&registration).map_err(|e| format!("序列化失败: {}", e))?,
+        });
+    }
+    Ok(files)
+}
 #[tauri::command]
 pub fn get_kiro_local_token() -> Option<KiroLocalToken> {
     let home = std::env::var("USERPROFILE")
@@ -157,7 +324,10 @@ pub fn get_machine_id() -> String {
 
 // ===== 切换账号 =====
 
+use crate::fs_util::write_atomic;
 use crate::process::{check_kiro_running, kill_kiro, launch_kiro};
+use crate::state::AppState;
+use tauri::State;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -166,157 +336,99 @@ pub struct SwitchAccountResult {
     pub message: String,
     pub kiro_was_running: bool,
     pub kiro_restarted: bool,
+    pub dry_run: bool,
+    /// ~/.aws/sso/cache 的完整路径
+    pub directory: String,
+    /// 写入（dry run 时为将要写入）的文件内容
+    pub files: Vec<IdeFile>,
 }
 
 /// 切换账号参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SwitchAccountParams {
-    pub access_token: String,
-    pub refresh_token: String,
-    pub provider: String,
-    #[serde(default)]
-    pub auth_method: Option<String>,
-    // Social 专用
-    #[serde(default)]
-    pub profile_arn: Option<String>,
-    // IdC 专用
-    #[serde(default)]
-    pub client_id_hash: Option<String>,
-    #[serde(default)]
-    pub client_id: Option<String>,
-    #[serde(default)]
-    pub client_secret: Option<String>,
-    #[serde(default)]
-    pub region: Option<String>,
+    pub account_id: String,
     // 选项
     #[serde(default)]
     pub reset_machine_id: Option<bool>,
     #[serde(default)]
     pub auto_restart: Option<bool>,
+    /// 只返回将要写入的文件，不写入也不动 IDE 进程
+    #[serde(default)]
+    pub dry_run: Option<bool>,
+}
+
+fn sso_cache_dir() -> Result<std::path::PathBuf, String> {
+    let home = std::env::var("USERPROFILE")
+        .or_else(|_| std::env::var("HOME"))
+        .map_err(|_| "Cannot find home directory")?;
+    Ok(std::path::Path::new(&home).join(".aws").join("sso").join("cache"))
 }
 
-/// 切换 Kiro 账号（直接写入 Token 文件，仅重置机器ID时才关闭IDE）
+/// 切换 Kiro 账号（用账号库中的 token 写入 IDE 的 Token 文件，仅重置机器ID时才关闭IDE）
 #[tauri::command]
-pub async fn switch_kiro_account(params: SwitchAccountParams) -> Result<SwitchAccountResult, String> {
+pub async fn switch_kiro_account(state: State<'_, AppState>, params: SwitchAccountParams) -> AppResult<SwitchAccountResult> {
+    let mut account = state.store.lock().unwrap()
+        .accounts.iter()
+        .find(|a| a.id == params.account_id)
+        .cloned()
+        .ok_or_else(|| AppError::not_found("Account not found"))?;
+
     // 使用 spawn_blocking 避免阻塞异步运行时
-    tokio::task::spawn_blocking(move || {
-        let kiro_was_running = check_kiro_running();
+    tokio::task::spawn_blocking(move || -> AppResult<SwitchAccountResult> {
+        let dry_run = params.dry_run.unwrap_or(false);
         let should_reset = params.reset_machine_id.unwrap_or(false);
         let should_restart = params.auto_restart.unwrap_or(true);
-        let auth_method = params.auth_method.unwrap_or_else(|| "social".to_string());
-        let access_token = params.access_token;
-        let refresh_token = params.refresh_token;
-        let provider = params.provider;
-        let profile_arn = params.profile_arn;
-        let client_id_hash = params.client_id_hash;
-        let client_id = params.client_id;
-        let client_secret = params.client_secret;
-        let region = params.region;
-        
+        let dir_path = sso_cache_dir()?;
+
+        // 旧账号没有记录客户端注册过期时间时，沿用 IDE 里同一注册的值
+        if account.client_secret_expires_at.is_none() && account.client_id.is_some() {
+            account.client_secret_expires_at = get_client_registration(&client_id_hash(&account))
+                .filter(|reg| Some(&reg.client_id) == account.client_id.as_ref())
+                .and_then(|reg| reg.expires_at);
+        }
+        let files = ide_files(&account)?;
+        let provider = account.provider.clone().unwrap_or_default();
+        let auth_method = if TokenManager::auth_method(&account) == AuthMethod::Idc { "IdC" } else { "social" };
+
+        let mut result = SwitchAccountResult {
+            success: true,
+            message: format!("Switched to {} ({}) account", provider, auth_method),
+            kiro_was_running: false,
+            kiro_restarted: false,
+            dry_run,
+            directory: dir_path.to_string_lossy().to_string(),
+            files,
+        };
+        if dry_run {
+            result.message = format!("Would switch to {} ({}) account", provider, auth_method);
+            return Ok(result);
+        }
+
+        result.kiro_was_running = check_kiro_running();
+
         // 1. 只在需要重置机器 ID 时才关闭 IDE
-        if should_reset && kiro_was_running {
+        if should_reset && result.kiro_was_running {
             kill_kiro()?;
             // 等待进程退出
             std::thread::sleep(std::time::Duration::from_millis(300));
         }
-        
+
         // 2. 如果需要重置机器 ID
         if should_reset {
             let _ = reset_kiro_machine_id_inner();
         }
-        
-        // 3. 替换 Token
-        let home = std::env::var("USERPROFILE")
-            .or_else(|_| std::env::var("HOME"))
-            .map_err(|_| "Cannot find home directory")?;
-        
-        let dir_path = std::path::Path::new(&home)
-            .join(".aws")
-            .join("sso")
-            .join("cache");
-        
-        std::fs::create_dir_all(&dir_path)
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
-        
-        let file_path = dir_path.join("kiro-auth-token.json");
-        
-        let expires_at = chrono::Utc::now() + chrono::Duration::hours(1);
-        
-        // 根据 auth_method 构建不同的 token 数据
-        let token_data = if auth_method == "IdC" {
-            // IdC 账号: clientIdHash + region
-            let hash = client_id_hash.clone().unwrap_or_default();
-            let data = serde_json::json!({
-                "accessToken": access_token,
-                "refreshToken": refresh_token,
-                "expiresAt": expires_at.to_rfc3339(),
-                "authMethod": "IdC",
-                "provider": provider,
-                "clientIdHash": hash,
-                "region": region.clone().unwrap_or_else(|| "us-east-1".to_string())
-            });
-            data
-        } else {
-            // Social 账号: profileArn
-            let arn = profile_arn.unwrap_or_else(|| 
-                "arn:aws:codewhisperer:us-east-1:699475941385:profile/EHGA3GRVQMUK".to_string()
-            );
-            serde_json::json!({
-                "accessToken": access_token,
-                "refreshToken": refresh_token,
-                "profileArn": arn,
-                "expiresAt": expires_at.to_rfc3339(),
-                "authMethod": "social",
-                "provider": provider
-            })
-        };
-        
-        let content = serde_json::to_string_pretty(&token_data)
-            .map_err(|e| format!("Failed to serialize: {}", e))?;
-        
-        // 原子写入：先写临时文件，再覆盖
-        let temp_file_path = dir_path.join("kiro-auth-token.json.tmp");
-        std::fs::write(&temp_file_path, &content)
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
-        std::fs::rename(&temp_file_path, &file_path)
-            .map_err(|e| format!("Failed to rename file: {}", e))?;
-        
-        // IdC 账号还需要写入 Client Registration 文件
-        if auth_method == "IdC" {
-            if let (Some(hash), Some(cid), Some(csec)) = (client_id_hash, client_id, client_secret) {
-                let client_reg_path = dir_path.join(format!("{}.json", hash));
-                let client_reg_temp_path = dir_path.join(format!("{}.json.tmp", hash));
-                let client_expires = chrono::Utc::now() + chrono::Duration::days(90);
-                let client_reg_data = serde_json::json!({
-                    "clientId": cid,
-                    "clientSecret": csec,
-                    "expiresAt": client_expires.to_rfc3339()
-                });
-                let client_reg_content = serde_json::to_string_pretty(&client_reg_data)
-                    .map_err(|e| format!("Failed to serialize client registration: {}", e))?;
-                // 原子写入
-                std::fs::write(&client_reg_temp_path, client_reg_content)
-                    .map_err(|e| format!("Failed to write client registration temp: {}", e))?;
-                std::fs::rename(&client_reg_temp_path, &client_reg_path)
-                    .map_err(|e| format!("Failed to rename client registration: {}", e))?;
-            }
+
+        // 3. 替换 Token，IdC 账号的注册文件一并写入
+        for file in &result.files {
+            write_atomic(&dir_path.join(&file.file_name), file.content.as_bytes())?;
         }
-        
+        info!("Switched IDE to {} ({})", account.email, auth_method);
+
         // 4. 切换完成
-        let kiro_restarted = if kiro_was_running && should_restart {
-            launch_kiro().is_ok()
-        } else {
-            false
-        };
-        
-        Ok(SwitchAccountResult {
-            success: true,
-            message: format!("Switched to {} ({}) account", provider, auth_method),
-            kiro_was_running,
-            kiro_restarted,
-        })
-    }).await.map_err(|e| format!("Task failed: {}", e))?
+        result.kiro_restarted = result.kiro_was_running && should_restart && launch_kiro().is_ok();
+        Ok(result)
+    }).await.map_err(|e| AppError::Other(format!("Task failed: {}", e)))?
 }
 
 // ===== 重置机器 ID =====
@@ -404,4 +516,29 @@ pub async fn reset_kiro_machine_id() -> Result<KiroTelemetryInfo, String> {
         .map_err(|e| format!("Task failed: {}", e))?
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(auth_method: &str) -> Account {
+        let mut account = Account::new("a@example.com".to_string(), "a".to_string());
+        account.auth_method = Some(auth_method.to_string());
+        account.access_token = Some("access".to_string());
+        account.refresh_token = Some("refresh".to_string());
+        account.expires_at = Some("2030/01/01 00:00:00".to_string());
+        account
+    }
+
+    #[test]
+    fn web_oauth_account_without_profile_arn_uses_default() {
+        let token = KiroLocalToken::from_account(&account("web_oauth")).unwrap();
+        assert_eq!(token.profile_arn.as_deref(), Some(PROFILE_ARN));
+        assert_eq!(token.auth_method.as_deref(), Some("social"));
+    }
 
+    #[test]
+    fn social_account_without_profile_arn_is_rejected() {
+        let err = KiroLocalToken::from_account(&account("social")).unwrap_err();
+        assert!(err.contains("profileArn"), "{}", err);
+    }
+}
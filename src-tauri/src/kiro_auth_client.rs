@@ -1,26 +1,27 @@
 use crate::browser::open_browser;
+use crate::error::{AppError, AppResult};
+use crate::{endpoints, http_client, retry};
 use reqwest::Client;
 use serde::Deserialize;
 use std::time::Duration;
+use tracing::{debug, info, warn};
 
 /// Kiro Authentication Service Client
-/// 负责与 https://prod.us-east-1.auth.desktop.kiro.dev 通信
+/// 负责与 Kiro 桌面端认证服务通信（默认 https://prod.us-east-1.auth.desktop.kiro.dev，见 endpoints）
 pub struct KiroAuthServiceClient {
     endpoint: String,
     client: Client,
 }
 
 impl KiroAuthServiceClient {
-    pub fn new() -> Self {
-        let endpoint = "https://prod.us-east-1.auth.desktop.kiro.dev".to_string();
+    pub fn new() -> AppResult<Self> {
+        let endpoint = endpoints::desktop_auth();
 
-        let client = Client::builder()
-            .timeout(Duration::from_millis(10_000))
-            .user_agent("KiroBatchLoginCLI/1.0.0")
-            .build()
-            .expect("failed to build reqwest client");
+        let client = http_client::build(
+            http_client::builder(Duration::from_millis(10_000))?.user_agent("KiroBatchLoginCLI/1.0.0"),
+        )?;
 
-        Self { endpoint, client }
+        Ok(Self { endpoint, client })
     }
 
     fn login_url(&self) -> String {
@@ -35,6 +36,10 @@ impl KiroAuthServiceClient {
         format!("{}/refreshToken", self.endpoint)
     }
 
+    fn logout_url(&self) -> String {
+        format!("{}/logout", self.endpoint)
+    }
+
     /// 打开浏览器到登录页面
     pub async fn login(
         &self,
@@ -42,7 +47,7 @@ impl KiroAuthServiceClient {
         redirect_uri: &str,
         code_challenge: &str,
         state: &str,
-    ) -> Result<(), String> {
+    ) -> AppResult<()> {
         let login_url = format!(
             "{}?idp={}&redirect_uri={}&code_challenge={}&code_challenge_method=S256&state={}",
             self.login_url(),
@@ -52,12 +57,8 @@ impl KiroAuthServiceClient {
             state,
         );
 
-        println!("\n[1] KIRO AUTH LOGIN");
-        println!("Provider: {}", provider);
-        println!("Redirect URI: {}", redirect_uri);
-        println!("Code Challenge: {}", code_challenge);
-        println!("State: {}", state);
-        println!();
+        info!("Opening {} login", provider);
+        debug!("Redirect URI: {}", redirect_uri);
 
         let login_url = login_url.trim().to_string();
         
@@ -73,12 +74,10 @@ impl KiroAuthServiceClient {
         code_verifier: &str,
         redirect_uri: &str,
         invitation_code: Option<&str>,
-    ) -> Result<T, String> {
-        println!("\n[6] CREATE TOKEN REQUEST");
-        println!("URL: {}", self.create_token_url());
-        println!("Code: {}", code);
-        println!("Code Verifier: {}", code_verifier);
-        println!("Redirect URI: {}", redirect_uri);
+    ) -> AppResult<T> {
+        info!("Exchanging authorization code for tokens");
+        debug!("URL: {}", self.create_token_url());
+        debug!("Redirect URI: {}", redirect_uri);
 
         #[derive(serde::Serialize)]
         struct Body<'a> {
@@ -95,61 +94,66 @@ impl KiroAuthServiceClient {
             invitation_code,
         };
 
-        let resp = self
-            .client
-            .post(self.create_token_url())
-            .json(&body)
-            .send()
+        let resp = retry::send_once(|_| self.client.post(self.create_token_url()).json(&body))
             .await
-            .map_err(|e| format!("Kiro Auth Service request failed: {}", e))?;
+            .map_err(|e| AppError::network("Kiro Auth Service request failed", e))?;
 
         let status = resp.status();
         let bytes = resp
             .bytes()
             .await
-            .map_err(|e| format!("Kiro Auth Service read body failed: {}", e))?;
+            .map_err(|e| AppError::network("Kiro Auth Service read body failed", e))?;
 
-        println!("\n[6] CREATE TOKEN RESPONSE");
-        println!("Status: {}", status);
+        debug!("CreateToken status: {}", status);
         
         let body_str = String::from_utf8_lossy(&bytes);
         
         if !status.is_success() {
-            println!("Error: {}", body_str);
-            return Err(format!(
-                "Kiro Auth Service token creation failed: {} - {}",
-                status,
-                body_str
-            ));
+            warn!("CreateToken failed ({}): {}", status, body_str);
+            return Err(AppError::from_response("Kiro Auth Service token creation", status, &body_str));
         }
 
         // 完整格式化打印 JSON
         match serde_json::from_str::<serde_json::Value>(&body_str) {
             Ok(json) => {
                 match serde_json::to_string_pretty(&json) {
-                    Ok(pretty) => println!("{}", pretty),
-                    Err(_) => println!("{}", body_str),
+                    Ok(pretty) => debug!("{}", pretty),
+                    Err(_) => debug!("{}", body_str),
                 }
             }
-            Err(_) => println!("{}", body_str),
+            Err(_) => debug!("{}", body_str),
         }
-        println!();
 
-        serde_json::from_slice::<T>(&bytes).map_err(|e| format!(
+        serde_json::from_slice::<T>(&bytes).map_err(|e| AppError::Parse(format!(
             "Kiro Auth Service token creation parse failed: {}",
             e
-        ))
+        )))
     }
 
     /// 刷新访问令牌
+    /// 注销会话，refresh token 在服务端失效
+    pub async fn logout(&self, refresh_token: &str) -> AppResult<()> {
+        let body = serde_json::json!({ "refreshToken": refresh_token });
+
+        let resp = retry::send_idempotent(|_| self.client.post(self.logout_url()).json(&body))
+            .await
+            .map_err(|e| AppError::network("Kiro Auth Service request failed", e))?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body_str = resp.text().await.unwrap_or_default();
+        if status.as_u16() == 401 {
+            return Err(AppError::AuthExpired("RefreshToken 已过期或无效".to_string()));
+        }
+        Err(AppError::from_response("Kiro Auth Service logout", status, &body_str))
+    }
+
     pub async fn refresh_token<T: for<'de> Deserialize<'de>>(
         &self,
         refresh_token: &str,
-    ) -> Result<T, String> {
-        // println!("\n[Social] REFRESH TOKEN REQUEST");
-        // println!("URL: {}", self.refresh_token_url());
-        // println!("RefreshToken: {}...", &refresh_token[..20.min(refresh_token.len())]);
-
+    ) -> AppResult<T> {
         #[derive(serde::Serialize)]
         struct Body<'a> {
             #[serde(rename = "refreshToken")]
@@ -158,51 +162,28 @@ impl KiroAuthServiceClient {
 
         let body = Body { refresh_token };
 
-        let resp = self
-            .client
-            .post(self.refresh_token_url())
-            .json(&body)
-            .send()
+        let resp = retry::send_once(|_| self.client.post(self.refresh_token_url()).json(&body))
             .await
-            .map_err(|e| format!("Kiro Auth Service request failed: {}", e))?;
+            .map_err(|e| AppError::network("Kiro Auth Service request failed", e))?;
 
         let status = resp.status();
         let bytes = resp
             .bytes()
             .await
-            .map_err(|e| format!("Kiro Auth Service read body failed: {}", e))?;
-
-        // println!("\n[Social] REFRESH TOKEN RESPONSE");
-        // println!("Status: {}", status);
+            .map_err(|e| AppError::network("Kiro Auth Service read body failed", e))?;
 
         let body_str = String::from_utf8_lossy(&bytes);
 
         if !status.is_success() {
-            // println!("Error: {}", body_str);
             if status.as_u16() == 401 {
-                return Err("RefreshToken 已过期或无效".to_string());
+                return Err(AppError::AuthExpired("RefreshToken 已过期或无效".to_string()));
             }
-            return Err(format!(
-                "Kiro Auth Service token refresh failed: {} - {}",
-                status,
-                body_str
-            ));
+            return Err(AppError::from_response("Kiro Auth Service token refresh", status, &body_str));
         }
 
-        // // 格式化打印 JSON
-        // match serde_json::from_str::<serde_json::Value>(&body_str) {
-        //     Ok(json) => {
-        //         match serde_json::to_string_pretty(&json) {
-        //             Ok(pretty) => println!("{}", pretty),
-        //             Err(_) => println!("{}", body_str),
-        //         }
-        //     }
-        //     Err(_) => println!("{}", body_str),
-        // }
-
-        serde_json::from_slice::<T>(&bytes).map_err(|e| format!(
+        serde_json::from_slice::<T>(&bytes).map_err(|e| AppError::Parse(format!(
             "Kiro Auth Service token refresh parse failed: {}",
             e
-        ))
+        )))
     }
 }
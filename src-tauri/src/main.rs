@@ -4,22 +4,42 @@ mod auth;
 mod auth_social;
 mod aws_sso_client;
 mod browser;
+mod bulk_sync;
 mod codewhisperer_client;
 mod commands;
 mod deep_link_handler;
+mod endpoints;
+mod error;
+mod ide_token_sync;
 
 mod kiro;
 mod kiro_auth_client;
+mod logging;
 mod mcp;
 mod powers;
 mod process;
 mod providers;
+mod refresh_scheduler;
+mod retry;
 mod state;
 mod steering;
+mod token_manager;
 mod account;
+mod account_backup;
+mod account_db;
+mod account_export;
+mod account_format;
+mod account_import;
+mod account_query;
+mod fs_util;
+mod http_client;
+mod jwt;
+mod vault;
 
 use account::AccountStore;
 use auth::AuthState;
+use refresh_scheduler::RefreshScheduler;
+use bulk_sync::SyncJobs;
 use state::AppState;
 use std::sync::Mutex;
 use tauri::{Listener, Manager};
@@ -27,27 +47,37 @@ use tauri::{Listener, Manager};
 // 导入命令
 use browser::detect_installed_browsers;
 use commands::account_cmd::{
-    get_accounts, delete_account, delete_accounts, update_account, sync_account,
+    get_accounts, get_account_detail, delete_account, delete_accounts, sign_out_and_delete_accounts, update_account, sync_account,
     refresh_account_token, verify_account, add_account_by_social, add_local_kiro_account,
-    add_account_by_idc, import_accounts, export_accounts
+    add_account_by_idc, import_accounts, export_accounts, export_accounts_profile, get_account_storage_info,
+    set_account_storage_backend, list_accounts_backups, restore_accounts_backup,
+    query_accounts, update_account_tags, set_accounts_group, get_account_facets
 };
 use commands::app_settings_cmd::*;
 use commands::auth_cmd::*;
 use commands::kiro_settings_cmd::*;
+use commands::log_cmd::*;
 use commands::machine_guid_cmd::*;
 use commands::mcp_cmd::*;
 use commands::powers_cmd::*;
 use commands::proxy_cmd::*;
+use commands::refresh_cmd::*;
+use commands::ide_token_cmd::*;
+use commands::sync_cmd::*;
 use commands::sso_import_cmd::*;
 use commands::update_cmd::*;
+use commands::vault_cmd::*;
 use commands::web_oauth_cmd::*;
 use commands::steering_cmd::*;
 use kiro::{
     get_kiro_local_token, get_kiro_telemetry_info, reset_kiro_machine_id, switch_kiro_account,
 };
 use process::{close_kiro_ide, is_kiro_ide_running, start_kiro_ide};
+use tracing::debug;
 
 fn main() {
+    logging::init();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
@@ -68,7 +98,7 @@ fn main() {
             let app_handle = app.handle().clone();
             app.listen("deep-link://new-url", move |event| {
                 let payload = event.payload();
-                println!("[DeepLink] Received: {}", payload);
+                debug!("Deep link received: {}", payload);
                 // 处理 OAuth 回调
                 deep_link_handler::handle_deep_link(payload);
                 // 聚焦窗口
@@ -76,6 +106,11 @@ fn main() {
                     let _ = window.set_focus();
                 }
             });
+
+            // 后台 token 刷新
+            refresh_scheduler::start(app.handle().clone());
+            // 监听 IDE token 文件
+            ide_token_sync::start(app.handle().clone());
             
             Ok(())
         })
@@ -84,11 +119,19 @@ fn main() {
             auth: AuthState::new(),
             pending_login: Mutex::new(None),
         })
+        .manage(RefreshScheduler::new())
+        .manage(SyncJobs::new())
         .invoke_handler(tauri::generate_handler![
             // 账号命令
             get_accounts,
+            get_account_detail,
             delete_account,
             delete_accounts,
+            sign_out_and_delete_accounts,
+            query_accounts,
+            update_account_tags,
+            set_accounts_group,
+            get_account_facets,
             update_account,
             sync_account,
             refresh_account_token,
@@ -98,12 +141,33 @@ fn main() {
             add_account_by_idc,
             import_accounts,
             export_accounts,
+            export_accounts_profile,
+            get_account_storage_info,
+            set_account_storage_backend,
+            list_accounts_backups,
+            restore_accounts_backup,
+            // 后台刷新命令
+            get_refresh_scheduler_status,
+            set_refresh_activity,
+            // 批量同步命令
+            sync_accounts,
+            cancel_sync,
+            // IDE token 同步
+            get_ide_active_account,
+            // 账号库加密命令
+            get_vault_status,
+            enable_vault,
+            unlock_vault,
+            lock_vault,
+            disable_vault,
+            change_vault_passphrase,
             // Auth 命令
             get_current_user,
             logout,
             kiro_login,
             get_supported_providers,
             handle_kiro_social_callback,
+            reregister_idc_account,
             add_kiro_account,
             // Kiro IDE 命令
             get_kiro_local_token,
@@ -121,6 +185,8 @@ fn main() {
             // 应用设置命令
             get_app_settings,
             save_app_settings,
+            // 日志命令
+            read_logs,
             // 账号绑定机器码命令
             bind_machine_id_to_account,
             unbind_machine_id_from_account,
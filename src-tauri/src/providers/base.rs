@@ -4,8 +4,10 @@
 use serde::{Deserialize, Serialize};
 use async_trait::async_trait;
 
+use crate::error::AppResult;
+
 /// 认证结果
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthResult {
     // 通用字段
@@ -22,8 +24,10 @@ pub struct AuthResult {
     pub region: Option<String>,
     pub client_id: Option<String>,
     pub client_secret: Option<String>,
+    pub client_secret_expires_at: Option<String>,  // 客户端注册过期时间 (RFC 3339)，只有新注册时才有
     pub client_id_hash: Option<String>,
     pub sso_session_id: Option<String>,
+    pub start_url: Option<String>,
     
     // Social (Google/Github) 专用
     pub profile_arn: Option<String>,
@@ -41,16 +45,22 @@ pub struct RefreshMetadata {
     pub region: Option<String>,
     pub client_id_hash: Option<String>,
     pub profile_arn: Option<String>,
+    // Web OAuth 门户刷新需要当前的 AccessToken 和 csrfToken
+    pub access_token: Option<String>,
+    pub csrf_token: Option<String>,
 }
 
 /// 认证提供者接口
 #[async_trait]
 pub trait AuthProvider: Send + Sync {
     /// 执行登录认证
-    async fn login(&self) -> Result<AuthResult, String>;
+    async fn login(&self) -> AppResult<AuthResult>;
     
     /// 刷新 Token
-    async fn refresh_token(&self, refresh_token: &str, metadata: RefreshMetadata) -> Result<AuthResult, String>;
+    async fn refresh_token(&self, refresh_token: &str, metadata: RefreshMetadata) -> AppResult<AuthResult>;
+    
+    /// 在服务端注销会话，使 refresh token 失效
+    async fn revoke(&self, refresh_token: &str, metadata: RefreshMetadata) -> AppResult<()>;
     
     /// 获取 Provider ID
     fn get_provider_id(&self) -> &str;
@@ -1,13 +1,28 @@
 // Provider Factory - 根据 provider 创建对应的认证提供者
 // 参考 kiro-batch-login/src/providers/provider-factory.js
 
-use super::{SocialProvider, IdcProvider};
+use super::{AuthProvider, SocialProvider, IdcProvider};
+use super::web_oauth::WebOAuthProvider;
 
 /// 认证方式
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AuthMethod {
     Social,
     Idc,
+    /// Google/Github 通过 Kiro Web 门户登录，刷新走门户的 CBOR 接口
+    WebOAuth,
+}
+
+impl AuthMethod {
+    /// 解析账号/IDE token 里的 authMethod 字段
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "social" => Some(AuthMethod::Social),
+            "IdC" => Some(AuthMethod::Idc),
+            "web_oauth" => Some(AuthMethod::WebOAuth),
+            _ => None,
+        }
+    }
 }
 
 /// Provider 配置
@@ -40,13 +55,57 @@ pub fn get_provider_config(provider: &str) -> Option<ProviderConfig> {
             region: "us-east-1".to_string(),
             start_url: Some("https://view.awsapps.com/start".to_string()),
         }),
+        // 登录时需要用户提供 start URL 和 region，见 enterprise_provider_config
+        "Enterprise" => Some(ProviderConfig {
+            provider_id: "Enterprise".to_string(),
+            auth_method: AuthMethod::Idc,
+            region: "us-east-1".to_string(),
+            start_url: None,
+        }),
         _ => None,
     }
 }
 
+/// IAM Identity Center（企业）登录配置
+/// start_url 形如 https://d-xxxxxxxxxx.awsapps.com/start，region 为 Identity Center 所在区域
+pub fn enterprise_provider_config(start_url: Option<String>, region: Option<String>) -> Result<ProviderConfig, String> {
+    let start_url = start_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .ok_or("Enterprise 登录需要提供 Start URL")?;
+    let parsed = url::Url::parse(&start_url)
+        .map_err(|e| format!("Start URL 无效: {}", e))?;
+    if parsed.scheme() != "https" || parsed.host_str().is_none() {
+        return Err("Start URL 必须是 https 地址".to_string());
+    }
+
+    let region = region
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .unwrap_or_else(|| "us-east-1".to_string());
+    if !is_valid_region(&region) {
+        return Err(format!("Region 无效: {}", region));
+    }
+
+    Ok(ProviderConfig {
+        provider_id: "Enterprise".to_string(),
+        auth_method: AuthMethod::Idc,
+        region,
+        start_url: Some(start_url),
+    })
+}
+
+/// 形如 us-east-1、ap-southeast-2、us-gov-west-1
+fn is_valid_region(region: &str) -> bool {
+    let parts: Vec<&str> = region.split('-').collect();
+    parts.len() >= 3
+        && parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()))
+        && parts.last().is_some_and(|p| p.chars().all(|c| c.is_ascii_digit()))
+}
+
 /// 获取支持的 providers
 pub fn get_supported_providers() -> Vec<&'static str> {
-    vec!["Google", "Github", "BuilderId"]
+    vec!["Google", "Github", "BuilderId", "Enterprise"]
 }
 
 /// 创建 Social Provider
@@ -58,3 +117,12 @@ pub fn create_social_provider(config: &ProviderConfig) -> SocialProvider {
 pub fn create_idc_provider(config: &ProviderConfig) -> IdcProvider {
     IdcProvider::new(&config.provider_id, &config.region, config.start_url.clone())
 }
+
+/// 按配置创建对应认证方式的 provider
+pub fn create_provider(config: &ProviderConfig) -> Box<dyn AuthProvider> {
+    match config.auth_method {
+        AuthMethod::Social => Box::new(create_social_provider(config)),
+        AuthMethod::Idc => Box::new(create_idc_provider(config)),
+        AuthMethod::WebOAuth => Box::new(WebOAuthProvider::new(&config.provider_id)),
+    }
+}
@@ -3,10 +3,12 @@
 
 use crate::aws_sso_client::{AWSSSOClient, DevicePollResult};
 use crate::browser::open_browser;
+use crate::error::{AppError, AppResult};
 use sha2::{Digest, Sha256};
 use super::{AuthResult, AuthProvider, RefreshMetadata};
 use async_trait::async_trait;
 use std::time::Duration;
+use tracing::{debug, info};
 
 const BUILDER_ID_START_URL: &str = "https://view.awsapps.com/start";
 
@@ -41,49 +43,49 @@ impl IdcProvider {
 
 #[async_trait]
 impl AuthProvider for IdcProvider {
-    async fn login(&self) -> Result<AuthResult, String> {
+    async fn login(&self) -> AppResult<AuthResult> {
         let provider = &self.provider_id;
         let region = &self.region;
         let start_url = self.get_start_url();
 
-        println!("\n[IdC] Starting {} authentication (Device Flow)...", provider);
-        println!("Region: {}", region);
-        println!("Start URL: {}", start_url);
+        info!("Starting {} authentication (Device Flow)...", provider);
+        debug!("Region: {}", region);
+        debug!("Start URL: {}", start_url);
 
         // Step 1: 创建 AWS SSO 客户端
-        let sso_client = AWSSSOClient::new(region);
+        let sso_client = AWSSSOClient::new(region)?;
 
         // Step 2: 注册支持设备授权的客户端
-        println!("[IdC] Registering device client...");
+        info!("Registering device client...");
         let client_reg = sso_client.register_device_client(start_url).await?;
-        println!("Client ID: {}", client_reg.client_id);
+        debug!("Client ID: {}", client_reg.client_id);
 
         // Step 3: 发起设备授权
-        println!("[IdC] Starting device authorization...");
+        info!("Starting device authorization...");
         let device_auth = sso_client.start_device_authorization(
             &client_reg.client_id,
             &client_reg.client_secret,
             start_url,
         ).await?;
         
-        println!("[IdC] User Code: {}", device_auth.user_code);
-        println!("[IdC] Verification URI: {}", device_auth.verification_uri);
+        info!("User Code: {}", device_auth.user_code);
+        info!("Verification URI: {}", device_auth.verification_uri);
 
         // Step 4: 打开浏览器让用户输入 user_code
         let verification_url = device_auth.verification_uri_complete
             .as_ref()
             .unwrap_or(&device_auth.verification_uri);
-        println!("[IdC] Opening browser: {}", verification_url);
+        info!("Opening browser: {}", verification_url);
         open_browser(verification_url)?;
 
         // Step 5: 轮询等待用户授权
-        println!("[IdC] Waiting for user authorization...");
+        info!("Waiting for user authorization...");
         let mut interval = device_auth.interval.unwrap_or(5) as u64;
         let timeout = std::time::Instant::now() + Duration::from_secs(device_auth.expires_in as u64);
 
         let token_response = loop {
             if std::time::Instant::now() > timeout {
-                return Err("设备授权超时，请重试".to_string());
+                return Err(AppError::Other("设备授权超时，请重试".to_string()));
             }
 
             tokio::time::sleep(Duration::from_secs(interval)).await;
@@ -94,7 +96,7 @@ impl AuthProvider for IdcProvider {
                 &device_auth.device_code,
             ).await? {
                 DevicePollResult::Success(token) => {
-                    println!("[IdC] Authorization successful!");
+                    info!("Authorization successful!");
                     break token;
                 }
                 DevicePollResult::Pending => {
@@ -107,10 +109,10 @@ impl AuthProvider for IdcProvider {
                     continue;
                 }
                 DevicePollResult::Expired => {
-                    return Err("设备码已过期，请重试".to_string());
+                    return Err(AppError::Other("设备码已过期，请重试".to_string()));
                 }
                 DevicePollResult::Denied => {
-                    return Err("用户拒绝授权".to_string());
+                    return Err(AppError::Other("用户拒绝授权".to_string()));
                 }
             }
         };
@@ -118,8 +120,11 @@ impl AuthProvider for IdcProvider {
         // Step 6: 构建 AuthResult
         let expires_at = chrono::Local::now() + chrono::Duration::seconds(token_response.expires_in);
         let client_id_hash = Self::compute_client_id_hash(start_url);
+        let client_secret_expires_at = client_reg.client_secret_expires_at
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|t| t.to_rfc3339());
 
-        println!("[IdC] {} login successful! {}", provider, serde_json::to_string_pretty(&serde_json::json!({
+        info!("{} login successful! {}", provider, serde_json::to_string_pretty(&serde_json::json!({
             "expiresIn": token_response.expires_in,
             "expiresAt": expires_at.format("%Y/%m/%d %H:%M:%S").to_string(),
             "hasIdToken": token_response.id_token.is_some(),
@@ -138,21 +143,23 @@ impl AuthProvider for IdcProvider {
             region: Some(region.clone()),
             client_id: Some(client_reg.client_id),
             client_secret: Some(client_reg.client_secret),
+            client_secret_expires_at,
             client_id_hash: Some(client_id_hash),
             sso_session_id: token_response.aws_sso_app_session_id,
+            start_url: Some(start_url.to_string()),
             profile_arn: None,
             csrf_token: None,
             session_token: None,
         })
     }
 
-    async fn refresh_token(&self, refresh_token: &str, metadata: RefreshMetadata) -> Result<AuthResult, String> {
+    async fn refresh_token(&self, refresh_token: &str, metadata: RefreshMetadata) -> AppResult<AuthResult> {
         // IdC 刷新需要 client_id 和 client_secret
         let client_id = metadata.client_id.ok_or("Client ID is required for IdC token refresh")?;
         let client_secret = metadata.client_secret.ok_or("Client secret is required for IdC token refresh")?;
         let region = metadata.region.as_deref().unwrap_or(&self.region);
 
-        let sso_client = AWSSSOClient::new(region);
+        let sso_client = AWSSSOClient::new(region)?;
         let token_response = sso_client.refresh_token(&client_id, &client_secret, refresh_token).await?;
 
         let expires_at = chrono::Local::now() + chrono::Duration::seconds(token_response.expires_in);
@@ -170,14 +177,31 @@ impl AuthProvider for IdcProvider {
             region: Some(region.to_string()),
             client_id: Some(client_id),
             client_secret: Some(client_secret),
+            client_secret_expires_at: None,
             client_id_hash: Some(client_id_hash),
             sso_session_id: token_response.aws_sso_app_session_id,
+            start_url: Some(self.get_start_url().to_string()),
             profile_arn: None,
             csrf_token: None,
             session_token: None,
         })
     }
 
+    async fn revoke(&self, refresh_token: &str, metadata: RefreshMetadata) -> AppResult<()> {
+        let region = metadata.region.clone().unwrap_or_else(|| self.region.clone());
+        let sso_client = AWSSSOClient::new(&region)?;
+
+        // Logout 需要有效的 access token：没有或已过期时先刷新一次
+        if let Some(access_token) = metadata.access_token.as_deref() {
+            match sso_client.logout(access_token).await {
+                Err(e) if e.is_auth() => {}
+                result => return result,
+            }
+        }
+        let refreshed = self.refresh_token(refresh_token, metadata).await?;
+        sso_client.logout(&refreshed.access_token).await
+    }
+
     fn get_provider_id(&self) -> &str {
         &self.provider_id
     }
@@ -4,9 +4,11 @@
 use crate::kiro_auth_client::KiroAuthServiceClient;
 use crate::deep_link_handler::{DeepLinkCallbackWaiter, register_waiter};
 use crate::auth_social;
+use crate::error::AppResult;
 use super::{AuthResult, AuthProvider, RefreshMetadata};
 use serde::Deserialize;
 use async_trait::async_trait;
+use tracing::{debug, info};
 
 /// Social 登录 Token 响应
 #[derive(Debug, Deserialize)]
@@ -56,7 +58,7 @@ impl SocialProvider {
 
 #[async_trait]
 impl AuthProvider for SocialProvider {
-    async fn login(&self) -> Result<AuthResult, String> {
+    async fn login(&self) -> AppResult<AuthResult> {
         let provider = &self.provider_id;
 
         // Step 1: 使用 deep link 作为回调 URI
@@ -67,28 +69,28 @@ impl AuthProvider for SocialProvider {
         let code_verifier = auth_social::generate_code_verifier_social();
         let code_challenge = auth_social::generate_code_challenge_social(&code_verifier);
 
-        println!("\n[Social] Starting {} authentication...", provider);
-        println!("Redirect URI: {}", redirect_uri);
-        println!("State: {}", state);
+        info!("Starting {} authentication...", provider);
+        debug!("Redirect URI: {}", redirect_uri);
+        debug!("State: {}", state);
 
         // Step 3: 注册回调等待器
         let waiter = register_waiter(&state);
 
         // Step 4: 打开浏览器登录
-        let client = KiroAuthServiceClient::new();
+        let client = KiroAuthServiceClient::new()?;
         client.login(provider, &redirect_uri, &code_challenge, &state).await?;
 
         // Step 5: 等待 deep link 回调
-        println!("[Social] Waiting for deep link callback...");
+        info!("Waiting for deep link callback...");
         let callback = tokio::task::spawn_blocking(move || waiter.wait_for_callback())
             .await
             .map_err(|e| format!("Failed to join callback waiter: {}", e))?
             .map_err(|e| format!("OAuth callback failed: {}", e))?;
         
-        println!("[Social] Callback received, state: {}", callback.state);
+        info!("Callback received, state: {}", callback.state);
 
         // Step 6: 交换 token
-        println!("[Social] Exchanging code for tokens...");
+        info!("Exchanging code for tokens...");
         let token_response: SocialTokenResponse = client
             .create_token(&callback.code, &code_verifier, &redirect_uri, None)
             .await?;
@@ -96,7 +98,7 @@ impl AuthProvider for SocialProvider {
         // Step 7: 构建 AuthResult
         let expires_at = chrono::Local::now() + chrono::Duration::seconds(token_response.expires_in);
 
-        println!("[Social] {} login successful! {}", provider, serde_json::to_string_pretty(&serde_json::json!({
+        info!("{} login successful! {}", provider, serde_json::to_string_pretty(&serde_json::json!({
             "expiresIn": token_response.expires_in,
             "expiresAt": expires_at.format("%Y/%m/%d %H:%M:%S").to_string(),
             "hasIdToken": token_response.id_token.is_some(),
@@ -115,16 +117,18 @@ impl AuthProvider for SocialProvider {
             region: None,
             client_id: None,
             client_secret: None,
+            client_secret_expires_at: None,
             client_id_hash: None,
             sso_session_id: None,
+            start_url: None,
             profile_arn: token_response.profile_arn,
             csrf_token: token_response.csrf_token,
             session_token: None,
         })
     }
 
-    async fn refresh_token(&self, refresh_token: &str, metadata: RefreshMetadata) -> Result<AuthResult, String> {
-        let client = KiroAuthServiceClient::new();
+    async fn refresh_token(&self, refresh_token: &str, metadata: RefreshMetadata) -> AppResult<AuthResult> {
+        let client = KiroAuthServiceClient::new()?;
         let token_response: SocialRefreshResponse = client.refresh_token(refresh_token).await?;
 
         let expires_at = chrono::Local::now() + chrono::Duration::seconds(token_response.expires_in);
@@ -141,14 +145,20 @@ impl AuthProvider for SocialProvider {
             region: None,
             client_id: None,
             client_secret: None,
+            client_secret_expires_at: None,
             client_id_hash: None,
             sso_session_id: None,
+            start_url: None,
             profile_arn: metadata.profile_arn.or(token_response.profile_arn),
             csrf_token: token_response.csrf_token,
             session_token: None,
         })
     }
 
+    async fn revoke(&self, refresh_token: &str, _metadata: RefreshMetadata) -> AppResult<()> {
+        KiroAuthServiceClient::new()?.logout(refresh_token).await
+    }
+
     fn get_provider_id(&self) -> &str {
         &self.provider_id
     }
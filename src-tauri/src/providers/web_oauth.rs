@@ -3,32 +3,41 @@
 // 独立于现有的 AuthDesktopService 登录
 
 use super::{AuthProvider, AuthResult, RefreshMetadata};
+use crate::error::{AppError, AppResult};
+use crate::retry;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-
-// ============================================================
-// 常量配置
-// ============================================================
-
-const KIRO_WEB_PORTAL: &str = "https://app.kiro.dev";
-const KIRO_REDIRECT_URI: &str = "https://app.kiro.dev/signin/oauth";
+use tracing::{debug, info, warn};
 
 // ============================================================
 // CBOR 编解码
 // ============================================================
 
 /// CBOR 编码请求体
-fn cbor_encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+fn cbor_encode<T: Serialize>(value: &T) -> AppResult<Vec<u8>> {
     let mut buf = Vec::new();
     ciborium::into_writer(value, &mut buf)
-        .map_err(|e| format!("CBOR encode error: {}", e))?;
+        .map_err(|e| AppError::Parse(format!("CBOR encode error: {}", e)))?;
     Ok(buf)
 }
 
 /// CBOR 解码响应体
-fn cbor_decode<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T, String> {
+fn cbor_decode<T: for<'de> Deserialize<'de>>(data: &[u8]) -> AppResult<T> {
     ciborium::from_reader(data)
-        .map_err(|e| format!("CBOR decode error: {}", e))
+        .map_err(|e| AppError::Parse(format!("CBOR decode error: {}", e)))
+}
+
+/// 门户接口的错误响应
+/// 423 Locked / AccountSuspendedException 表示账号被封禁，其它按状态码归类
+fn portal_error(context: &str, status: reqwest::StatusCode, error_msg: &str) -> AppError {
+    if status.as_u16() == 423 || error_msg.contains("AccountSuspendedException") {
+        let reason = serde_json::from_str::<serde_json::Value>(error_msg)
+            .ok()
+            .and_then(|v| v.get("message").or_else(|| v.get("reason")).and_then(|m| m.as_str()).map(|m| m.to_string()))
+            .unwrap_or_else(|| "AccountSuspendedException".to_string());
+        return AppError::AccountSuspended { reason };
+    }
+    AppError::from_response(context, status, error_msg)
 }
 
 // ============================================================
@@ -98,9 +107,9 @@ struct RefreshTokenRequest {
     csrf_token: String,
 }
 
-/// RefreshToken 响应
+/// RefreshToken 响应 (CBOR body)
 #[derive(Debug, Deserialize)]
-pub struct RefreshTokenResponse {
+struct RefreshTokenCborResponse {
     #[serde(rename = "accessToken")]
     access_token: Option<String>,
     #[serde(rename = "csrfToken")]
@@ -111,6 +120,16 @@ pub struct RefreshTokenResponse {
     profile_arn: Option<String>,
 }
 
+/// RefreshToken 完整结果 (body | Set-Cookie 合并)
+#[derive(Debug)]
+pub struct RefreshTokenResponse {
+    pub access_token: Option<String>,   // body | Set-Cookie
+    pub csrf_token: Option<String>,     // body
+    pub expires_in: Option<i64>,        // body
+    pub profile_arn: Option<String>,    // body
+    pub session_token: Option<String>,  // Set-Cookie RefreshToken（轮换后的新值，未轮换时为空）
+}
+
 /// GetUserInfo 响应
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GetUserInfoResponse {
@@ -240,11 +259,11 @@ pub struct KiroWebPortalClient {
 }
 
 impl KiroWebPortalClient {
-    pub fn new() -> Self {
-        Self {
-            client: reqwest::Client::new(),
-            endpoint: KIRO_WEB_PORTAL.to_string(),
-        }
+    pub fn new() -> AppResult<Self> {
+        Ok(Self {
+            client: crate::http_client::client(std::time::Duration::from_secs(30))?,
+            endpoint: crate::endpoints::web_portal(),
+        })
     }
 
     /// 调用 InitiateLogin 接口 - 获取 OAuth 重定向 URL
@@ -254,7 +273,7 @@ impl KiroWebPortalClient {
         redirect_uri: &str,
         code_challenge: &str,
         state: &str,
-    ) -> Result<InitiateLoginResponse, String> {
+    ) -> AppResult<InitiateLoginResponse> {
         let url = format!(
             "{}/service/KiroWebPortalService/operation/InitiateLogin",
             self.endpoint
@@ -270,22 +289,23 @@ impl KiroWebPortalClient {
 
         let body = cbor_encode(&request)?;
 
-        let response = self.client
-            .post(&url)
-            .header("Content-Type", "application/cbor")
-            .header("Accept", "application/cbor")
-            .header("smithy-protocol", "rpc-v2-cbor")
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| format!("InitiateLogin request failed: {}", e))?;
+        let response = retry::send_idempotent(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/cbor")
+                .header("Accept", "application/cbor")
+                .header("smithy-protocol", "rpc-v2-cbor")
+                .body(body.clone())
+        })
+        .await
+        .map_err(|e| AppError::network("InitiateLogin request failed", e))?;
 
         let status = response.status();
         let bytes = response.bytes().await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+            .map_err(|e| AppError::network("Failed to read response", e))?;
 
         if !status.is_success() {
-            return Err(format!("InitiateLogin failed ({}): {:?}", status, bytes));
+            return Err(AppError::from_response("InitiateLogin", status, &String::from_utf8_lossy(&bytes)));
         }
 
         cbor_decode(&bytes)
@@ -299,7 +319,7 @@ impl KiroWebPortalClient {
         code_verifier: &str,
         redirect_uri: &str,
         state: &str,
-    ) -> Result<ExchangeTokenResult, String> {
+    ) -> AppResult<ExchangeTokenResult> {
         let url = format!(
             "{}/service/KiroWebPortalService/operation/ExchangeToken",
             self.endpoint
@@ -313,34 +333,34 @@ impl KiroWebPortalClient {
             state: state.to_string(),
         };
 
-        println!("[WebOAuth] ExchangeToken Request: {}", serde_json::to_string_pretty(&serde_json::json!({
+        debug!("ExchangeToken Request: {}", serde_json::to_string_pretty(&serde_json::json!({
             "url": url,
             "idp": idp,
-            "code": format!("{}...{}", &code[..20.min(code.len())], if code.len() > 30 { &code[code.len()-10..] } else { "" }),
-            "codeVerifier": code_verifier,
             "redirectUri": redirect_uri,
-            "state": format!("{}...", &state[..40.min(state.len())])
         })).unwrap_or_default());
 
         let body = cbor_encode(&request)?;
 
-        let response = self.client
-            .post(&url)
-            .header("Content-Type", "application/cbor")
-            .header("Accept", "application/cbor")
-            .header("smithy-protocol", "rpc-v2-cbor")
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| format!("ExchangeToken request failed: {}", e))?;
+        let response = retry::send_once(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/cbor")
+                .header("Accept", "application/cbor")
+                .header("smithy-protocol", "rpc-v2-cbor")
+                .body(body.clone())
+        })
+        .await
+        .map_err(|e| AppError::network("ExchangeToken request failed", e))?;
 
         let status = response.status();
         
-        // 打印所有响应头
-        println!("[WebOAuth] ExchangeToken Response Headers:");
+        // 打印响应头（Set-Cookie 里是 token，只记录名称）
+        debug!("ExchangeToken Response Headers:");
         for (name, value) in response.headers().iter() {
             if let Ok(v) = value.to_str() {
-                println!("  {}: {}", name, v);
+                if name != "set-cookie" {
+                    debug!("  {}: {}", name, v);
+                }
             }
         }
         
@@ -351,9 +371,8 @@ impl KiroWebPortalClient {
         
         for value in response.headers().get_all("set-cookie") {
             if let Ok(cookie_str) = value.to_str() {
-                println!("[WebOAuth] Set-Cookie raw: {}", cookie_str);
                 if let Ok(c) = cookie::Cookie::parse(cookie_str) {
-                    println!("[WebOAuth] Set-Cookie parsed: {}={}", c.name(), &c.value()[..20.min(c.value().len())]);
+                    debug!("Set-Cookie: {}", c.name());
                     match c.name() {
                         "RefreshToken" => cookie_session_token = Some(c.value().to_string()),
                         "AccessToken" => cookie_access_token = Some(c.value().to_string()),
@@ -365,7 +384,7 @@ impl KiroWebPortalClient {
         }
         
         let bytes = response.bytes().await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+            .map_err(|e| AppError::network("Failed to read response", e))?;
 
         if !status.is_success() {
             let error_msg = if let Ok(error) = cbor_decode::<serde_json::Value>(&bytes) {
@@ -373,15 +392,15 @@ impl KiroWebPortalClient {
             } else {
                 String::from_utf8_lossy(&bytes).to_string()
             };
-            println!("[WebOAuth] ExchangeToken Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
-            return Err(format!("ExchangeToken failed ({}): {}", status, error_msg));
+            warn!("ExchangeToken Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
+            return Err(AppError::from_response("ExchangeToken", status, &error_msg));
         }
 
-        println!("[WebOAuth] ExchangeToken Status: {} ({} bytes)", status, bytes.len());
+        info!("ExchangeToken Status: {} ({} bytes)", status, bytes.len());
         
         // 打印原始响应体 (CBOR -> JSON)
         if let Ok(raw_json) = cbor_decode::<serde_json::Value>(&bytes) {
-            println!("[WebOAuth] ExchangeToken Response Body: {}", 
+            debug!("ExchangeToken Response Body: {}", 
                 serde_json::to_string_pretty(&raw_json).unwrap_or_default());
         }
         
@@ -408,7 +427,7 @@ impl KiroWebPortalClient {
         csrf_token: &str,
         session_token: &str,
         idp: &str,
-    ) -> Result<RefreshTokenResponse, String> {
+    ) -> AppResult<RefreshTokenResponse> {
         let url = format!(
             "{}/service/KiroWebPortalService/operation/RefreshToken",
             self.endpoint
@@ -426,29 +445,44 @@ impl KiroWebPortalClient {
             access_token, session_token, idp
         );
 
-        println!("[WebOAuth] RefreshToken Request: {}", serde_json::to_string_pretty(&serde_json::json!({
+        debug!("RefreshToken Request: {}", serde_json::to_string_pretty(&serde_json::json!({
             "url": url,
             "idp": idp,
-            "accessToken": format!("{}...", &access_token[..20.min(access_token.len())]),
-            "refreshToken": format!("{}...", &session_token[..20.min(session_token.len())]),
-            "csrfToken": csrf_token
+            "hasAccessToken": !access_token.is_empty(),
+            "hasRefreshToken": !session_token.is_empty(),
         })).unwrap_or_default());
 
-        let response = self.client
-            .post(&url)
-            .header("Content-Type", "application/cbor")
-            .header("Accept", "application/cbor")
-            .header("smithy-protocol", "rpc-v2-cbor")
-            .header("x-csrf-token", csrf_token)
-            .header("Cookie", cookie)
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| format!("RefreshToken request failed: {}", e))?;
+        let response = retry::send_once(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/cbor")
+                .header("Accept", "application/cbor")
+                .header("smithy-protocol", "rpc-v2-cbor")
+                .header("x-csrf-token", csrf_token)
+                .header("Cookie", &cookie)
+                .body(body.clone())
+        })
+        .await
+        .map_err(|e| AppError::network("RefreshToken request failed", e))?;
 
         let status = response.status();
+
+        // 服务端会通过 Set-Cookie 轮换 RefreshToken / AccessToken
+        let mut cookie_session_token: Option<String> = None;
+        let mut cookie_access_token: Option<String> = None;
+        for value in response.headers().get_all("set-cookie") {
+            let Ok(cookie_str) = value.to_str() else { continue };
+            if let Ok(c) = cookie::Cookie::parse(cookie_str) {
+                match c.name() {
+                    "RefreshToken" if !c.value().is_empty() => cookie_session_token = Some(c.value().to_string()),
+                    "AccessToken" if !c.value().is_empty() => cookie_access_token = Some(c.value().to_string()),
+                    _ => {}
+                }
+            }
+        }
+
         let bytes = response.bytes().await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+            .map_err(|e| AppError::network("Failed to read response", e))?;
 
         if !status.is_success() {
             let error_msg = if let Ok(error) = cbor_decode::<serde_json::Value>(&bytes) {
@@ -456,24 +490,78 @@ impl KiroWebPortalClient {
             } else {
                 String::from_utf8_lossy(&bytes).to_string()
             };
-            println!("[WebOAuth] RefreshToken Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
-            
-            // 423 Locked = AccountSuspendedException = 账号被封禁
-            if status.as_u16() == 423 || error_msg.contains("AccountSuspendedException") {
-                return Err("BANNED: 账号已被封禁".to_string());
-            }
-            return Err(format!("RefreshToken failed ({}): {}", status, error_msg));
+            warn!("RefreshToken Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
+            return Err(portal_error("RefreshToken", status, &error_msg));
         }
 
-        println!("[WebOAuth] RefreshToken Status: {} ({} bytes)", status, bytes.len());
+        info!("RefreshToken Status: {} ({} bytes)", status, bytes.len());
         
         // 打印原始响应体 (CBOR -> JSON)
         if let Ok(raw_json) = cbor_decode::<serde_json::Value>(&bytes) {
-            println!("[WebOAuth] RefreshToken Response Body: {}", 
+            debug!("RefreshToken Response Body: {}", 
                 serde_json::to_string_pretty(&raw_json).unwrap_or_default());
         }
         
-        cbor_decode(&bytes)
+        let cbor_resp: RefreshTokenCborResponse = cbor_decode(&bytes)?;
+
+        Ok(RefreshTokenResponse {
+            access_token: cbor_resp.access_token.or(cookie_access_token),
+            csrf_token: cbor_resp.csrf_token,
+            expires_in: cbor_resp.expires_in,
+            profile_arn: cbor_resp.profile_arn,
+            session_token: cookie_session_token,
+        })
+    }
+
+    /// 调用 Logout 接口，注销门户会话（RefreshToken cookie 随之失效）
+    pub async fn logout(
+        &self,
+        access_token: &str,
+        csrf_token: &str,
+        session_token: &str,
+        idp: &str,
+    ) -> AppResult<()> {
+        let url = format!(
+            "{}/service/KiroWebPortalService/operation/Logout",
+            self.endpoint
+        );
+
+        let request = RefreshTokenRequest {
+            csrf_token: csrf_token.to_string(),
+        };
+        let body = cbor_encode(&request)?;
+
+        let cookie = format!(
+            "AccessToken={}; RefreshToken={}; Idp={}",
+            access_token, session_token, idp
+        );
+
+        let response = retry::send_idempotent(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/cbor")
+                .header("Accept", "application/cbor")
+                .header("smithy-protocol", "rpc-v2-cbor")
+                .header("x-csrf-token", csrf_token)
+                .header("Cookie", &cookie)
+                .body(body.clone())
+        })
+        .await
+        .map_err(|e| AppError::network("Logout request failed", e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            info!("Logout Status: {}", status);
+            return Ok(());
+        }
+
+        let bytes = response.bytes().await.unwrap_or_default();
+        let error_msg = if let Ok(error) = cbor_decode::<serde_json::Value>(&bytes) {
+            serde_json::to_string(&error).unwrap_or_default()
+        } else {
+            String::from_utf8_lossy(&bytes).to_string()
+        };
+        Err(AppError::from_response("Logout", status, &error_msg))
     }
 
     /// 调用 GetUserInfo 接口 (KiroWebPortalService)
@@ -484,7 +572,7 @@ impl KiroWebPortalClient {
         _csrf_token: &str,  // 保留参数兼容性，但不再使用
         _session_token: &str,
         idp: &str,
-    ) -> Result<GetUserInfoResponse, String> {
+    ) -> AppResult<GetUserInfoResponse> {
         let url = format!(
             "{}/service/KiroWebPortalService/operation/GetUserInfo",
             self.endpoint
@@ -503,27 +591,28 @@ impl KiroWebPortalClient {
         // 简化 Cookie，只需要 Idp 和 AccessToken
         let cookie = format!("Idp={}; AccessToken={}", idp, access_token);
 
-        println!("[WebOAuth] GetUserInfo Request: {}", serde_json::to_string_pretty(&serde_json::json!({
+        debug!("GetUserInfo Request: {}", serde_json::to_string_pretty(&serde_json::json!({
             "url": url,
             "idp": idp
         })).unwrap_or_default());
 
-        let response = self.client
-            .post(&url)
-            .header("Content-Type", "application/cbor")
-            .header("Accept", "application/cbor")
-            .header("smithy-protocol", "rpc-v2-cbor")
-            .header("authorization", format!("Bearer {}", access_token))
-            // 不再需要 x-csrf-token
-            .header("Cookie", cookie)
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| format!("GetUserInfo request failed: {}", e))?;
+        let response = retry::send_idempotent(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/cbor")
+                .header("Accept", "application/cbor")
+                .header("smithy-protocol", "rpc-v2-cbor")
+                .header("authorization", format!("Bearer {}", access_token))
+                // 不再需要 x-csrf-token
+                .header("Cookie", &cookie)
+                .body(body.clone())
+        })
+        .await
+        .map_err(|e| AppError::network("GetUserInfo request failed", e))?;
 
         let status = response.status();
         let bytes = response.bytes().await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+            .map_err(|e| AppError::network("Failed to read response", e))?;
 
         if !status.is_success() {
             let error_msg = if let Ok(error) = cbor_decode::<serde_json::Value>(&bytes) {
@@ -531,20 +620,15 @@ impl KiroWebPortalClient {
             } else {
                 String::from_utf8_lossy(&bytes).to_string()
             };
-            println!("[WebOAuth] GetUserInfo Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
-            
-            // 423 Locked = AccountSuspendedException = 账号被封禁
-            if status.as_u16() == 423 || error_msg.contains("AccountSuspendedException") {
-                return Err("BANNED: 账号已被封禁".to_string());
-            }
-            return Err(format!("GetUserInfo failed ({}): {}", status, error_msg));
+            warn!("GetUserInfo Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
+            return Err(portal_error("GetUserInfo", status, &error_msg));
         }
 
-        println!("[WebOAuth] GetUserInfo Status: {} ({} bytes)", status, bytes.len());
+        info!("GetUserInfo Status: {} ({} bytes)", status, bytes.len());
         
         // 打印原始响应体 (CBOR -> JSON)
         if let Ok(raw_json) = cbor_decode::<serde_json::Value>(&bytes) {
-            println!("[WebOAuth] GetUserInfo Response Body: {}", 
+            debug!("GetUserInfo Response Body: {}", 
                 serde_json::to_string_pretty(&raw_json).unwrap_or_default());
         }
         
@@ -560,7 +644,7 @@ impl KiroWebPortalClient {
         _csrf_token: &str,  // 保留参数兼容性，但不再使用
         _session_token: &str,
         idp: &str,
-    ) -> Result<GetUserUsageAndLimitsResponse, String> {
+    ) -> AppResult<GetUserUsageAndLimitsResponse> {
         let url = format!(
             "{}/service/KiroWebPortalService/operation/GetUserUsageAndLimits",
             self.endpoint
@@ -575,27 +659,28 @@ impl KiroWebPortalClient {
         // 简化 Cookie，只需要 Idp 和 AccessToken
         let cookie = format!("Idp={}; AccessToken={}", idp, access_token);
 
-        println!("[WebOAuth] GetUserUsageAndLimits Request: {}", serde_json::to_string_pretty(&serde_json::json!({
+        debug!("GetUserUsageAndLimits Request: {}", serde_json::to_string_pretty(&serde_json::json!({
             "url": url,
             "idp": idp
         })).unwrap_or_default());
 
-        let response = self.client
-            .post(&url)
-            .header("Content-Type", "application/cbor")
-            .header("Accept", "application/cbor")
-            .header("smithy-protocol", "rpc-v2-cbor")
-            .header("authorization", format!("Bearer {}", access_token))
-            // 不再需要 x-csrf-token
-            .header("Cookie", cookie)
-            .body(body)
-            .send()
-            .await
-            .map_err(|e| format!("GetUserUsageAndLimits request failed: {}", e))?;
+        let response = retry::send_idempotent(|_| {
+            self.client
+                .post(&url)
+                .header("Content-Type", "application/cbor")
+                .header("Accept", "application/cbor")
+                .header("smithy-protocol", "rpc-v2-cbor")
+                .header("authorization", format!("Bearer {}", access_token))
+                // 不再需要 x-csrf-token
+                .header("Cookie", &cookie)
+                .body(body.clone())
+        })
+        .await
+        .map_err(|e| AppError::network("GetUserUsageAndLimits request failed", e))?;
 
         let status = response.status();
         let bytes = response.bytes().await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+            .map_err(|e| AppError::network("Failed to read response", e))?;
 
         if !status.is_success() {
             let error_msg = if let Ok(error) = cbor_decode::<serde_json::Value>(&bytes) {
@@ -603,20 +688,15 @@ impl KiroWebPortalClient {
             } else {
                 String::from_utf8_lossy(&bytes).to_string()
             };
-            println!("[WebOAuth] GetUserUsageAndLimits Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
-            
-            // 423 Locked = AccountSuspendedException = 账号被封禁
-            if status.as_u16() == 423 || error_msg.contains("AccountSuspendedException") {
-                return Err("BANNED: 账号已被封禁".to_string());
-            }
-            return Err(format!("GetUserUsageAndLimits failed ({}): {}", status, error_msg));
+            warn!("GetUserUsageAndLimits Error: {}", serde_json::to_string_pretty(&serde_json::json!({"status": status.to_string(), "error": error_msg})).unwrap_or_default());
+            return Err(portal_error("GetUserUsageAndLimits", status, &error_msg));
         }
 
-        println!("[WebOAuth] GetUserUsageAndLimits Status: {} ({} bytes)", status, bytes.len());
+        info!("GetUserUsageAndLimits Status: {} ({} bytes)", status, bytes.len());
         
         // 打印原始响应体 (CBOR -> JSON)
         if let Ok(raw_json) = cbor_decode::<serde_json::Value>(&bytes) {
-            println!("[WebOAuth] GetUserUsageAndLimits Response Body: {}", 
+            debug!("GetUserUsageAndLimits Response Body: {}", 
                 serde_json::to_string_pretty(&raw_json).unwrap_or_default());
         }
         
@@ -652,16 +732,29 @@ impl WebOAuthProvider {
 
 #[async_trait]
 impl AuthProvider for WebOAuthProvider {
-    async fn login(&self) -> Result<AuthResult, String> {
+    async fn login(&self) -> AppResult<AuthResult> {
         // Web OAuth 需要两步流程，不能用单一的 login 方法
         // 请使用 initiate_login() 和 complete_login()
-        Err("Web OAuth requires two-step flow: use initiate_login() and complete_login()".to_string())
+        Err(AppError::Other("Web OAuth requires two-step flow: use initiate_login() and complete_login()".to_string()))
     }
 
-    async fn refresh_token(&self, _refresh_token: &str, _metadata: RefreshMetadata) -> Result<AuthResult, String> {
-        // Web OAuth 刷新需要 access_token 和 csrf_token，不能用这个方法
-        // 请使用 refresh_token_impl(access_token, csrf_token)
-        Err("Web OAuth refresh requires access_token and csrf_token. Use refresh_token_impl() instead.".to_string())
+    async fn refresh_token(&self, refresh_token: &str, metadata: RefreshMetadata) -> AppResult<AuthResult> {
+        // Web 门户刷新需要当前的 AccessToken 和 csrfToken（cookie + header）
+        let access_token = metadata.access_token.ok_or("Web OAuth refresh requires access_token")?;
+        let csrf_token = metadata.csrf_token.ok_or("Web OAuth refresh requires csrf_token")?;
+        let mut result = self.refresh_token_impl(&access_token, &csrf_token, refresh_token).await?;
+        if result.profile_arn.is_none() {
+            result.profile_arn = metadata.profile_arn;
+        }
+        Ok(result)
+    }
+
+    async fn revoke(&self, refresh_token: &str, metadata: RefreshMetadata) -> AppResult<()> {
+        let access_token = metadata.access_token.ok_or("Web OAuth logout requires access_token")?;
+        let csrf_token = metadata.csrf_token.ok_or("Web OAuth logout requires csrf_token")?;
+        KiroWebPortalClient::new()?
+            .logout(&access_token, &csrf_token, refresh_token, self.get_idp_name())
+            .await
     }
 
     fn get_provider_id(&self) -> &str {
@@ -675,15 +768,15 @@ impl AuthProvider for WebOAuthProvider {
 
 impl WebOAuthProvider {
     /// 发起登录 - 返回授权 URL 和需要保存的参数（不自动打开浏览器）
-    pub async fn initiate_login(&self) -> Result<WebOAuthInitResult, String> {
+    pub async fn initiate_login(&self) -> AppResult<WebOAuthInitResult> {
         let state = uuid::Uuid::new_v4().to_string();
         let code_verifier = generate_code_verifier();
         let code_challenge = generate_code_challenge(&code_verifier);
-        let redirect_uri = KIRO_REDIRECT_URI.to_string();
+        let redirect_uri = crate::endpoints::web_oauth_redirect_uri();
 
         let idp = self.get_idp_name();
         
-        println!("[WebOAuth] InitiateLogin Request: {}", serde_json::to_string_pretty(&serde_json::json!({
+        debug!("InitiateLogin Request: {}", serde_json::to_string_pretty(&serde_json::json!({
             "provider": self.provider_id,
             "idp": idp,
             "redirectUri": redirect_uri,
@@ -691,7 +784,7 @@ impl WebOAuthProvider {
             "codeChallenge": code_challenge
         })).unwrap_or_default());
 
-        let client = KiroWebPortalClient::new();
+        let client = KiroWebPortalClient::new()?;
         let initiate_response = client
             .initiate_login(idp, &redirect_uri, &code_challenge, &state)
             .await?;
@@ -699,9 +792,7 @@ impl WebOAuthProvider {
         let authorize_url = initiate_response.redirect_url
             .ok_or("No redirectUrl in InitiateLogin response")?;
         
-        println!("[WebOAuth] InitiateLogin Response: {}", serde_json::to_string_pretty(&serde_json::json!({
-            "redirectUrl": &authorize_url[..100.min(authorize_url.len())]
-        })).unwrap_or_default());
+        debug!("InitiateLogin redirect: {}", &authorize_url[..100.min(authorize_url.len())]);
 
         Ok(WebOAuthInitResult {
             authorize_url,
@@ -714,24 +805,24 @@ impl WebOAuthProvider {
     }
 
     /// 完成登录 - 用回调 URL 中的 code 换取 token
-    pub async fn complete_login(&self, code: &str, returned_state: &str, code_verifier: &str, _expected_state: &str) -> Result<AuthResult, String> {
+    pub async fn complete_login(&self, code: &str, returned_state: &str, code_verifier: &str, _expected_state: &str) -> AppResult<AuthResult> {
         // 注意：returned_state 是 AWS/Cognito 返回的 state（可能是编码后的值）
         // 需要传给 ExchangeToken API
 
         let idp = self.get_idp_name();
-        let redirect_uri = KIRO_REDIRECT_URI;
+        let redirect_uri = crate::endpoints::web_oauth_redirect_uri();
 
-        let client = KiroWebPortalClient::new();
+        let client = KiroWebPortalClient::new()?;
         let result = client
-            .exchange_token(idp, code, code_verifier, redirect_uri, returned_state)
+            .exchange_token(idp, code, code_verifier, &redirect_uri, returned_state)
             .await?;
 
-        println!("[WebOAuth] ExchangeToken Response: {}", serde_json::to_string_pretty(&serde_json::json!({
-            "accessToken": result.access_token.as_ref().map(|s| format!("{}...{}", &s[..20.min(s.len())], if s.len() > 30 { &s[s.len()-10..] } else { "" })),
-            "csrfToken": result.csrf_token,
+        debug!("ExchangeToken Response: {}", serde_json::to_string_pretty(&serde_json::json!({
+            "hasAccessToken": result.access_token.is_some(),
+            "hasCsrfToken": result.csrf_token.is_some(),
             "expiresIn": result.expires_in,
             "profileArn": result.profile_arn,
-            "sessionToken": result.session_token.as_ref().map(|s| format!("{}...", &s[..20.min(s.len())])),
+            "hasSessionToken": result.session_token.is_some(),
             "idp": result.idp,
         })).unwrap_or_default());
 
@@ -743,7 +834,7 @@ impl WebOAuthProvider {
         let expires_in = result.expires_in.unwrap_or(3600);
         let expires_at = chrono::Local::now() + chrono::Duration::seconds(expires_in);
 
-        println!("[WebOAuth] Login Success: {}", serde_json::to_string_pretty(&serde_json::json!({
+        info!("Login Success: {}", serde_json::to_string_pretty(&serde_json::json!({
             "provider": self.provider_id,
             "expiresIn": expires_in,
             "expiresAt": expires_at.format("%Y/%m/%d %H:%M:%S").to_string(),
@@ -756,7 +847,7 @@ impl WebOAuthProvider {
         
         Ok(AuthResult {
             access_token,
-            refresh_token,  // RefreshToken/SessionToken cookie
+            refresh_token: refresh_token.clone(),  // RefreshToken/SessionToken cookie
             expires_at: expires_at.format("%Y/%m/%d %H:%M:%S").to_string(),
             provider: self.provider_id.clone(),
             auth_method: "web_oauth".to_string(),
@@ -766,11 +857,13 @@ impl WebOAuthProvider {
             region: None,
             client_id: None,
             client_secret: None,
+            client_secret_expires_at: None,
             client_id_hash: None,
             sso_session_id: None,
+            start_url: None,
             profile_arn: result.profile_arn,
             csrf_token: Some(csrf_token),  // csrfToken
-            session_token: None,
+            session_token: Some(refresh_token),
         })
     }
 
@@ -778,28 +871,32 @@ impl WebOAuthProvider {
     /// access_token: 当前的 AccessToken
     /// csrf_token: 当前的 csrfToken
     /// session_token: 当前的 SessionToken
-    pub async fn refresh_token_impl(&self, access_token: &str, csrf_token: &str, session_token: &str) -> Result<AuthResult, String> {
+    pub async fn refresh_token_impl(&self, access_token: &str, csrf_token: &str, session_token: &str) -> AppResult<AuthResult> {
         let idp = self.get_idp_name();
-        let client = KiroWebPortalClient::new();
+        let client = KiroWebPortalClient::new()?;
         let token_response = client.refresh_token_with_cookies(access_token, csrf_token, session_token, idp).await?;
 
-        println!("[WebOAuth] RefreshToken Response: {}", serde_json::to_string_pretty(&serde_json::json!({
-            "accessToken": token_response.access_token.as_ref().map(|s| format!("{}...", &s[..20.min(s.len())])),
-            "csrfToken": token_response.csrf_token,
+        debug!("RefreshToken Response: {}", serde_json::to_string_pretty(&serde_json::json!({
+            "hasAccessToken": token_response.access_token.is_some(),
+            "hasCsrfToken": token_response.csrf_token.is_some(),
             "expiresIn": token_response.expires_in,
-            "profileArn": token_response.profile_arn
+            "profileArn": token_response.profile_arn,
+            "sessionTokenRotated": token_response.session_token.is_some()
         })).unwrap_or_default());
 
         let new_access_token = token_response.access_token
             .ok_or("No access_token in response")?;
         let new_csrf_token = token_response.csrf_token
             .ok_or("No csrf_token in response")?;
+        // 没有轮换时继续使用原来的 RefreshToken cookie
+        let new_session_token = token_response.session_token
+            .unwrap_or_else(|| session_token.to_string());
         let expires_in = token_response.expires_in.unwrap_or(3600);
         let expires_at = chrono::Local::now() + chrono::Duration::seconds(expires_in);
 
         Ok(AuthResult {
             access_token: new_access_token,
-            refresh_token: new_csrf_token.clone(),
+            refresh_token: new_session_token.clone(),
             expires_at: expires_at.format("%Y/%m/%d %H:%M:%S").to_string(),
             provider: self.provider_id.clone(),
             auth_method: "web_oauth".to_string(),
@@ -809,11 +906,13 @@ impl WebOAuthProvider {
             region: None,
             client_id: None,
             client_secret: None,
+            client_secret_expires_at: None,
             client_id_hash: None,
             sso_session_id: None,
+            start_url: None,
             profile_arn: token_response.profile_arn,
             csrf_token: Some(new_csrf_token),
-            session_token: None,
+            session_token: Some(new_session_token),
         })
     }
 }
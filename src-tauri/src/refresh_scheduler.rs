@@ -0,0 +1,339 @@
+// 后台 token 刷新调度
+// 按每个账号的 expiresAt 在过期前 margin 分钟刷新（再提前一个随机抖动，避免同时到期的账号扎堆请求）
+// 同时刷新的账号数有上限；应用在后台、离线、账号库锁定或关闭自动刷新时暂停
+// 每个账号的刷新过程通过 "token-refresh" 事件通知前端，调度状态变化通过 "refresh-scheduler-status" 通知
+
+use chrono::{DateTime, Duration, Local};
+use rand::Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{Notify, Semaphore};
+
+use crate::account::{AccountState};
+use crate::commands::account_cmd::refresh_token_by_id;
+use crate::commands::app_settings_cmd::get_refresh_settings;
+use crate::error::AppError;
+use crate::state::AppState;
+use tracing::{info, warn};
+
+/// 没有到期账号时最长睡眠时间（期间新增/修改的账号最迟在下一轮被发现）
+const SCAN_INTERVAL_SECS: i64 = 60;
+/// 随机抖动上限
+const MAX_JITTER_SECS: i64 = 120;
+/// 单个账号连续失败后的重试间隔：1, 2, 4 ... 最长 60 分钟
+const FAILURE_BACKOFF_MINUTES: i64 = 1;
+const MAX_BACKOFF_MINUTES: i64 = 60;
+/// 整批都因网络错误失败时，按离线处理暂停这么久
+const OFFLINE_PAUSE_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PauseReason {
+    Disabled,
+    Background,
+    Offline,
+    VaultLocked,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerStatus {
+    pub paused: Option<PauseReason>,
+    /// 正在刷新的账号数
+    pub running: usize,
+    pub next_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RefreshPhase {
+    Started,
+    Succeeded,
+    Failed,
+    /// 排队期间调度被暂停，未刷新
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRefreshEvent {
+    pub account_id: String,
+    pub email: String,
+    pub phase: RefreshPhase,
+    pub expires_at: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 由 main.rs 注册为 Tauri 托管状态
+pub struct RefreshScheduler {
+    background: AtomicBool,
+    offline: AtomicBool,
+    network_down_until: Mutex<Option<DateTime<Local>>>,
+    status: Mutex<SchedulerStatus>,
+    wake: Notify,
+}
+
+impl RefreshScheduler {
+    pub fn new() -> Self {
+        Self {
+            background: AtomicBool::new(false),
+            offline: AtomicBool::new(false),
+            network_down_until: Mutex::new(None),
+            status: Mutex::new(SchedulerStatus::default()),
+            wake: Notify::new(),
+        }
+    }
+
+    /// 前端上报窗口是否在后台、网络是否在线；恢复时立即补刷到期账号
+    pub fn set_activity(&self, background: Option<bool>, online: Option<bool>) {
+        if let Some(background) = background {
+            self.background.store(background, Ordering::SeqCst);
+        }
+        if let Some(online) = online {
+            self.offline.store(!online, Ordering::SeqCst);
+            if online {
+                *self.network_down_until.lock().unwrap() = None;
+            }
+        }
+        self.wake();
+    }
+
+    /// 设置或账号变化后让调度立即重新检查
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    pub fn status(&self) -> SchedulerStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    fn pause_reason(&self, enabled: bool, vault_locked: bool) -> Option<PauseReason> {
+        if !enabled {
+            return Some(PauseReason::Disabled);
+        }
+        if self.background.load(Ordering::SeqCst) {
+            return Some(PauseReason::Background);
+        }
+        let network_down = matches!(*self.network_down_until.lock().unwrap(), Some(t) if t > Local::now());
+        if self.offline.load(Ordering::SeqCst) || network_down {
+            return Some(PauseReason::Offline);
+        }
+        if vault_locked {
+            return Some(PauseReason::VaultLocked);
+        }
+        None
+    }
+
+    /// 排队中的刷新在开始前检查，暂停后不再发起新请求
+    fn is_paused(&self) -> bool {
+        self.background.load(Ordering::SeqCst) || self.offline.load(Ordering::SeqCst)
+    }
+
+    fn update_status<F: FnOnce(&mut SchedulerStatus)>(&self, app: &AppHandle, apply: F) {
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            apply(&mut status);
+            status.clone()
+        };
+        let _ = app.emit("refresh-scheduler-status", status);
+    }
+}
+
+/// 调度循环的内部状态
+#[derive(Default)]
+struct ScheduleState {
+    /// 账号 id -> (对应的 expiresAt, 抖动秒数)，expiresAt 变化后重新抽取
+    jitter: HashMap<String, (String, i64)>,
+    /// 账号 id -> (下次允许重试的时间, 连续失败次数)
+    backoff: HashMap<String, (DateTime<Local>, u32)>,
+}
+
+struct DueAccount {
+    id: String,
+    email: String,
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(run(app));
+}
+
+async fn run(app: AppHandle) {
+    info!("Started");
+    let mut schedule = ScheduleState::default();
+
+    loop {
+        let (enabled, margin_minutes, concurrency) = tokio::task::spawn_blocking(get_refresh_settings)
+            .await
+            .unwrap_or((true, 5, 3));
+
+        let scheduler = app.state::<RefreshScheduler>();
+        let (due, next_due, vault_locked) = {
+            let state = app.state::<AppState>();
+            let mut store = state.store.lock().unwrap();
+            let _ = store.reload_if_changed();
+            let vault_locked = store.vault_status().locked;
+            let (due, next_due) = collect_due(&store.accounts, margin_minutes, &mut schedule);
+            (due, next_due, vault_locked)
+        };
+
+        let paused = scheduler.pause_reason(enabled, vault_locked);
+        if scheduler.status().paused != paused {
+            info!("Paused: {:?}", paused);
+            scheduler.update_status(&app, |s| s.paused = paused);
+        }
+
+        if paused.is_none() && !due.is_empty() {
+            refresh_batch(&app, due, concurrency, &mut schedule).await;
+            scheduler.update_status(&app, |s| s.last_run_at = Some(Local::now().to_rfc3339()));
+            // 刷新后 expiresAt 已变化，立即重新计算
+            continue;
+        }
+
+        let now = Local::now();
+        let wake_at = match (paused, next_due) {
+            (None, Some(t)) => t.min(now + Duration::seconds(SCAN_INTERVAL_SECS)),
+            _ => now + Duration::seconds(SCAN_INTERVAL_SECS),
+        };
+        scheduler.update_status(&app, |s| {
+            s.next_run_at = match paused {
+                None => next_due.map(|t| t.to_rfc3339()),
+                Some(_) => None,
+            };
+        });
+
+        let sleep = (wake_at - now).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(sleep) => {}
+            _ = scheduler.wake.notified() => {}
+        }
+    }
+}
+
+/// 返回现在需要刷新的账号，以及之后最早需要刷新的时间
+fn collect_due(
+    accounts: &[crate::account::Account],
+    margin_minutes: u32,
+    schedule: &mut ScheduleState,
+) -> (Vec<DueAccount>, Option<DateTime<Local>>) {
+    let now = Local::now();
+    let margin = Duration::minutes(margin_minutes as i64);
+    let max_jitter = MAX_JITTER_SECS.min(margin.num_seconds() / 2);
+
+    schedule.jitter.retain(|id, _| accounts.iter().any(|a| &a.id == id));
+    schedule.backoff.retain(|id, _| accounts.iter().any(|a| &a.id == id));
+
+    let mut due = Vec::new();
+    let mut next_due: Option<DateTime<Local>> = None;
+    for account in accounts {
+        // 封禁或需要重新登录的账号刷新也没用
+        if account.refresh_token.is_none()
+            || matches!(account.status.state, AccountState::Banned | AccountState::NeedsReauth)
+        {
+            continue;
+        }
+        let (Some(expires_raw), Some(expires_at)) = (account.expires_at.as_ref(), account.expires_at_time()) else {
+            continue;
+        };
+
+        let jitter = match schedule.jitter.get(&account.id) {
+            Some((raw, jitter)) if raw == expires_raw => *jitter,
+            _ => {
+                let jitter = rand::thread_rng().gen_range(0..=max_jitter.max(0));
+                schedule.jitter.insert(account.id.clone(), (expires_raw.clone(), jitter));
+                jitter
+            }
+        };
+
+        let mut refresh_at = expires_at - margin - Duration::seconds(jitter);
+        if let Some((retry_at, _)) = schedule.backoff.get(&account.id) {
+            refresh_at = refresh_at.max(*retry_at);
+        }
+
+        if refresh_at <= now {
+            due.push(DueAccount { id: account.id.clone(), email: account.email.clone() });
+        } else if next_due.is_none_or(|t| refresh_at < t) {
+            next_due = Some(refresh_at);
+        }
+    }
+    (due, next_due)
+}
+
+async fn refresh_batch(app: &AppHandle, due: Vec<DueAccount>, concurrency: usize, schedule: &mut ScheduleState) {
+    info!("Refreshing {} account(s), concurrency {}", due.len(), concurrency);
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for account in due {
+        let app = app.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.ok();
+            let scheduler = app.state::<RefreshScheduler>();
+            let mut event = TokenRefreshEvent {
+                account_id: account.id.clone(),
+                email: account.email,
+                phase: RefreshPhase::Skipped,
+                expires_at: None,
+                error: None,
+            };
+            if scheduler.is_paused() {
+                let _ = app.emit("token-refresh", event);
+                return (account.id, None);
+            }
+
+            event.phase = RefreshPhase::Started;
+            let _ = app.emit("token-refresh", event.clone());
+            scheduler.update_status(&app, |s| s.running += 1);
+
+            let state = app.state::<AppState>();
+            let result = refresh_token_by_id(&state, &account.id).await;
+            scheduler.update_status(&app, |s| s.running = s.running.saturating_sub(1));
+
+            match &result {
+                Ok(updated) => {
+                    event.phase = RefreshPhase::Succeeded;
+                    event.expires_at = updated.expires_at.clone();
+                }
+                Err(e) => {
+                    warn!("{} refresh failed: {}", event.email, e);
+                    event.phase = RefreshPhase::Failed;
+                    event.error = Some(e.to_string());
+                }
+            }
+            let _ = app.emit("token-refresh", event);
+            (account.id, Some(result.err()))
+        });
+    }
+
+    let mut succeeded = 0;
+    let mut network_failures = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((id, outcome)) = joined else { continue };
+        match outcome {
+            // 被跳过
+            None => {}
+            Some(None) => {
+                succeeded += 1;
+                schedule.backoff.remove(&id);
+            }
+            Some(Some(error)) => {
+                if matches!(error, AppError::Network(_)) {
+                    network_failures += 1;
+                }
+                let failures = schedule.backoff.get(&id).map(|(_, n)| n + 1).unwrap_or(1);
+                let minutes = (FAILURE_BACKOFF_MINUTES << (failures - 1).min(6)).min(MAX_BACKOFF_MINUTES);
+                schedule.backoff.insert(id, (Local::now() + Duration::minutes(minutes), failures));
+            }
+        }
+    }
+
+    if succeeded == 0 && network_failures > 0 {
+        warn!("Network unavailable, pausing for {}s", OFFLINE_PAUSE_SECS);
+        let scheduler = app.state::<RefreshScheduler>();
+        *scheduler.network_down_until.lock().unwrap() = Some(Local::now() + Duration::seconds(OFFLINE_PAUSE_SECS));
+    }
+}
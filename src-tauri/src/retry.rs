@@ -0,0 +1,241 @@
+// 统一的请求重试策略，由调用方按请求是否幂等选择：
+// - send_idempotent：查询、注销等重复发送无副作用的请求
+//   可重试：网络错误（连接失败、超时）、429、408 和 5xx（501 除外）
+//   不重试：401 / 403 等其它 4xx，直接把响应交给调用方按原逻辑处理
+// - send_once：授权码换 token、刷新 token 等一次性请求，服务端可能已处理（授权码、refresh token 已作废），
+//   只在连接失败（请求未发出）时重试，超时和错误响应都直接返回
+// - 退避：指数增长 + 抖动，有上限；服务端返回 Retry-After 时按它等待（超过上限则不再重试）
+// 每次尝试都会重新构建请求，调用方可以据此填写 amz-sdk-request 等带尝试次数的请求头
+
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::time::Duration;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 总尝试次数（含第一次）
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    /// 指数退避的上限
+    pub max_delay: Duration,
+    /// 服务端要求等待超过这个时间时直接返回响应，不再重试
+    pub max_retry_after: Duration,
+    /// 请求是否幂等；非幂等请求只在连接失败时重试
+    pub idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            max_retry_after: Duration::from_secs(30),
+            idempotent: true,
+        }
+    }
+}
+
+/// 当前是第几次尝试，从 1 开始
+#[derive(Debug, Clone, Copy)]
+pub struct Attempt {
+    pub number: u32,
+    pub max: u32,
+}
+
+impl Attempt {
+    /// AWS SDK 的 amz-sdk-request 请求头
+    pub fn sdk_request_header(&self) -> String {
+        format!("attempt={}; max={}", self.number, self.max)
+    }
+}
+
+/// 状态码是否值得重试
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+}
+
+/// 网络层错误是否值得重试（构建请求、解码响应等错误重试也没用）
+pub fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request()
+}
+
+/// 解析 Retry-After：秒数或 HTTP 日期
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+impl RetryPolicy {
+    /// 非幂等请求的策略
+    pub fn once() -> Self {
+        Self { idempotent: false, ..Self::default() }
+    }
+
+    fn should_retry_error(&self, error: &reqwest::Error) -> bool {
+        if self.idempotent {
+            is_retryable_error(error)
+        } else {
+            error.is_connect()
+        }
+    }
+
+    /// 第 attempt 次失败后的等待时间：base * 2^(attempt-1)，封顶后取一半固定 + 一半随机
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.base_delay.saturating_mul(1 << (attempt.saturating_sub(1)).min(16));
+        let capped = exp.min(self.max_delay);
+        let half = capped / 2;
+        half + rand::thread_rng().gen_range(Duration::ZERO..=half)
+    }
+
+    /// 发送请求，按策略重试；build 每次尝试调用一次
+    /// 返回最后一次的响应（可能是不可重试或重试耗尽的错误响应），或最后一次的网络错误
+    pub async fn send<F>(&self, mut build: F) -> Result<Response, reqwest::Error>
+    where
+        F: FnMut(Attempt) -> RequestBuilder,
+    {
+        let max = self.max_attempts.max(1);
+        let mut number = 1;
+        loop {
+            let (client, request) = build(Attempt { number, max }).build_split();
+            let request = request?;
+            let target = format!("{} {}", request.method(), request.url().path());
+
+            let (wait, reason) = match client.execute(request).await {
+                Ok(response) => {
+                    let status = response.status();
+                    if !self.idempotent || !is_retryable_status(status) || number >= max {
+                        return Ok(response);
+                    }
+                    match retry_after(response.headers()) {
+                        Some(wait) if wait > self.max_retry_after => return Ok(response),
+                        Some(wait) => (wait, status.to_string()),
+                        None => (self.backoff(number), status.to_string()),
+                    }
+                }
+                Err(e) => {
+                    if !self.should_retry_error(&e) || number >= max {
+                        return Err(e);
+                    }
+                    (self.backoff(number), e.to_string())
+                }
+            };
+
+            warn!("{} attempt {}/{} failed ({}), retrying in {}ms", target, number, max, reason, wait.as_millis());
+            tokio::time::sleep(wait).await;
+            number += 1;
+        }
+    }
+}
+
+/// 发送幂等请求，用默认策略重试
+pub async fn send_idempotent<F>(build: F) -> Result<Response, reqwest::Error>
+where
+    F: FnMut(Attempt) -> RequestBuilder,
+{
+    RetryPolicy::default().send(build).await
+}
+
+/// 发送非幂等请求，只在连接失败时重试
+pub async fn send_once<F>(build: F) -> Result<Response, reqwest::Error>
+where
+    F: FnMut(Attempt) -> RequestBuilder,
+{
+    RetryPolicy::once().send(build).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn fast_policy(idempotent: bool) -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            idempotent,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// 本地服务，每个请求都返回 503，返回地址和收到的请求数
+    fn unavailable_server() -> (String, Arc<AtomicU32>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+        let hits = Arc::new(AtomicU32::new(0));
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = request.respond(tiny_http::Response::empty(503));
+            }
+        });
+        (url, hits)
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_and_is_capped() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            ..RetryPolicy::default()
+        };
+        for _ in 0..20 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.backoff(3);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            let capped = policy.backoff(30);
+            assert!(capped >= Duration::from_millis(500) && capped <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_date() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(reqwest::header::RETRY_AFTER, " 7 ".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        let at = (chrono::Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        headers.insert(reqwest::header::RETRY_AFTER, at.parse().unwrap());
+        let wait = retry_after(&headers).unwrap();
+        assert!(wait > Duration::from_secs(110) && wait <= Duration::from_secs(120));
+
+        // 已过去的时间点不用等待
+        headers.insert(reqwest::header::RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(reqwest::header::RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn idempotent_requests_retry_server_errors() {
+        let (url, hits) = unavailable_server();
+        let client = reqwest::Client::new();
+        let response = fast_policy(true).send(|_| client.get(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn one_shot_requests_are_sent_once() {
+        let (url, hits) = unavailable_server();
+        let client = reqwest::Client::new();
+        let response = fast_policy(false).send(|_| client.post(&url)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,507 @@
+// TokenManager - 按账号的认证方式选择 AuthProvider 刷新 token，并把结果写回账号
+// 所有刷新 token 的命令都经过这里，新 provider 只需在 providers::factory 中注册
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::account::{Account, AccountState, AccountStatus, AccountStore, SyncOperation};
+use crate::auth::get_usage_limits_desktop;
+use crate::codewhisperer_client::CodeWhispererClient;
+use crate::error::{AppError, AppResult};
+use crate::ide_token_sync;
+use crate::jwt::{self, TokenClaims};
+use crate::kiro::get_machine_id;
+use crate::providers::web_oauth::KiroWebPortalClient;
+use crate::providers::{create_provider, get_provider_config, AuthMethod, AuthProvider, AuthResult, ProviderConfig, RefreshMetadata};
+use tracing::{info, warn};
+
+pub struct TokenManager;
+
+/// 每个账号一把刷新锁，见 `TokenManager::lock_account`
+static REFRESH_LOCKS: OnceLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+
+impl TokenManager {
+    /// 账号的认证方式：优先用记录的 authMethod，否则按 provider 推断
+    pub fn auth_method(account: &Account) -> AuthMethod {
+        if let Some(method) = account.auth_method.as_deref().and_then(AuthMethod::parse) {
+            return method;
+        }
+        if let Some(config) = account.provider.as_deref().and_then(get_provider_config) {
+            return config.auth_method;
+        }
+        // 未知 provider：有客户端注册信息的按 IdC 处理
+        if account.client_id.is_some() { AuthMethod::Idc } else { AuthMethod::Social }
+    }
+
+    /// 按账号记录的 provider、region、start URL 创建对应认证方式的 provider
+    pub fn provider_for(account: &Account, auth_method: AuthMethod) -> Box<dyn AuthProvider> {
+        let provider_id = account.provider.clone().unwrap_or_else(|| "Google".to_string());
+        let mut config = get_provider_config(&provider_id)
+            .filter(|c| c.auth_method == auth_method)
+            .unwrap_or(ProviderConfig {
+                provider_id,
+                auth_method,
+                region: "us-east-1".to_string(),
+                start_url: None,
+            });
+        if let Some(region) = &account.region {
+            config.region = region.clone();
+        }
+        if account.start_url.is_some() {
+            config.start_url = account.start_url.clone();
+        }
+        create_provider(&config)
+    }
+
+    fn metadata_for(account: &Account) -> RefreshMetadata {
+        RefreshMetadata {
+            client_id: account.client_id.clone(),
+            client_secret: account.client_secret.clone(),
+            region: account.region.clone(),
+            client_id_hash: account.client_id_hash.clone(),
+            profile_arn: account.profile_arn.clone(),
+            access_token: account.access_token.clone(),
+            csrf_token: account.csrf_token.clone(),
+        }
+    }
+
+    /// 刷新 token，不修改账号
+    /// Web OAuth 账号走门户刷新；缺少 AccessToken/csrfToken 或门户拒绝当前会话时退回桌面端接口
+    pub async fn refresh(account: &Account) -> AppResult<AuthResult> {
+        let auth_method = Self::auth_method(account);
+        if auth_method != AuthMethod::WebOAuth {
+            return Self::refresh_with(account, auth_method).await;
+        }
+
+        if account.access_token.is_none() || account.csrf_token.is_none() {
+            warn!("Web portal refresh not possible (missing access/csrf token), using desktop API");
+            return Self::refresh_with(account, AuthMethod::Social).await;
+        }
+        match Self::refresh_with(account, AuthMethod::WebOAuth).await {
+            Err(e) if e.is_auth() => {
+                warn!("Web portal refresh rejected ({}), using desktop API", e);
+                Self::refresh_with(account, AuthMethod::Social).await
+            }
+            result => result,
+        }
+    }
+
+    async fn refresh_with(account: &Account, auth_method: AuthMethod) -> AppResult<AuthResult> {
+        let refresh_token = account.refresh_token.as_deref().ok_or("No refresh token")?;
+        if auth_method == AuthMethod::Idc && account.client_registration_expired() {
+            return Err(AppError::AuthExpired("客户端注册已过期或无效，请重新注册".to_string()));
+        }
+        let provider = Self::provider_for(account, auth_method);
+        info!("Refreshing {} ({}) token", provider.get_provider_id(), provider.get_auth_method());
+        provider.refresh_token(refresh_token, Self::metadata_for(account)).await
+    }
+
+    /// 在服务端注销账号的会话，refresh token 随之失效；token 本来就已失效时视为成功
+    /// Web OAuth 账号缺少 AccessToken/csrfToken 时和刷新一样退回桌面端接口
+    pub async fn sign_out(account: &Account) -> AppResult<()> {
+        let refresh_token = account.refresh_token.as_deref().ok_or("No refresh token")?;
+        let mut auth_method = Self::auth_method(account);
+        if auth_method == AuthMethod::WebOAuth && (account.access_token.is_none() || account.csrf_token.is_none()) {
+            auth_method = AuthMethod::Social;
+        }
+        let provider = Self::provider_for(account, auth_method);
+        info!("Signing out {} ({})", account.email, provider.get_auth_method());
+        match provider.revoke(refresh_token, Self::metadata_for(account)).await {
+            Err(e) if e.is_auth() => {
+                warn!("{} session already invalid: {}", account.email, e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// 把刷新结果写回账号，并记录刷新成功（只改内存，保存和同步到 IDE 见 save_refreshed）
+    pub fn apply(account: &mut Account, result: &AuthResult) {
+        account.access_token = Some(result.access_token.clone());
+        account.refresh_token = Some(result.refresh_token.clone());
+        // access token 本身是 JWT 时以其中的 exp 为准
+        account.expires_at = Some(
+            jwt::decode_claims(&result.access_token)
+                .and_then(|c| c.expires_at)
+                .unwrap_or_else(|| result.expires_at.clone()),
+        );
+        if result.id_token.is_some() {
+            account.id_token = result.id_token.clone();
+        }
+        if result.sso_session_id.is_some() {
+            account.sso_session_id = result.sso_session_id.clone();
+        }
+        if result.profile_arn.is_some() {
+            account.profile_arn = result.profile_arn.clone();
+        }
+        if result.csrf_token.is_some() {
+            account.csrf_token = result.csrf_token.clone();
+        }
+        if result.session_token.is_some() {
+            account.session_token = result.session_token.clone();
+        }
+        if result.region.is_some() {
+            account.region = result.region.clone();
+        }
+        if account.start_url.is_none() {
+            account.start_url = result.start_url.clone();
+        }
+        if account.client_id_hash.is_none() {
+            account.client_id_hash = result.client_id_hash.clone();
+        }
+        if result.client_secret_expires_at.is_some() {
+            account.client_secret_expires_at = result.client_secret_expires_at.clone();
+        }
+        if account.auth_method.is_none() {
+            account.auth_method = Some(result.auth_method.clone());
+        }
+        if let Some(claims) = Self::claims(result) {
+            Self::apply_claims(account, claims);
+        }
+        // 刷新成功说明 token 可用，封禁状态只由 usage 查询判定
+        if account.status.state != AccountState::Banned {
+            account.set_status(AccountStatus::active());
+        }
+        account.record_refresh_success();
+    }
+
+    /// 保存刷新后的账号，释放账号库锁后再同步到 IDE：IDE 正在用同一会话时，旧 refresh token 可能已被轮换
+    /// 保存失败也同步，IDE 不能继续用失效的 token
+    pub fn save_refreshed(mut store: MutexGuard<'_, AccountStore>, previous_refresh_token: Option<&str>, updated: &Account) -> AppResult<()> {
+        let saved = store.save_to_file();
+        drop(store);
+        ide_token_sync::push_if_active(previous_refresh_token, updated);
+        saved
+    }
+
+    /// 登录结果写入账号：登录方式、provider、客户端注册和 start URL 以本次登录为准，其余同 apply
+    pub fn apply_login(account: &mut Account, result: &AuthResult) {
+        account.auth_method = Some(result.auth_method.clone());
+        if !result.provider.is_empty() {
+            account.provider = Some(result.provider.clone());
+        }
+        if result.client_id.is_some() {
+            account.client_id = result.client_id.clone();
+            account.client_secret = result.client_secret.clone();
+            account.client_id_hash = result.client_id_hash.clone();
+        }
+        if result.start_url.is_some() {
+            account.start_url = result.start_url.clone();
+        }
+        Self::apply(account, result);
+    }
+
+    /// 登录/刷新结果里的 JWT claims：优先 id_token，其次 access token（是 JWT 时）
+    pub fn claims(result: &AuthResult) -> Option<TokenClaims> {
+        result.id_token.as_deref()
+            .and_then(jwt::decode_claims)
+            .or_else(|| jwt::decode_claims(&result.access_token))
+    }
+
+    /// 用 claims 补全账号缺失的身份信息，已有的值不覆盖
+    pub fn apply_claims(account: &mut Account, claims: TokenClaims) {
+        if account.user_id.is_none() {
+            account.user_id = claims.subject;
+        }
+        if is_placeholder_email(&account.email) {
+            if let Some(email) = claims.email {
+                account.email = email;
+            }
+        }
+        if account.provider.is_none() {
+            account.provider = claims.provider;
+        }
+    }
+
+    /// 同一账号的刷新串行执行：refresh token 每次刷新都会轮换，并发刷新时后到的请求用旧 token 会收到 invalid_grant，
+    /// 把正常账号误标为需要重新登录。所有刷新账号库中账号的路径（后台调度、批量同步、命令）都先取这把锁，
+    /// 拿到锁后再从账号库读取最新的 refresh token
+    pub async fn lock_account(id: &str) -> OwnedMutexGuard<()> {
+        let lock = REFRESH_LOCKS.get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+
+    /// 账号库中账号的当前副本
+    pub fn stored(store: &Mutex<AccountStore>, id: &str) -> AppResult<Account> {
+        store.lock().unwrap()
+            .accounts.iter()
+            .find(|a| a.id == id)
+            .cloned()
+            .ok_or_else(|| AppError::not_found("Account not found"))
+    }
+
+    /// 刷新账号库中的账号并保存；失败时记录错误和状态
+    pub async fn refresh_by_id(store: &Mutex<AccountStore>, id: &str) -> AppResult<Account> {
+        let _guard = Self::lock_account(id).await;
+        let account = Self::stored(store, id)?;
+
+        let result = Self::refresh(&account).await
+            .map_err(|e| store.lock().unwrap().record_refresh_error(id, e))?;
+
+        let mut store = store.lock().unwrap();
+        let updated = {
+            let a = store.accounts.iter_mut().find(|a| a.id == id)
+                .ok_or_else(|| AppError::not_found("Account not found after update"))?;
+            Self::apply(a, &result);
+            a.clone()
+        };
+        Self::save_refreshed(store, account.refresh_token.as_deref(), &updated)?;
+        Ok(updated)
+    }
+
+    /// 刷新 token 并查询 usage，写回账号库并保存
+    pub async fn sync_by_id(store: &Mutex<AccountStore>, id: &str) -> AppResult<Account> {
+        let _guard = Self::lock_account(id).await;
+        let account = Self::stored(store, id)?;
+
+        info!("Syncing {} account", account.provider.as_deref().unwrap_or("Google"));
+        let result = Self::refresh(&account).await
+            .map_err(|e| store.lock().unwrap().record_refresh_error(id, e))?;
+        let (usage_data, status, usage_error) = Self::fetch_usage(&account, &result).await;
+
+        let mut store = store.lock().unwrap();
+        let updated = {
+            let a = store.accounts.iter_mut().find(|a| a.id == id)
+                .ok_or_else(|| AppError::not_found("Account not found after update"))?;
+            Self::apply(a, &result);
+            a.usage_data = Some(usage_data);
+            if let Some(status) = status {
+                a.set_status(status);
+            }
+            match usage_error {
+                None => a.record_sync_success(),
+                Some(e) => a.record_error(SyncOperation::Usage, &e),
+            }
+            a.clone()
+        };
+        Self::save_refreshed(store, account.refresh_token.as_deref(), &updated)?;
+        Ok(updated)
+    }
+
+    /// 用刷新结果查询 usage（按实际使用的刷新方式选接口），返回 (原始响应, 状态, 错误)
+    /// 查询失败且无法判断账号状态时状态为 None
+    pub async fn fetch_usage(account: &Account, result: &AuthResult) -> (serde_json::Value, Option<AccountStatus>, Option<AppError>) {
+        let access_token = &result.access_token;
+        let profile_arn = result.profile_arn.as_deref().or(account.profile_arn.as_deref());
+        match AuthMethod::parse(&result.auth_method).unwrap_or_else(|| Self::auth_method(account)) {
+            AuthMethod::Idc => {
+                let cw_client = match CodeWhispererClient::new(&get_machine_id()) {
+                    Ok(client) => client.for_profile(profile_arn),
+                    Err(e) => return (serde_json::Value::Null, None, Some(e)),
+                };
+                let usage_call = cw_client.get_usage_limits(access_token).await;
+                let usage = usage_call.as_ref().ok().cloned();
+                (serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null), AccountStatus::from_usage_result(&usage_call), usage_call.err())
+            }
+            AuthMethod::Social => {
+                let usage_call = get_usage_limits_desktop(access_token, profile_arn).await;
+                let usage = usage_call.as_ref().ok().cloned();
+                (serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null), AccountStatus::from_usage_result(&usage_call), usage_call.err())
+            }
+            AuthMethod::WebOAuth => {
+                let idp = account.provider.as_deref().unwrap_or("Google");
+                let portal_client = match KiroWebPortalClient::new() {
+                    Ok(client) => client,
+                    Err(e) => return (serde_json::Value::Null, None, Some(e)),
+                };
+                let usage_call = portal_client.get_user_usage_and_limits(
+                    access_token,
+                    result.csrf_token.as_deref().unwrap_or(""),
+                    &result.refresh_token,
+                    idp,
+                ).await;
+                let usage = usage_call.as_ref().ok().cloned();
+                (serde_json::to_value(&usage).unwrap_or(serde_json::Value::Null), AccountStatus::from_usage_result(&usage_call), usage_call.err())
+            }
+        }
+    }
+}
+
+/// 登录时拿不到邮箱使用的占位值
+pub fn is_placeholder_email(email: &str) -> bool {
+    matches!(email, "" | "unknown@kiro.dev" | "user@builder.id" | "builderid@kiro.dev" | "unknown@builderid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn refresh_lock_is_per_account() {
+        let guard = TokenManager::lock_account("account-a").await;
+        // 其他账号不受影响
+        let _other = TokenManager::lock_account("account-b").await;
+        let pending = tokio::spawn(async { TokenManager::lock_account("account-a").await; });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!pending.is_finished());
+        drop(guard);
+        pending.await.unwrap();
+    }
+
+    // ==================== 注销（本地 mock 服务） ====================
+
+    /// mock 服务收到的请求
+    #[derive(Debug, Clone)]
+    struct Recorded {
+        path: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl Recorded {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers.iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    type Responder = fn(&Recorded) -> (u16, String);
+
+    /// 环境变量是进程级的，用到服务地址覆盖的测试逐个执行
+    static ENDPOINTS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// 启动 mock 服务，并把所有服务地址指向它（按服务加路径前缀区分）
+    fn mock_endpoints(respond: Responder) -> Arc<Mutex<Vec<Recorded>>> {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr());
+        for (var, prefix) in [
+            ("KIRO_DESKTOP_AUTH_ENDPOINT", "desktop"),
+            ("KIRO_WEB_PORTAL_ENDPOINT", "web"),
+            ("KIRO_OIDC_ENDPOINT", "oidc"),
+            ("KIRO_SSO_PORTAL_ENDPOINT", "portal"),
+        ] {
+            std::env::set_var(var, format!("{}/{}", base, prefix));
+        }
+        crate::http_client::use_direct_connection_for_tests();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                let entry = Recorded {
+                    path: request.url().to_string(),
+                    headers: request.headers().iter()
+                        .map(|h| (h.field.to_string(), h.value.to_string()))
+                        .collect(),
+                    body,
+                };
+                let (status, response) = respond(&entry);
+                recorded.lock().unwrap().push(entry);
+                let _ = request.respond(tiny_http::Response::from_string(response).with_status_code(status));
+            }
+        });
+        requests
+    }
+
+    fn paths(requests: &Arc<Mutex<Vec<Recorded>>>) -> Vec<String> {
+        requests.lock().unwrap().iter().map(|r| r.path.clone()).collect()
+    }
+
+    fn account(auth_method: &str, provider: &str) -> Account {
+        let mut account = Account::new("user@example.com".to_string(), "test".to_string());
+        account.auth_method = Some(auth_method.to_string());
+        account.provider = Some(provider.to_string());
+        account.access_token = Some("old-access".to_string());
+        account.refresh_token = Some("refresh".to_string());
+        account
+    }
+
+    fn idc_account() -> Account {
+        let mut account = account("IdC", "BuilderId");
+        account.client_id = Some("client".to_string());
+        account.client_secret = Some("secret".to_string());
+        account.region = Some("us-east-1".to_string());
+        account
+    }
+
+    #[tokio::test]
+    async fn idc_sign_out_uses_the_current_access_token() {
+        let _env = ENDPOINTS.lock().await;
+        let requests = mock_endpoints(|_| (200, String::new()));
+
+        TokenManager::sign_out(&idc_account()).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/portal/logout");
+        assert_eq!(requests[0].header("x-amz-sso_bearer_token"), Some("old-access"));
+    }
+
+    #[tokio::test]
+    async fn idc_sign_out_refreshes_an_expired_access_token_first() {
+        let _env = ENDPOINTS.lock().await;
+        let requests = mock_endpoints(|request| match request.path.as_str() {
+            "/oidc/token" => (200, r#"{"accessToken":"new-access","refreshToken":"refresh-2","expiresIn":3600}"#.to_string()),
+            "/portal/logout" if request.header("x-amz-sso_bearer_token") == Some("new-access") => (200, String::new()),
+            _ => (401, r#"{"message":"expired"}"#.to_string()),
+        });
+
+        TokenManager::sign_out(&idc_account()).await.unwrap();
+
+        assert_eq!(paths(&requests), vec!["/portal/logout", "/oidc/token", "/portal/logout"]);
+        let requests = requests.lock().unwrap();
+        assert!(requests[1].body.contains(r#""refreshToken":"refresh""#));
+        assert_eq!(requests[2].header("x-amz-sso_bearer_token"), Some("new-access"));
+    }
+
+    #[tokio::test]
+    async fn social_sign_out_calls_desktop_logout() {
+        let _env = ENDPOINTS.lock().await;
+        let requests = mock_endpoints(|_| (200, String::new()));
+
+        TokenManager::sign_out(&account("social", "Google")).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/desktop/logout");
+        assert!(requests[0].body.contains(r#""refreshToken":"refresh""#));
+    }
+
+    #[tokio::test]
+    async fn web_oauth_sign_out_calls_portal_logout() {
+        let _env = ENDPOINTS.lock().await;
+        let requests = mock_endpoints(|_| (200, String::new()));
+        let mut account = account("web_oauth", "Google");
+        account.csrf_token = Some("csrf".to_string());
+
+        TokenManager::sign_out(&account).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/web/service/KiroWebPortalService/operation/Logout");
+        assert_eq!(requests[0].header("x-csrf-token"), Some("csrf"));
+        assert!(requests[0].header("Cookie").is_some_and(|c| c.contains("RefreshToken=refresh")));
+    }
+
+    #[tokio::test]
+    async fn already_invalid_session_counts_as_signed_out() {
+        let _env = ENDPOINTS.lock().await;
+        let requests = mock_endpoints(|request| match request.path.as_str() {
+            "/oidc/token" => (400, r#"{"error":"invalid_grant"}"#.to_string()),
+            _ => (401, r#"{"message":"expired"}"#.to_string()),
+        });
+
+        TokenManager::sign_out(&account("social", "Google")).await.unwrap();
+        TokenManager::sign_out(&idc_account()).await.unwrap();
+
+        assert_eq!(paths(&requests), vec!["/desktop/logout", "/portal/logout", "/oidc/token"]);
+    }
+
+    #[tokio::test]
+    async fn sign_out_reports_server_errors() {
+        let _env = ENDPOINTS.lock().await;
+        mock_endpoints(|_| (400, r#"{"message":"bad request"}"#.to_string()));
+
+        let err = TokenManager::sign_out(&account("social", "Google")).await.unwrap_err();
+        assert!(!err.is_auth(), "{:?}", err);
+    }
+}
@@ -0,0 +1,533 @@
+// 账号库加密 (Vault)
+// 主密码经 Argon2id 派生密钥，Account 的敏感字段用 AES-256-GCM 封存
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+use crate::account::Account;
+use crate::fs_util::write_atomic;
+
+const VAULT_VERSION: u32 = 1;
+const SEALED_PREFIX: &str = "v1";
+const VERIFIER_PLAINTEXT: &[u8] = b"kiro-account-manager-vault";
+const VERIFIER_AAD: &[u8] = b"vault-verifier";
+
+// Argon2id 默认参数 (OWASP 推荐: 19 MiB, 2 次迭代, 1 并行度)
+const DEFAULT_M_COST: u32 = 19 * 1024;
+const DEFAULT_T_COST: u32 = 2;
+const DEFAULT_P_COST: u32 = 1;
+// 导入的加密备份可以指定参数，超过上限的拒绝，避免构造的文件耗尽内存或长时间卡住
+const MAX_M_COST: u32 = DEFAULT_M_COST * 4;
+const MAX_T_COST: u32 = DEFAULT_T_COST * 4;
+const MAX_P_COST: u32 = 4;
+
+// ============================================================
+// Vault 头信息 (vault.json)
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultHeader {
+    pub version: u32,
+    pub kdf: String,
+    pub salt: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    /// 用派生密钥封存的固定明文，用于校验主密码
+    pub verifier: String,
+}
+
+/// Account 中需要加密的字段
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountSecrets {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    client_secret: Option<String>,
+    session_token: Option<String>,
+    // 旧版本封存的数据没有这两个字段，反序列化时为 None
+    id_token: Option<String>,
+    csrf_token: Option<String>,
+}
+
+impl AccountSecrets {
+    fn take_from(account: &mut Account) -> Self {
+        Self {
+            access_token: account.access_token.take(),
+            refresh_token: account.refresh_token.take(),
+            client_secret: account.client_secret.take(),
+            session_token: account.session_token.take(),
+            id_token: account.id_token.take(),
+            csrf_token: account.csrf_token.take(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.access_token.is_none()
+            && self.refresh_token.is_none()
+            && self.client_secret.is_none()
+            && self.session_token.is_none()
+            && self.id_token.is_none()
+            && self.csrf_token.is_none()
+    }
+
+    fn restore_into(self, account: &mut Account) {
+        account.access_token = self.access_token;
+        account.refresh_token = self.refresh_token;
+        account.client_secret = self.client_secret;
+        account.session_token = self.session_token;
+        account.id_token = self.id_token;
+        account.csrf_token = self.csrf_token;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub enabled: bool,
+    pub locked: bool,
+}
+
+// ============================================================
+// Vault
+// ============================================================
+
+pub struct Vault {
+    header_path: PathBuf,
+    header: Option<VaultHeader>,
+    key: Option<[u8; 32]>,
+}
+
+/// 更换主密码前的头信息和密钥，失败时用于回滚
+pub struct PreviousKey {
+    header: VaultHeader,
+    key: [u8; 32],
+}
+
+impl Drop for PreviousKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl Vault {
+    /// 从 accounts.json 同目录加载 vault.json（不存在表示未启用加密）
+    pub fn load(store_dir: &Path) -> Self {
+        let header_path = store_dir.join("vault.json");
+        let header = read_header(&header_path);
+        Self {
+            header_path,
+            header,
+            key: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.header.is_some()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.is_enabled() && self.key.is_none()
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        VaultStatus {
+            enabled: self.is_enabled(),
+            locked: self.is_locked(),
+        }
+    }
+
+    /// 启用加密：生成盐和校验值，写入 vault.json，保持解锁状态
+    pub fn enable(&mut self, passphrase: &str) -> Result<(), String> {
+        if self.is_enabled() {
+            return Err("账号库加密已启用".to_string());
+        }
+        if passphrase.is_empty() {
+            return Err("主密码不能为空".to_string());
+        }
+
+        let (header, key) = new_header(passphrase)?;
+        self.write_header(&self.header_path, &header)?;
+        self.header = Some(header);
+        self.key = Some(key);
+        Ok(())
+    }
+
+    /// 关闭加密：删除 vault.json（调用方负责随后以明文保存）
+    pub fn disable(&mut self, passphrase: &str) -> Result<(), String> {
+        self.unlock(passphrase)?;
+        if self.header_path.exists() {
+            std::fs::remove_file(&self.header_path)
+                .map_err(|e| format!("删除 vault.json 失败: {}", e))?;
+        }
+        self.discard_pending();
+        self.header = None;
+        self.lock();
+        Ok(())
+    }
+
+    /// 校验主密码并保存派生密钥
+    /// 上次更换主密码中途中断时，账号可能已用新密钥封存，此时也接受 vault.pending.json 中的新密码
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), String> {
+        let header = self.header.as_ref().ok_or("账号库未启用加密")?;
+        if let Some(key) = verify_passphrase(header, passphrase)? {
+            self.key = Some(key);
+            return Ok(());
+        }
+        if let Some(pending) = self.read_pending() {
+            if let Some(key) = verify_passphrase(&pending, passphrase)? {
+                self.header = Some(pending);
+                self.key = Some(key);
+                return Ok(());
+            }
+        }
+        Err("主密码错误".to_string())
+    }
+
+    /// 解锁并确认账号能用当前密钥解封后调用：写入当前头信息并清理中断遗留的 vault.pending.json
+    pub fn settle_pending(&self) -> Result<(), String> {
+        let pending_path = self.pending_path();
+        if !pending_path.exists() {
+            return Ok(());
+        }
+        if let Some(header) = &self.header {
+            self.write_header(&self.header_path, header)?;
+        }
+        std::fs::remove_file(&pending_path)
+            .map_err(|e| format!("删除 vault.pending.json 失败: {}", e))
+    }
+
+    /// 解锁后账号无法解封时调用：清除密钥，并恢复为磁盘上的 vault.json（撤销对 pending 的采用）
+    pub fn abort_unlock(&mut self) {
+        self.lock();
+        self.header = read_header(&self.header_path);
+    }
+
+    /// 清除内存中的密钥
+    pub fn lock(&mut self) {
+        if let Some(mut key) = self.key.take() {
+            key.zeroize();
+        }
+    }
+
+    /// 开始更换主密码：新头信息先写入 vault.pending.json，内存中切换为新密钥
+    /// 调用方随后用新密钥重新保存账号，成功后 `commit_passphrase`，失败则 `rollback_passphrase`
+    pub fn begin_change_passphrase(&mut self, old_passphrase: &str, new_passphrase: &str) -> Result<PreviousKey, String> {
+        if new_passphrase.is_empty() {
+            return Err("主密码不能为空".to_string());
+        }
+        let current = self.header.clone().ok_or("账号库未启用加密")?;
+        let old_key = verify_passphrase(&current, old_passphrase)?.ok_or("主密码错误")?;
+        let previous = PreviousKey { header: current, key: old_key };
+
+        let (header, key) = new_header(new_passphrase)?;
+        self.write_header(&self.pending_path(), &header)?;
+        self.lock();
+        self.header = Some(header);
+        self.key = Some(key);
+        Ok(previous)
+    }
+
+    /// 账号已用新密钥写入磁盘后，原子替换 vault.json
+    pub fn commit_passphrase(&self) -> Result<(), String> {
+        self.settle_pending()
+    }
+
+    /// 恢复更换前的头信息和密钥
+    pub fn rollback_passphrase(&mut self, previous: PreviousKey) {
+        self.lock();
+        self.header = Some(previous.header.clone());
+        self.key = Some(previous.key);
+    }
+
+    /// 放弃未提交的新头信息（磁盘上的账号仍是旧密钥封存时调用）
+    pub fn discard_pending(&self) {
+        let _ = std::fs::remove_file(self.pending_path());
+    }
+
+    /// 封存账号的敏感字段，返回可写入磁盘的副本
+    pub fn seal_account(&self, account: &Account) -> Result<Account, String> {
+        let key = self.key.as_ref().ok_or("账号库已锁定")?;
+        let mut sealed = account.clone();
+        let secrets = AccountSecrets::take_from(&mut sealed);
+        sealed.sealed_secrets = if secrets.is_empty() {
+            None
+        } else {
+            let plain = serde_json::to_vec(&secrets)
+                .map_err(|e| format!("序列化敏感字段失败: {}", e))?;
+            Some(seal_bytes(key, &plain, account.id.as_bytes())?)
+        };
+        Ok(sealed)
+    }
+
+    /// 解封账号的敏感字段（未封存的明文账号原样返回，下次保存时会被封存）
+    pub fn open_account(&self, mut account: Account) -> Result<Account, String> {
+        let key = self.key.as_ref().ok_or("账号库已锁定")?;
+        if let Some(sealed) = account.sealed_secrets.take() {
+            let plain = open_bytes(key, &sealed, account.id.as_bytes())
+                .map_err(|e| format!("账号 {} 解密失败: {}", account.email, e))?;
+            let secrets: AccountSecrets = serde_json::from_slice(&plain)
+                .map_err(|e| format!("账号 {} 敏感字段解析失败: {}", account.email, e))?;
+            secrets.restore_into(&mut account);
+        }
+        Ok(account)
+    }
+
+    fn pending_path(&self) -> PathBuf {
+        self.header_path.with_file_name("vault.pending.json")
+    }
+
+    fn read_pending(&self) -> Option<VaultHeader> {
+        read_header(&self.pending_path())
+    }
+
+    fn write_header(&self, path: &Path, header: &VaultHeader) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(header)
+            .map_err(|e| format!("序列化 vault.json 失败: {}", e))?;
+        write_atomic(path, content.as_bytes())
+            .map_err(|e| format!("写入 vault.json 失败: {}", e))
+    }
+}
+
+impl Drop for Vault {
+    fn drop(&mut self) {
+        self.lock();
+    }
+}
+
+// ============================================================
+// 密码加密（导出的加密备份使用，与账号库主密码无关）
+// ============================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordSealed {
+    pub kdf: String,
+    pub salt: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    /// "v1:<nonce>:<ciphertext>"
+    pub data: String,
+}
+
+pub fn seal_with_password(plain: &[u8], password: &str, aad: &[u8]) -> Result<PasswordSealed, String> {
+    if password.is_empty() {
+        return Err("密码不能为空".to_string());
+    }
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let mut sealed = PasswordSealed {
+        kdf: "argon2id".to_string(),
+        salt: STANDARD.encode(salt),
+        m_cost: DEFAULT_M_COST,
+        t_cost: DEFAULT_T_COST,
+        p_cost: DEFAULT_P_COST,
+        data: String::new(),
+    };
+    let mut key = derive_key_with(&sealed.kdf, &sealed.salt, sealed.m_cost, sealed.t_cost, sealed.p_cost, password)?;
+    let data = seal_bytes(&key, plain, aad);
+    key.zeroize();
+    sealed.data = data?;
+    Ok(sealed)
+}
+
+pub fn open_with_password(sealed: &PasswordSealed, password: &str, aad: &[u8]) -> Result<Vec<u8>, String> {
+    let mut key = derive_key_with(&sealed.kdf, &sealed.salt, sealed.m_cost, sealed.t_cost, sealed.p_cost, password)?;
+    let plain = open_bytes(&key, &sealed.data, aad);
+    key.zeroize();
+    plain.map_err(|_| "密码错误或文件已损坏".to_string())
+}
+
+// ============================================================
+// 密钥派生与 AEAD
+// ============================================================
+
+fn read_header(path: &Path) -> Option<VaultHeader> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// 生成新的盐和校验值
+fn new_header(passphrase: &str) -> Result<(VaultHeader, [u8; 32]), String> {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    let mut header = VaultHeader {
+        version: VAULT_VERSION,
+        kdf: "argon2id".to_string(),
+        salt: STANDARD.encode(salt),
+        m_cost: DEFAULT_M_COST,
+        t_cost: DEFAULT_T_COST,
+        p_cost: DEFAULT_P_COST,
+        verifier: String::new(),
+    };
+    let key = derive_key(&header, passphrase)?;
+    header.verifier = seal_bytes(&key, VERIFIER_PLAINTEXT, VERIFIER_AAD)?;
+    Ok((header, key))
+}
+
+/// 主密码正确时返回派生密钥，错误时返回 None
+fn verify_passphrase(header: &VaultHeader, passphrase: &str) -> Result<Option<[u8; 32]>, String> {
+    let mut key = derive_key(header, passphrase)?;
+    match open_bytes(&key, &header.verifier, VERIFIER_AAD) {
+        Ok(plain) if plain == VERIFIER_PLAINTEXT => Ok(Some(key)),
+        _ => {
+            key.zeroize();
+            Ok(None)
+        }
+    }
+}
+
+fn derive_key(header: &VaultHeader, passphrase: &str) -> Result<[u8; 32], String> {
+    derive_key_with(&header.kdf, &header.salt, header.m_cost, header.t_cost, header.p_cost, passphrase)
+}
+
+fn derive_key_with(kdf: &str, salt: &str, m_cost: u32, t_cost: u32, p_cost: u32, passphrase: &str) -> Result<[u8; 32], String> {
+    if kdf != "argon2id" {
+        return Err(format!("不支持的 KDF: {}", kdf));
+    }
+    if m_cost > MAX_M_COST || t_cost > MAX_T_COST || p_cost > MAX_P_COST {
+        return Err(format!("Argon2 参数超出上限 (m={}, t={}, p={})", m_cost, t_cost, p_cost));
+    }
+    let salt = STANDARD.decode(salt)
+        .map_err(|e| format!("盐值无效: {}", e))?;
+    let params = Params::new(m_cost, t_cost, p_cost, Some(32))
+        .map_err(|e| format!("Argon2 参数无效: {}", e))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("密钥派生失败: {}", e))?;
+    Ok(key)
+}
+
+/// 加密为 "v1:<nonce>:<ciphertext>" (base64)
+fn seal_bytes(key: &[u8; 32], plain: &[u8], aad: &[u8]) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plain, aad })
+        .map_err(|_| "加密失败".to_string())?;
+    Ok(format!(
+        "{}:{}:{}",
+        SEALED_PREFIX,
+        STANDARD.encode(nonce),
+        STANDARD.encode(ciphertext)
+    ))
+}
+
+fn open_bytes(key: &[u8; 32], sealed: &str, aad: &[u8]) -> Result<Vec<u8>, String> {
+    let mut parts = sealed.splitn(3, ':');
+    let (prefix, nonce, ciphertext) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(p), Some(n), Some(c)) => (p, n, c),
+        _ => return Err("密文格式无效".to_string()),
+    };
+    if prefix != SEALED_PREFIX {
+        return Err(format!("不支持的密文版本: {}", prefix));
+    }
+    let nonce = STANDARD.decode(nonce).map_err(|_| "密文格式无效".to_string())?;
+    let ciphertext = STANDARD.decode(ciphertext).map_err(|_| "密文格式无效".to_string())?;
+    if nonce.len() != 12 {
+        return Err("密文格式无效".to_string());
+    }
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad })
+        .map_err(|_| "密钥不匹配或数据已损坏".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kiro-vault-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn account_with_secrets() -> Account {
+        let mut account = Account::new("user@example.com".to_string(), "test".to_string());
+        account.access_token = Some("access".to_string());
+        account.refresh_token = Some("refresh".to_string());
+        account.id_token = Some("id".to_string());
+        account.csrf_token = Some("csrf".to_string());
+        account
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let dir = temp_dir();
+        let mut vault = Vault::load(&dir);
+        vault.enable("passphrase").unwrap();
+
+        let account = account_with_secrets();
+        let sealed = vault.seal_account(&account).unwrap();
+        assert!(sealed.sealed_secrets.is_some());
+        assert!(sealed.access_token.is_none() && sealed.refresh_token.is_none());
+        assert!(sealed.id_token.is_none() && sealed.csrf_token.is_none());
+
+        let mut reloaded = Vault::load(&dir);
+        assert!(reloaded.is_locked());
+        assert!(reloaded.unlock("wrong").is_err());
+        reloaded.unlock("passphrase").unwrap();
+        let opened = reloaded.open_account(sealed).unwrap();
+        assert_eq!(opened.refresh_token.as_deref(), Some("refresh"));
+        assert_eq!(opened.id_token.as_deref(), Some("id"));
+        assert_eq!(opened.csrf_token.as_deref(), Some("csrf"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_oversized_kdf_params() {
+        let mut sealed = seal_with_password(b"data", "password", b"aad").unwrap();
+        sealed.m_cost = 4 * 1024 * 1024;
+        assert!(open_with_password(&sealed, "password", b"aad").unwrap_err().contains("上限"));
+    }
+
+    #[test]
+    fn interrupted_passphrase_change_unlocks_with_new_passphrase() {
+        let dir = temp_dir();
+        let mut vault = Vault::load(&dir);
+        vault.enable("old").unwrap();
+        let _previous = vault.begin_change_passphrase("old", "new").unwrap();
+        // 账号已用新密钥写入，vault.json 尚未替换时崩溃
+        let sealed = vault.seal_account(&account_with_secrets()).unwrap();
+
+        let mut reloaded = Vault::load(&dir);
+        reloaded.unlock("new").unwrap();
+        assert!(reloaded.open_account(sealed).is_ok());
+        reloaded.settle_pending().unwrap();
+
+        let mut settled = Vault::load(&dir);
+        assert!(settled.unlock("old").is_err());
+        settled.unlock("new").unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rollback_restores_old_key() {
+        let dir = temp_dir();
+        let mut vault = Vault::load(&dir);
+        vault.enable("old").unwrap();
+        let previous = vault.begin_change_passphrase("old", "new").unwrap();
+        vault.rollback_passphrase(previous);
+        vault.discard_pending();
+
+        let sealed = vault.seal_account(&account_with_secrets()).unwrap();
+        let mut reloaded = Vault::load(&dir);
+        assert!(reloaded.unlock("new").is_err());
+        reloaded.unlock("old").unwrap();
+        assert!(reloaded.open_account(sealed).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,8 +1,10 @@
-import { useState } from 'react'
+import { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { X, Copy, Check, RefreshCw, User, CreditCard, Key, Clock, ChevronDown, ChevronUp, Shield } from 'lucide-react'
 import { useTheme } from '../contexts/ThemeContext'
 import { useDialog } from '../contexts/DialogContext'
+import { isActive, isBanned, getStatusLabel, getStatusTitle } from '../utils/accountStatus'
+import { errorMessage } from '../utils/errors'
 
 function AccountDetailModal({ account, onClose }) {
   const { theme, colors } = useTheme()
@@ -23,6 +25,14 @@ function AccountDetailModal({ account, onClose }) {
   const [refreshing, setRefreshing] = useState(false)
   const [copied, setCopied] = useState(null)
   const [showTokens, setShowTokens] = useState(true)
+  // id_token / access token 解码出的 claims（token 是 JWT 时才有）
+  const [claims, setClaims] = useState(null)
+
+  useEffect(() => {
+    invoke('get_account_detail', { id: account.id })
+      .then(detail => setClaims({ idToken: detail.idTokenClaims, accessToken: detail.accessTokenClaims }))
+      .catch(() => setClaims(null))
+  }, [account.id])
 
 
 
@@ -34,7 +44,7 @@ function AccountDetailModal({ account, onClose }) {
       const used = updated.usageData?.usageBreakdownList?.[0]?.currentUsage ?? 0
       setForm(prev => ({ ...prev, quota, used, status: updated.status }))
     } catch (e) {
-      await showError("刷新失败", e.toString())
+      await showError("刷新失败", errorMessage(e))
     } finally {
       setRefreshing(false)
     }
@@ -341,16 +351,41 @@ function AccountDetailModal({ account, onClose }) {
                 </div>
               )}
             </div>
+
+            {/* Token Claims */}
+            {(claims?.idToken || claims?.accessToken) && (
+              <div className={`${colors.card} rounded-xl p-5 shadow-sm`}>
+                <div className="flex items-center gap-2 mb-4">
+                  <Shield size={18} className={colors.textMuted} />
+                  <span className={`font-medium ${colors.text}`}>{"Token Claims"}</span>
+                </div>
+                <div className="space-y-4">
+                  {[['ID Token', claims.idToken], ['Access Token', claims.accessToken]].filter(([, c]) => c).map(([name, c]) => (
+                    <div key={name}>
+                      <div className={`text-xs font-medium ${colors.textMuted} mb-2`}>{name}</div>
+                      <div className="grid grid-cols-2 gap-x-4 gap-y-1.5 text-xs">
+                        {[['Subject', c.subject], ['Email', c.email], ['Issuer', c.issuer], ['Provider', c.provider], ['签发时间', c.issuedAt], ['过期时间', c.expiresAt]].filter(([, v]) => v).map(([label, value]) => (
+                          <div key={label} className="min-w-0">
+                            <span className={colors.textMuted}>{label}: </span>
+                            <span className={`font-mono break-all ${colors.text}`}>{value}</span>
+                          </div>
+                        ))}
+                      </div>
+                    </div>
+                  ))}
+                </div>
+              </div>
+            )}
           </div>
 
           {/* Footer */}
           <div className={`flex justify-between items-center px-6 py-4 ${colors.card} border-t ${colors.cardBorder}`}>
             <div className={`text-xs ${colors.textMuted}`}>
-              {account.status === '正常' || account.status === '有效' 
+              {isActive(account)
                 ? <span className="flex items-center gap-1 text-green-500"><Shield size={12} />{"账号正常"}</span> 
-                : account.status === '封禁' || account.status === '已封禁'
-                  ? <span className="flex items-center gap-1 text-red-500"><Shield size={12} />{"账号已封禁"}</span>
-                  : <span className="flex items-center gap-1 text-orange-500"><Shield size={12} />{account.status}</span>}
+                : isBanned(account)
+                  ? <span className="flex items-center gap-1 text-red-500" title={getStatusTitle(account)}><Shield size={12} />{"账号已封禁"}</span>
+                  : <span className="flex items-center gap-1 text-orange-500" title={getStatusTitle(account)}><Shield size={12} />{getStatusLabel(account)}</span>}
             </div>
             <button type="button" onClick={onClose} className="px-5 py-2 bg-blue-500 text-white rounded-lg text-sm font-medium hover:bg-blue-600">
               关闭
@@ -2,6 +2,7 @@ import { RefreshCw, Eye, Trash2, Copy, Check, Clock, Repeat, Edit2 } from 'lucid
 import { useTheme } from '../../contexts/ThemeContext'
 import { getUsagePercent, getProgressBarColor } from './hooks/useAccountStats'
 import { getQuota, getUsed, getSubType, getSubPlan } from '../../utils/accountStats'
+import { isActive, isBanned as isStatusBanned, getStatusLabel, getStatusTitle, getRegistrationDaysLeft, needsReregistration } from '../../utils/accountStatus'
 
 function AccountCard({
   account,
@@ -14,6 +15,7 @@ function AccountCard({
   onEdit,
   onEditLabel,
   onDelete,
+  onReregister,
   refreshingId,
   switchingId,
   isCurrentAccount,
@@ -28,8 +30,9 @@ function AccountCard({
   const breakdown = account.usageData?.usageBreakdownList?.[0]
   const percent = getUsagePercent(used, quota)
   const isExpired = account.expiresAt && new Date(account.expiresAt.replace(/\//g, '-')) < new Date()
-  const isBanned = account.status === '封禁' || account.status === '已封禁'
-  const isNormal = account.status === '正常' || account.status === '有效'
+  const isBanned = isStatusBanned(account)
+  const isNormal = isActive(account)
+  const registrationDaysLeft = getRegistrationDaysLeft(account)
 
   // 状态光环颜色
   const glowColor = isCurrentAccount
@@ -65,12 +68,12 @@ function AccountCard({
       {/* 状态标签 */}
       <div className="absolute top-3 right-3 flex items-center gap-2">
         <span className={`inline-flex px-2 py-0.5 rounded text-xs font-medium ${
-          account.status === '正常' || account.status === '有效'
+          isNormal
             ? (isDark ? 'bg-green-500/20 text-green-400' : 'bg-green-100 text-green-700')
-            : account.status === '封禁' || account.status === '已封禁'
+            : isBanned
               ? (isDark ? 'bg-red-500/20 text-red-400' : 'bg-red-100 text-red-600')
               : (isDark ? 'bg-orange-500/20 text-orange-400' : 'bg-orange-100 text-orange-600')
-        }`}>{account.status}</span>
+        }`} title={getStatusTitle(account)}>{getStatusLabel(account)}</span>
       </div>
 
       <div className="p-4 pt-10 flex-1 flex flex-col">
@@ -153,6 +156,19 @@ function AccountCard({
           </div>
         )}
 
+        {/* IdC 客户端注册即将过期 */}
+        {needsReregistration(account) && (
+          <div className="text-xs text-orange-500 mb-3 flex items-center justify-between gap-2">
+            <span className="flex items-center gap-1">
+              <Clock size={12} />
+              {registrationDaysLeft < 0 ? "客户端注册已过期" : `客户端注册 ${registrationDaysLeft} 天后过期`}
+            </span>
+            <button onClick={() => onReregister(account)} className="font-medium hover:underline">
+              {"重新注册"}
+            </button>
+          </div>
+        )}
+
         {/* 操作按钮 */}
         <div className="flex items-center justify-center gap-2 pt-2 mt-auto border-t border-gray-200 dark:border-gray-700">
           <button 
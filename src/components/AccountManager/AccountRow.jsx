@@ -2,6 +2,7 @@ import { RefreshCw, Edit2, Trash2, Copy, Check, Clock, Repeat } from 'lucide-rea
 import { useTheme } from '../../contexts/ThemeContext'
 import { getUsagePercent, getProgressBarColor } from './hooks/useAccountStats'
 import { getQuota, getUsed, getSubType, getSubPlan } from '../../utils/accountStats'
+import { isActive, getStatusLabel, getStatusTitle } from '../../utils/accountStatus'
 
 function AccountRow({
   account,
@@ -99,10 +100,10 @@ function AccountRow({
       </td>
       <td className="px-4 py-3">
         <span className={`inline-flex px-2 py-1 rounded-lg text-xs font-medium transition-transform hover:scale-105 ${
-          account.status === '正常' || account.status === '有效'
+          isActive(account)
             ? (isDark ? 'bg-green-500/20 text-green-400' : 'bg-green-100 text-green-700')
             : (isDark ? 'bg-red-500/20 text-red-400' : 'bg-red-100 text-red-600')
-        }`}>{account.status}</span>
+        }`} title={getStatusTitle(account)}>{getStatusLabel(account)}</span>
       </td>
       <td className="px-4 py-3">
         {account.expiresAt ? (
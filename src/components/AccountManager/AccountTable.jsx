@@ -15,10 +15,11 @@ function AccountTable({
   onEdit,
   onEditLabel,
   onDelete,
+  onReregister,
   onAdd,
   refreshingId,
   switchingId,
-  localToken,
+  ideAccountId,
 }) {
   const { theme, colors } = useTheme()
   const isDark = theme === 'dark'
@@ -76,9 +77,10 @@ function AccountTable({
               onEdit={onEdit}
               onEditLabel={onEditLabel}
               onDelete={onDelete}
+              onReregister={onReregister}
               refreshingId={refreshingId}
               switchingId={switchingId}
-              isCurrentAccount={localToken?.refreshToken && account.refreshToken === localToken.refreshToken}
+              isCurrentAccount={!!ideAccountId && account.id === ideAccountId}
             />
           ))}
           {/* 添加账号卡片 */}
@@ -1,7 +1,9 @@
 import { useState, useRef } from 'react'
 import { invoke } from '@tauri-apps/api/core'
-import { X, Download, Key, Shield, ChevronDown, Upload, FileText, AlertCircle } from 'lucide-react'
+import { X, Download, Key, Shield, ChevronDown, Upload, FileText, AlertCircle, LogIn } from 'lucide-react'
 import { useTheme } from '../../contexts/ThemeContext'
+import { isBanned as isStatusBanned } from '../../utils/accountStatus'
+import { errorCode, errorMessage } from '../../utils/errors'
 
 function AddAccountModal({ onClose, onSuccess }) {
   const { theme, colors } = useTheme()
@@ -14,6 +16,9 @@ function AddAccountModal({ onClose, onSuccess }) {
   const [clientSecret, setClientSecret] = useState('')
   const [region, setRegion] = useState('us-east-1')
   const [batchProgress, setBatchProgress] = useState(null)
+  const [showEnterprise, setShowEnterprise] = useState(false)
+  const [startUrl, setStartUrl] = useState('')
+  const [enterpriseRegion, setEnterpriseRegion] = useState('us-east-1')
   const fileInputRef = useRef(null)
 
   const awsRegions = [
@@ -30,7 +35,26 @@ function AddAccountModal({ onClose, onSuccess }) {
       onSuccess()
       onClose()
     } catch (e) {
-      setAddError(e.toString())
+      setAddError(errorMessage(e))
+    } finally {
+      setAddLoading(false)
+    }
+  }
+
+  // Enterprise (IAM Identity Center) 浏览器登录，后端完成设备授权后保存账号
+  const handleEnterpriseLogin = async () => {
+    if (!startUrl.trim()) {
+      setAddError('请输入 Start URL')
+      return
+    }
+    setAddLoading(true)
+    setAddError('')
+    try {
+      await invoke('kiro_login', { provider: 'Enterprise', startUrl: startUrl.trim(), region: enterpriseRegion.trim() })
+      onSuccess()
+      onClose()
+    } catch (e) {
+      setAddError(errorMessage(e))
     } finally {
       setAddLoading(false)
     }
@@ -92,7 +116,7 @@ function AddAccountModal({ onClose, onSuccess }) {
         onSuccess()
         onClose()
       } catch (e) {
-        setAddError(e.toString())
+        setAddError(errorMessage(e))
       } finally {
         setAddLoading(false)
       }
@@ -114,20 +138,23 @@ function AddAccountModal({ onClose, onSuccess }) {
         const account = await invoke('add_account_by_social', { refreshToken: token })
         success = true
         email = account.email || '未知'
-        isBanned = account.status === '已封禁' || account.status === '封禁'
+        isBanned = isStatusBanned(account)
         message = isBanned ? '已添加（账号已封禁）' : '添加成功'
       } catch (e) {
-        const errorMsg = String(e)
-        // 检测是否是封禁错误
-        if (errorMsg.includes('BANNED') || errorMsg.includes('封禁') || errorMsg.includes('AccountSuspendedException')) {
-          isBanned = true
-          message = '账号已被封禁'
-        } else if (errorMsg.includes('401') || errorMsg.includes('Unauthorized')) {
-          message = 'Token 无效或已过期'
-        } else if (errorMsg.includes('网络') || errorMsg.includes('timeout')) {
-          message = '网络请求失败'
-        } else {
-          message = errorMsg.slice(0, 50)
+        switch (errorCode(e)) {
+          case 'account-suspended':
+            isBanned = true
+            message = '账号已被封禁'
+            break
+          case 'auth-expired':
+          case 'invalid-grant':
+            message = 'Token 无效或已过期'
+            break
+          case 'network':
+            message = '网络请求失败'
+            break
+          default:
+            message = errorMessage(e).slice(0, 50)
         }
       }
       
@@ -187,6 +214,59 @@ function AddAccountModal({ onClose, onSuccess }) {
             </div>
           </button>
 
+          {/* Enterprise 登录 */}
+          <div className={`border rounded-xl ${isDark ? 'border-white/10' : 'border-gray-200'}`}>
+            <button
+              type="button"
+              onClick={() => setShowEnterprise(!showEnterprise)}
+              className={`w-full flex items-center gap-4 px-4 py-3 rounded-xl transition-colors ${isDark ? 'hover:bg-white/5' : 'hover:bg-gray-50'}`}
+            >
+              <div className={`w-10 h-10 rounded-xl ${isDark ? 'bg-blue-500/15' : 'bg-blue-50'} flex items-center justify-center`}>
+                <LogIn size={20} className="text-blue-500" />
+              </div>
+              <div className="flex-1 text-left">
+                <div className={`font-medium ${colors.text}`}>{"Enterprise 登录"}</div>
+                <div className={`text-xs ${colors.textMuted}`}>{"通过 IAM Identity Center 在浏览器中授权"}</div>
+              </div>
+              <ChevronDown size={16} className={`${colors.textMuted} transition-transform ${showEnterprise ? 'rotate-180' : ''}`} />
+            </button>
+            {showEnterprise && (
+              <div className="px-4 pb-4 space-y-3">
+                <div>
+                  <label className={`block text-xs font-medium ${colors.textMuted} mb-1.5`}>{"Start URL"}</label>
+                  <input
+                    type="url"
+                    placeholder="https://d-xxxxxxxxxx.awsapps.com/start"
+                    value={startUrl}
+                    onChange={(e) => setStartUrl(e.target.value)}
+                    className={`w-full px-4 py-3 border rounded-xl text-sm ${colors.text} ${isDark ? 'bg-white/5 border-white/10' : 'bg-white border-gray-200'} focus:outline-none focus:ring-2 focus:ring-blue-500/30 focus:border-blue-500 transition-all`}
+                  />
+                </div>
+                <div>
+                  <label className={`block text-xs font-medium ${colors.textMuted} mb-1.5`}>{"Region"}</label>
+                  <input
+                    type="text"
+                    list="enterprise-regions"
+                    placeholder="us-east-1"
+                    value={enterpriseRegion}
+                    onChange={(e) => setEnterpriseRegion(e.target.value)}
+                    className={`w-full px-4 py-3 border rounded-xl text-sm ${colors.text} ${isDark ? 'bg-white/5 border-white/10' : 'bg-white border-gray-200'} focus:outline-none focus:ring-2 focus:ring-blue-500/30 focus:border-blue-500 transition-all`}
+                  />
+                  <datalist id="enterprise-regions">
+                    {awsRegions.map((r) => (<option key={r.value} value={r.value}>{r.label}</option>))}
+                  </datalist>
+                </div>
+                <button
+                  onClick={handleEnterpriseLogin}
+                  disabled={addLoading || !startUrl.trim()}
+                  className="w-full px-4 py-3 bg-gradient-to-r from-blue-500 to-purple-500 text-white rounded-xl text-sm font-medium shadow-lg shadow-blue-500/25 hover:from-blue-600 hover:to-purple-600 transition-all disabled:opacity-50 disabled:cursor-not-allowed active:scale-[0.98]"
+                >
+                  {addLoading ? '等待浏览器授权...' : '登录'}
+                </button>
+              </div>
+            )}
+          </div>
+
           {/* 分隔线 */}
           <div className="flex items-center gap-3">
             <div className={`flex-1 h-px ${isDark ? 'bg-white/10' : 'bg-gray-200'}`}></div>
@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core'
 import { X, Key, Copy, Check, Shield, ChevronDown, ChevronUp, Clock } from 'lucide-react'
 import { useTheme } from '../../contexts/ThemeContext'
 import { useDialog } from '../../contexts/DialogContext'
+import { errorMessage } from '../../utils/errors'
 
 function EditAccountModal({ account, onClose, onSuccess }) {
   const { theme, colors } = useTheme()
@@ -45,7 +46,7 @@ function EditAccountModal({ account, onClose, onSuccess }) {
       onSuccess?.()
       onClose()
     } catch (e) {
-      await showError("保存失败", e.toString())
+      await showError("保存失败", errorMessage(e))
     } finally {
       setSaving(false)
     }
@@ -2,6 +2,7 @@ import { useState, useRef } from 'react'
 import { X, Upload, FileJson, AlertCircle, CheckCircle, Loader2, Key, FileCode } from 'lucide-react'
 import { invoke } from '@tauri-apps/api/core'
 import { useTheme } from '../../contexts/ThemeContext'
+import { errorMessage } from '../../utils/errors'
 
 // 校验单条账号数据（兼容导出格式和手动输入格式）
 function validateAccount(item, index) {
@@ -163,7 +164,7 @@ function ImportAccountModal({ onClose, onSuccess }) {
         }
         success.push({ index: item._index + 1, email: account.email })
       } catch (e) {
-        failed.push({ index: item._index + 1, error: String(e).slice(0, 50) })
+        failed.push({ index: item._index + 1, error: errorMessage(e).slice(0, 50) })
       }
       
       if (i < parseResult.valid.length - 1) {
@@ -206,7 +207,7 @@ function ImportAccountModal({ onClose, onSuccess }) {
           failed.push({ index: i + 1, error: result.error || '未知错误' })
         }
       } catch (e) {
-        failed.push({ index: i + 1, error: String(e).slice(0, 80) })
+        failed.push({ index: i + 1, error: errorMessage(e).slice(0, 80) })
       }
       
       // 间隔避免请求过快
@@ -1,7 +1,7 @@
 import { RefreshCw } from 'lucide-react'
 import { useTheme } from '../../contexts/ThemeContext'
 
-function RefreshProgressModal({ refreshProgress }) {
+function RefreshProgressModal({ refreshProgress, onCancel }) {
   const { theme, colors } = useTheme()
   const isDark = theme === 'dark'
 
@@ -40,7 +40,11 @@ function RefreshProgressModal({ refreshProgress }) {
               ))}
             </div>
           )}
-          {refreshProgress.current === refreshProgress.total && (<div className="text-center text-green-500 font-medium">刷新完成！</div>)}
+          {refreshProgress.current === refreshProgress.total ? (
+            <div className="text-center text-green-500 font-medium">刷新完成！</div>
+          ) : onCancel && (
+            <button onClick={onCancel} className={`w-full py-2 rounded-xl text-sm ${colors.textMuted} ${isDark ? 'hover:bg-white/10' : 'hover:bg-gray-100'}`}>取消剩余账号</button>
+          )}
         </div>
       </div>
     </div>
@@ -0,0 +1,63 @@
+import { useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { Lock, Unlock, RefreshCw } from 'lucide-react'
+import { useTheme } from '../../contexts/ThemeContext'
+import { errorMessage } from '../../utils/errors'
+
+// 账号库锁定时显示在账号列表上方，输入主密码解锁
+function VaultUnlockBanner({ onUnlocked }) {
+  const { theme, colors } = useTheme()
+  const isDark = theme === 'dark'
+  const [passphrase, setPassphrase] = useState('')
+  const [unlocking, setUnlocking] = useState(false)
+  const [error, setError] = useState('')
+
+  const handleUnlock = async (e) => {
+    e.preventDefault()
+    if (!passphrase) return
+    setUnlocking(true)
+    setError('')
+    try {
+      await invoke('unlock_vault', { passphrase })
+      setPassphrase('')
+      onUnlocked()
+    } catch (err) {
+      setError(errorMessage(err))
+    } finally {
+      setUnlocking(false)
+    }
+  }
+
+  return (
+    <form
+      onSubmit={handleUnlock}
+      className={`mx-6 mt-4 p-4 rounded-xl border flex items-center gap-3 ${isDark ? 'bg-amber-500/10 border-amber-500/20' : 'bg-amber-50 border-amber-200'}`}
+    >
+      <Lock size={18} className="text-amber-500 flex-shrink-0" />
+      <div className="flex-1 min-w-0">
+        <div className={`text-sm font-medium ${colors.text}`}>{"账号库已锁定"}</div>
+        <div className={`text-xs ${error ? 'text-red-500' : colors.textMuted}`}>
+          {error || "输入主密码后才能查看和使用账号"}
+        </div>
+      </div>
+      <input
+        type="password"
+        value={passphrase}
+        onChange={(e) => setPassphrase(e.target.value)}
+        placeholder="主密码"
+        autoFocus
+        className={`w-48 px-3 py-2 border rounded-lg text-sm ${colors.text} ${isDark ? 'bg-white/5 border-white/10' : 'bg-white border-gray-200'} focus:outline-none focus:ring-2 focus:ring-blue-500/30`}
+      />
+      <button
+        type="submit"
+        disabled={unlocking || !passphrase}
+        className="px-4 py-2 rounded-lg text-sm font-medium flex items-center gap-2 bg-blue-500 text-white hover:bg-blue-600 disabled:opacity-50 disabled:cursor-not-allowed transition-all"
+      >
+        {unlocking ? <RefreshCw size={14} className="animate-spin" /> : <Unlock size={14} />}
+        {"解锁"}
+      </button>
+    </form>
+  )
+}
+
+export default VaultUnlockBanner
@@ -1,7 +1,14 @@
-import { useState, useEffect, useCallback } from 'react'
+import { useState, useEffect, useCallback, useRef } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import { TOKEN_EXPIRY_THRESHOLD_MS } from '../../../constants/config'
+import { errorMessage } from '../../../utils/errors'
+
+const SYNC_OUTCOME_MESSAGES = {
+  'succeeded': '已同步',
+  'needs-reauth': '需重新登录',
+  'cancelled': '已取消',
+}
 
 export function useAccounts() {
   const [accounts, setAccounts] = useState([])
@@ -10,6 +17,7 @@ export function useAccounts() {
   const [lastRefreshTime, setLastRefreshTime] = useState(null)
   const [refreshingId, setRefreshingId] = useState(null)
   const [switchingId, setSwitchingId] = useState(null)
+  const syncJobRef = useRef(null)
 
   const isExpiringSoon = useCallback((account) => {
     if (!account.expiresAt) return true
@@ -25,56 +33,51 @@ export function useAccounts() {
     }
   }, [])
 
+  // 批量同步交给后端 sync_accounts（有并发上限），进度通过 sync-progress 事件推送
   const autoRefreshAll = useCallback(async (accountList, forceAll = false) => {
     if (autoRefreshing || accountList.length === 0) return
     const accountsToRefresh = forceAll ? accountList : accountList.filter(isExpiringSoon)
     if (accountsToRefresh.length === 0) return
 
+    const jobId = crypto.randomUUID()
+    syncJobRef.current = jobId
     setAutoRefreshing(true)
     setRefreshProgress({ current: 0, total: accountsToRefresh.length, currentEmail: '', results: [] })
 
-    const updatedAccounts = [...accountList]
     const results = []
-    let completedCount = 0
-
-    // 并发刷新所有账号
-    const refreshPromises = accountsToRefresh.map(async (account) => {
-      let success = false, message = ''
-      try {
-        // 只刷新 token，不获取 usage
-        const updated = await invoke('refresh_account_token', { id: account.id })
-        const idx = updatedAccounts.findIndex(a => a.id === account.id)
-        if (idx !== -1) updatedAccounts[idx] = updated
-        success = true
-        message = 'Token 已刷新'
-      } catch (e) {
-        message = String(e).slice(0, 30)
+    const unlisten = await listen('sync-progress', (event) => {
+      const p = event.payload
+      if (p.jobId !== jobId) return
+      if (p.phase === 'started') {
+        setRefreshProgress(prev => ({ ...prev, currentEmail: p.email }))
+        return
       }
-      
-      // 更新进度
-      completedCount++
-      const result = { email: account.email, success, message }
-      results.push(result)
-      setRefreshProgress({ 
-        current: completedCount, 
-        total: accountsToRefresh.length, 
-        currentEmail: account.email, 
-        results: [...results] 
-      })
-      
-      return result
+      const message = p.outcome === 'failed' ? String(p.error).slice(0, 30) : SYNC_OUTCOME_MESSAGES[p.outcome]
+      results.push({ email: p.email, success: p.outcome === 'succeeded', message })
+      setRefreshProgress({ current: p.completed, total: p.total, currentEmail: p.email, results: [...results] })
     })
 
-    // 等待所有刷新完成
-    await Promise.allSettled(refreshPromises)
+    try {
+      await invoke('sync_accounts', { ids: accountsToRefresh.map(a => a.id), jobId })
+    } catch (e) {
+      console.error(e)
+    } finally {
+      unlisten()
+      syncJobRef.current = null
+    }
 
-    setAccounts(updatedAccounts)
+    await loadAccounts()
     setLastRefreshTime(new Date().toLocaleTimeString())
     setTimeout(() => {
       setAutoRefreshing(false)
       setRefreshProgress({ current: 0, total: 0, currentEmail: '', results: [] })
     }, 1500)
-  }, [autoRefreshing, isExpiringSoon])
+  }, [autoRefreshing, isExpiringSoon, loadAccounts])
+
+  // 取消进行中的批量同步：未开始的账号跳过，已开始的会完成
+  const cancelRefreshAll = useCallback(() => {
+    if (syncJobRef.current) invoke('cancel_sync', { jobId: syncJobRef.current }).catch(() => {})
+  }, [])
 
 
   const handleRefreshStatus = useCallback(async (id) => {
@@ -85,9 +88,9 @@ export function useAccounts() {
       return { success: true }
     } catch (e) {
       console.warn(e)
-      // 更新账号状态为错误信息
-      const errorMsg = String(e)
-      setAccounts(prev => prev.map(a => a.id === id ? { ...a, status: errorMsg.includes('401') || errorMsg.includes('过期') ? 'Token已失效' : '刷新失败' } : a))
+      // 后端已记录失败状态，重新读取账号
+      const errorMsg = errorMessage(e)
+      invoke('get_accounts').then(setAccounts).catch(() => {})
       return { success: false, error: errorMsg }
     } finally {
       setRefreshingId(null)
@@ -147,18 +150,21 @@ export function useAccounts() {
       }
     })
 
-    const interval = setInterval(async () => {
-      if (document.hidden) return
-      const data = await invoke('get_accounts')
-      if (data.length > 0) autoRefreshAll(data)
-    }, TOKEN_EXPIRY_THRESHOLD_MS)
+    // 后端刷新调度每刷新完一个账号就重新读取列表
+    const unlistenTokenRefresh = listen('token-refresh', (event) => {
+      const { phase } = event.payload || {}
+      if (phase === 'succeeded' || phase === 'failed') {
+        loadAccounts()
+        setLastRefreshTime(new Date().toLocaleTimeString())
+      }
+    })
 
     return () => {
       unlistenLoginSuccess.then(fn => fn())
       unlistenKiroLoginData.then(fn => fn())
-      clearInterval(interval)
+      unlistenTokenRefresh.then(fn => fn())
     }
-  }, [loadAccounts, autoRefreshAll])
+  }, [loadAccounts])
 
   return {
     accounts,
@@ -170,6 +176,7 @@ export function useAccounts() {
     switchingId,
     setSwitchingId,
     autoRefreshAll,
+    cancelRefreshAll,
     handleRefreshStatus,
     handleExport,
   }
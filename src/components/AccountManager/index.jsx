@@ -1,8 +1,10 @@
 import { useState, useCallback, useMemo, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { useTheme } from '../../contexts/ThemeContext'
 import { useDialog } from '../../contexts/DialogContext'
 import { useAccounts } from './hooks/useAccounts'
+import { errorMessage } from '../../utils/errors'
 import AccountHeader from './AccountHeader'
 import AccountTable from './AccountTable'
 import AddAccountModal from './AddAccountModal'
@@ -11,10 +13,11 @@ import RefreshProgressModal from './RefreshProgressModal'
 import AccountDetailModal from '../AccountDetailModal'
 import EditAccountModal from './EditAccountModal'
 import ConfirmDialog from './ConfirmDialog'
+import VaultUnlockBanner from './VaultUnlockBanner'
 
 function AccountManager() {
   const { colors } = useTheme()
-  const { showConfirm } = useDialog()
+  const { showConfirm, showError } = useDialog()
   const [searchTerm, setSearchTerm] = useState('')
   const [selectedIds, setSelectedIds] = useState([])
   const [editingAccount, setEditingAccount] = useState(null)
@@ -26,12 +29,11 @@ function AccountManager() {
   // 切换账号弹窗状态
   const [switchDialog, setSwitchDialog] = useState(null) // { type, title, message, account }
   
-  // 当前登录的本地 token
-  const [localToken, setLocalToken] = useState(null)
-  
-  useEffect(() => {
-    invoke('get_kiro_local_token').then(setLocalToken).catch(() => setLocalToken(null))
-  }, [])
+  // 账号库加密且已锁定时，列表为空，需要先解锁
+  const [vaultLocked, setVaultLocked] = useState(false)
+
+  // Kiro IDE 当前使用的账号（后端监听 IDE token 文件并匹配）
+  const [ideAccountId, setIdeAccountId] = useState(null)
 
   const {
     accounts,
@@ -43,10 +45,40 @@ function AccountManager() {
     switchingId,
     setSwitchingId,
     autoRefreshAll,
+    cancelRefreshAll,
     handleRefreshStatus,
     handleExport,
   } = useAccounts()
 
+  useEffect(() => {
+    invoke('get_ide_active_account').then(a => setIdeAccountId(a.accountId)).catch(() => setIdeAccountId(null))
+    const unlisten = listen('ide-active-account', (event) => {
+      setIdeAccountId(event.payload.accountId)
+      // IDE 刷新后的 token 已写回账号
+      if (event.payload.updated) loadAccounts()
+    })
+    return () => { unlisten.then(fn => fn()) }
+  }, [loadAccounts])
+
+  const loadVaultStatus = useCallback(() => {
+    invoke('get_vault_status').then(s => setVaultLocked(s.locked)).catch(() => setVaultLocked(false))
+  }, [])
+
+  useEffect(() => {
+    loadVaultStatus()
+    // 设置页启用、锁定、解锁后同步状态
+    const unlisten = listen('vault-changed', () => {
+      loadVaultStatus()
+      loadAccounts()
+    })
+    return () => { unlisten.then(fn => fn()) }
+  }, [loadVaultStatus, loadAccounts])
+
+  const handleVaultUnlocked = useCallback(() => {
+    setVaultLocked(false)
+    loadAccounts()
+  }, [loadAccounts])
+
   const filteredAccounts = useMemo(() => {
     const searchLower = searchTerm.toLowerCase()
     return accounts.filter(a =>
@@ -60,25 +92,49 @@ function AccountManager() {
   const handleSelectOne = useCallback((id, checked) => { setSelectedIds(prev => checked ? [...prev, id] : prev.filter(i => i !== id)) }, [])
   const handleCopy = useCallback((text, id) => { navigator.clipboard.writeText(text); setCopiedId(id); setTimeout(() => setCopiedId(null), 1500) }, [])
   
+  // IdC 客户端注册过期/将过期：重新注册并授权，账号保持不变
+  const handleReregister = useCallback(async (account) => {
+    try {
+      await invoke('reregister_idc_account', { id: account.id })
+      loadAccounts()
+    } catch (e) {
+      await showError('重新注册失败', errorMessage(e))
+    }
+  }, [showError, loadAccounts])
+
+  // 删除前询问是否同时在服务端注销会话（注销后 refresh token 失效）；注销失败的账号保留
+  const deleteAccounts = useCallback(async (ids) => {
+    const signOut = await showConfirm('注销会话', '是否同时在服务端注销登录会话？注销后该账号的 Token 将失效。', { confirmText: '注销并删除', cancelText: '仅删除' })
+    if (!signOut) {
+      await invoke('delete_accounts', { ids })
+      return
+    }
+    const results = await invoke('sign_out_and_delete_accounts', { ids })
+    const failed = results.filter(r => !r.signedOut)
+    if (failed.length > 0) {
+      await showError('注销失败', `以下账号未删除：\n${failed.map(r => `${r.email}: ${errorMessage(r.error)}`).join('\n')}`)
+    }
+  }, [showConfirm, showError])
+
   // 删除单个账号
   const handleDelete = useCallback(async (id) => {
     const confirmed = await showConfirm('删除', '确定要删除这个账号吗？')
     if (confirmed) {
-      await invoke('delete_account', { id })
+      await deleteAccounts([id])
       loadAccounts()
     }
-  }, [showConfirm, loadAccounts])
+  }, [showConfirm, deleteAccounts, loadAccounts])
 
   // 批量删除
   const onBatchDelete = useCallback(async () => {
     if (selectedIds.length === 0) return
     const confirmed = await showConfirm('批量删除', `确定要删除选中的 ${selectedIds.length} 个账号吗？`)
     if (confirmed) {
-      await invoke('delete_accounts', { ids: selectedIds })
+      await deleteAccounts(selectedIds)
       setSelectedIds([])
       loadAccounts()
     }
-  }, [selectedIds, showConfirm, loadAccounts])
+  }, [selectedIds, showConfirm, deleteAccounts, loadAccounts])
 
   // 切换账号 - 显示确认弹窗
   const handleSwitchAccount = useCallback((account) => {
@@ -131,34 +187,19 @@ function AccountManager() {
         }
       }
       
-      const isIdC = account.provider === 'BuilderId' || account.provider === 'Enterprise' || account.clientIdHash
-      const authMethod = isIdC ? 'IdC' : 'social'
-      
-      // 直接使用账号中的 token 进行切换，不再刷新
+      // 直接使用账号库中的 token 进行切换，不再刷新
       // 如果启用了绑定机器码且使用绑定的，不需要再 resetMachineId
       const shouldResetMachineId = autoChangeMachineId && !(bindMachineIdToAccount && useBoundMachineId)
       const params = {
-        accessToken: account.accessToken,
-        refreshToken: account.refreshToken,
-        provider: account.provider || 'Google',
-        authMethod,
+        accountId: account.id,
         resetMachineId: shouldResetMachineId,
         autoRestart: false
       }
       
-      if (isIdC) {
-        params.clientIdHash = account.clientIdHash || null
-        params.region = account.region || 'us-east-1'
-        params.clientId = account.clientId || null
-        params.clientSecret = account.clientSecret || null
-      } else {
-        params.profileArn = account.profileArn || 'arn:aws:codewhisperer:us-east-1:699475941385:profile/EHGA3GRVQMUK'
-      }
-      
       await invoke('switch_kiro_account', { params })
       
       // 更新当前账号标识
-      invoke('get_kiro_local_token').then(setLocalToken).catch(() => setLocalToken(null))
+      invoke('get_ide_active_account').then(a => setIdeAccountId(a.accountId)).catch(() => setIdeAccountId(null))
       
       // 从 usage_data 获取配额信息
       const usageData = account.usageData
@@ -177,7 +218,7 @@ function AccountManager() {
       setSwitchDialog({
         type: 'error',
         title: '切换失败',
-        message: String(e),
+        message: errorMessage(e),
         account: null,
       })
     } finally {
@@ -200,6 +241,7 @@ function AccountManager() {
         lastRefreshTime={lastRefreshTime}
         refreshProgress={refreshProgress}
       />
+      {vaultLocked && <VaultUnlockBanner onUnlocked={handleVaultUnlocked} />}
       <div className="flex-1 overflow-auto">
         <AccountTable
           accounts={filteredAccounts}
@@ -214,10 +256,11 @@ function AccountManager() {
           onEdit={setEditingAccount}
           onEditLabel={setEditingLabelAccount}
           onDelete={handleDelete}
+          onReregister={handleReregister}
           onAdd={() => setShowAddModal(true)}
           refreshingId={refreshingId}
           switchingId={switchingId}
-          localToken={localToken}
+          ideAccountId={ideAccountId}
         />
       </div>
       {editingAccount && (
@@ -229,7 +272,7 @@ function AccountManager() {
       {showAddModal && (<AddAccountModal onClose={() => setShowAddModal(false)} onSuccess={loadAccounts} />)}
       {editingLabelAccount && (<EditAccountModal account={editingLabelAccount} onClose={() => setEditingLabelAccount(null)} onSuccess={loadAccounts} />)}
       {showImportModal && (<ImportAccountModal onClose={() => setShowImportModal(false)} onSuccess={loadAccounts} />)}
-      {autoRefreshing && (<RefreshProgressModal refreshProgress={refreshProgress} />)}
+      {autoRefreshing && (<RefreshProgressModal refreshProgress={refreshProgress} onCancel={cancelRefreshAll} />)}
       
       {/* 切换账号弹窗 */}
       {switchDialog && (
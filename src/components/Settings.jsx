@@ -1,9 +1,11 @@
 import { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
 import { emit } from '@tauri-apps/api/event'
-import { Lock, Copy, Sun, Moon, Palette, Check, RefreshCw, Settings as SettingsIcon, Clock, Globe, Search, Shield, Download, Upload, Shuffle, AlertTriangle } from 'lucide-react'
+import { Lock, Copy, Sun, Moon, Palette, Check, RefreshCw, Settings as SettingsIcon, Clock, Globe, Search, Shield, Download, Upload, Shuffle, AlertTriangle, FileText } from 'lucide-react'
 import { useTheme } from '../contexts/ThemeContext'
 import { useDialog } from '../contexts/DialogContext'
+import VaultSettings from './VaultSettings'
+import { errorMessage } from '../utils/errors'
 
 function Settings() {
   const { theme, setTheme, colors } = useTheme()
@@ -13,7 +15,7 @@ function Settings() {
   const [aiModel, setAiModel] = useState('claude-sonnet-4.5')
   const [lockModel, setLockModel] = useState(true)
   const [autoRefresh, setAutoRefresh] = useState(true)
-  const [autoRefreshInterval, setAutoRefreshInterval] = useState(50) // 分钟
+  const [refreshMarginMinutes, setRefreshMarginMinutes] = useState(5) // Token 过期前多少分钟刷新
   const [autoChangeMachineId, setAutoChangeMachineId] = useState(false)
   const [bindMachineIdToAccount, setBindMachineIdToAccount] = useState(false)
   const [httpProxy, setHttpProxy] = useState('')
@@ -26,6 +28,9 @@ function Settings() {
   const [detectedBrowsers, setDetectedBrowsers] = useState([])
   const [showBrowserList, setShowBrowserList] = useState(false)
   const [detectingProxy, setDetectingProxy] = useState(false)
+  // 本应用的网络设置（登录、刷新、同步等请求）
+  const [network, setNetwork] = useState({ proxyMode: 'auto', proxyUrl: '', noProxy: '', caBundlePath: '', connectTimeoutSecs: '', requestTimeoutSecs: '' })
+  const [savingNetwork, setSavingNetwork] = useState(false)
   
   // Kiro IDE 状态
   const [loading, setLoading] = useState(false)
@@ -63,12 +68,20 @@ function Settings() {
       if (appSettings) {
         setLockModel(appSettings.lockModel ?? true)
         setAutoRefresh(appSettings.autoRefresh ?? true)
-        setAutoRefreshInterval(appSettings.autoRefreshInterval ?? 50)
+        setRefreshMarginMinutes(appSettings.refreshMarginMinutes ?? 5)
         setAutoChangeMachineId(appSettings.autoChangeMachineId ?? false)
         setBindMachineIdToAccount(appSettings.bindMachineIdToAccount ?? false)
         const browser = appSettings.browserPath || ''
         setBrowserPath(browser)
         setOriginalBrowserPath(browser)
+        setNetwork({
+          proxyMode: appSettings.proxyMode || 'auto',
+          proxyUrl: appSettings.proxyUrl || '',
+          noProxy: appSettings.noProxy || '',
+          caBundlePath: appSettings.caBundlePath || '',
+          connectTimeoutSecs: appSettings.connectTimeoutSecs ? String(appSettings.connectTimeoutSecs) : '',
+          requestTimeoutSecs: appSettings.requestTimeoutSecs ? String(appSettings.requestTimeoutSecs) : '',
+        })
       }
     } catch (err) {
       console.error('Failed to load settings:', err)
@@ -91,7 +104,7 @@ function Settings() {
       }
     } catch (err) {
       console.error('Failed to save app settings:', err)
-      await showError("保存失败", "保存失败" + ': ' + err)
+      await showError("保存失败", "保存失败" + ': ' + errorMessage(err))
     }
   }
 
@@ -102,7 +115,7 @@ function Settings() {
       setOriginalProxy(httpProxy) // 保存成功后更新原始值
       await showSuccess("保存成功", httpProxy ? "代理已应用" : "代理已清除")
     } catch (err) {
-      await showError("保存失败", "保存失败" + ': ' + err)
+      await showError("保存失败", "保存失败" + ': ' + errorMessage(err))
     } finally {
       setSavingProxy(false)
     }
@@ -121,7 +134,7 @@ function Settings() {
         await saveAppSettings({ locked_model: model })
       }
     } catch (err) {
-      await showError("保存失败", "保存失败" + ': ' + err)
+      await showError("保存失败", "保存失败" + ': ' + errorMessage(err))
     } finally {
       setSavingModel(false)
     }
@@ -137,11 +150,10 @@ function Settings() {
     await saveAppSettings({ autoRefresh: checked }, true)
   }
 
-  const handleAutoRefreshIntervalChange = async (value) => {
-    const interval = parseInt(value) || 50
-    setAutoRefreshInterval(interval)
-    await saveAppSettings({ autoRefreshInterval: interval }, true)
-    await saveAppSettings({ autoRefreshInterval: interval })
+  const handleRefreshMarginChange = async (value) => {
+    const margin = parseInt(value) || 5
+    setRefreshMarginMinutes(margin)
+    await saveAppSettings({ refreshMarginMinutes: margin }, true)
   }
 
   const handleAutoChangeMachineIdChange = async (checked) => {
@@ -161,7 +173,7 @@ function Settings() {
       setOriginalBrowserPath(browserPath)
       await showSuccess("保存成功", browserPath ? "浏览器路径已保存" : "使用默认浏览器")
     } catch (err) {
-      await showError("保存失败", "保存失败" + ': ' + err)
+      await showError("保存失败", "保存失败" + ': ' + errorMessage(err))
     } finally {
       setSavingBrowser(false)
     }
@@ -178,7 +190,7 @@ function Settings() {
         await showError("检测失败", "未找到浏览器")
       }
     } catch (err) {
-      await showError("检测失败", "检测失败" + ': ' + err)
+      await showError("检测失败", "检测失败" + ': ' + errorMessage(err))
     }
   }
 
@@ -201,12 +213,55 @@ function Settings() {
         await showError("未检测到代理", "系统未配置代理")
       }
     } catch (err) {
-      await showError("检测失败", "检测失败" + ': ' + err)
+      await showError("检测失败", "检测失败" + ': ' + errorMessage(err))
     } finally {
       setDetectingProxy(false)
     }
   }
 
+  // 保存网络设置，后端校验代理地址和 CA 证书，有误时拒绝保存
+  const handleApplyNetwork = async () => {
+    setSavingNetwork(true)
+    try {
+      await invoke('save_app_settings', { settings: {
+        proxyMode: network.proxyMode,
+        proxyUrl: network.proxyUrl.trim(),
+        noProxy: network.noProxy.trim(),
+        caBundlePath: network.caBundlePath.trim(),
+        connectTimeoutSecs: parseInt(network.connectTimeoutSecs) || 0,
+        requestTimeoutSecs: parseInt(network.requestTimeoutSecs) || 0,
+      } })
+      await showSuccess("保存成功", "网络设置已应用")
+    } catch (err) {
+      await showError("保存失败", "保存失败" + ': ' + errorMessage(err))
+    } finally {
+      setSavingNetwork(false)
+    }
+  }
+
+  // 诊断日志（后端已脱敏），用于反馈问题
+  const [logLevel, setLogLevel] = useState('info')
+  const [logs, setLogs] = useState(null)
+  const [loadingLogs, setLoadingLogs] = useState(false)
+  const [logsCopied, setLogsCopied] = useState(false)
+
+  const handleLoadLogs = async () => {
+    setLoadingLogs(true)
+    try {
+      setLogs(await invoke('read_logs', { limit: 500, level: logLevel }))
+    } catch (err) {
+      await showError("读取失败", "读取日志失败: " + errorMessage(err))
+    } finally {
+      setLoadingLogs(false)
+    }
+  }
+
+  const handleCopyLogs = () => {
+    navigator.clipboard.writeText(logs.entries.join('\n'))
+    setLogsCopied(true)
+    setTimeout(() => setLogsCopied(false), 1500)
+  }
+
   const handleSelectBrowser = (browser, useIncognito = true) => {
     const path = useIncognito && browser.incognitoArg 
       ? `"${browser.path}" ${browser.incognitoArg}`
@@ -475,16 +530,16 @@ function Settings() {
 
           {autoRefresh && (
             <div className={`ml-7 mb-3 p-4 rounded-xl ${isDark ? 'bg-white/5' : 'bg-gray-50'}`}>
-              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"刷新间隔"}</label>
+              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"提前刷新"}</label>
               <select
-                value={autoRefreshInterval}
-                onChange={(e) => handleAutoRefreshIntervalChange(e.target.value)}
+                value={refreshMarginMinutes}
+                onChange={(e) => handleRefreshMarginChange(e.target.value)}
                 className={`w-full px-4 py-2 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 appearance-none cursor-pointer transition-all`}
               >
-                <option value="30">30 {"分钟"}</option>
-                <option value="50">50 {"分钟"} ({"推荐"})</option>
-                <option value="60">60 {"分钟"}</option>
-                <option value="120">2 {"小时"}</option>
+                <option value="5">{"过期前"} 5 {"分钟"} ({"推荐"})</option>
+                <option value="10">{"过期前"} 10 {"分钟"}</option>
+                <option value="15">{"过期前"} 15 {"分钟"}</option>
+                <option value="30">{"过期前"} 30 {"分钟"}</option>
               </select>
             </div>
           )}
@@ -520,6 +575,16 @@ function Settings() {
           )}
         </section>
 
+        {/* 账号加密 */}
+        <section className={`card-glow ${colors.card} rounded-2xl p-6 shadow-sm border ${colors.cardBorder} mb-6 animate-slide-in-left delay-300`}>
+          <div className="flex items-center gap-2 mb-1">
+            <Shield size={18} className="text-blue-500" />
+            <h2 className={`text-lg font-semibold ${colors.text}`}>{"账号加密"}</h2>
+          </div>
+          <p className={`text-sm ${colors.textMuted} mb-5`}>{"用主密码加密保存账号的 Token 和密钥，锁定后需输入主密码才能使用"}</p>
+          <VaultSettings />
+        </section>
+
         {/* 浏览器设置 */}
         <section className={`card-glow ${colors.card} rounded-2xl p-6 shadow-sm border ${colors.cardBorder} mb-6 animate-slide-in-left delay-350`}>
           <div className="flex items-center gap-2 mb-1">
@@ -660,6 +725,90 @@ function Settings() {
           </p>
         </section>
 
+        {/* 网络设置 */}
+        <section className={`card-glow ${colors.card} rounded-2xl p-6 shadow-sm border ${colors.cardBorder} mb-6 animate-slide-in-left delay-400`}>
+          <h2 className={`text-lg font-semibold ${colors.text} mb-1`}>{"网络设置"}</h2>
+          <p className={`text-sm ${colors.textMuted} mb-5`}>
+            {"本应用登录、刷新和同步账号时使用的代理与证书"}
+          </p>
+
+          <div className="grid grid-cols-2 gap-3 mb-3">
+            <div>
+              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"代理模式"}</label>
+              <select
+                value={network.proxyMode}
+                onChange={(e) => setNetwork({ ...network, proxyMode: e.target.value })}
+                className={`w-full px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 appearance-none cursor-pointer transition-all`}
+              >
+                <option value="auto">{"自动（Kiro 代理 / 系统代理 / 环境变量）"}</option>
+                <option value="manual">{"手动"}</option>
+                <option value="none">{"不使用代理"}</option>
+              </select>
+            </div>
+            <div>
+              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"代理地址"}</label>
+              <input
+                type="text"
+                value={network.proxyUrl}
+                onChange={(e) => setNetwork({ ...network, proxyUrl: e.target.value })}
+                disabled={network.proxyMode !== 'manual'}
+                placeholder="http://127.0.0.1:7897 / socks5://127.0.0.1:1080"
+                className={`w-full px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 disabled:opacity-50 transition-all`}
+              />
+            </div>
+            <div>
+              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"不使用代理的地址"}</label>
+              <input
+                type="text"
+                value={network.noProxy}
+                onChange={(e) => setNetwork({ ...network, noProxy: e.target.value })}
+                placeholder="localhost,127.0.0.1,.corp.example.com"
+                className={`w-full px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 transition-all`}
+              />
+            </div>
+            <div>
+              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"额外信任的 CA 证书 (PEM)"}</label>
+              <input
+                type="text"
+                value={network.caBundlePath}
+                onChange={(e) => setNetwork({ ...network, caBundlePath: e.target.value })}
+                placeholder="C:\certs\corp-ca.pem"
+                className={`w-full px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 transition-all`}
+              />
+            </div>
+            <div>
+              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"连接超时（秒）"}</label>
+              <input
+                type="number"
+                min="1"
+                value={network.connectTimeoutSecs}
+                onChange={(e) => setNetwork({ ...network, connectTimeoutSecs: e.target.value })}
+                placeholder="10"
+                className={`w-full px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 transition-all`}
+              />
+            </div>
+            <div>
+              <label className={`block text-sm ${colors.textMuted} mb-2`}>{"请求超时（秒）"}</label>
+              <input
+                type="number"
+                min="1"
+                value={network.requestTimeoutSecs}
+                onChange={(e) => setNetwork({ ...network, requestTimeoutSecs: e.target.value })}
+                placeholder={"默认"}
+                className={`w-full px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 transition-all`}
+              />
+            </div>
+          </div>
+          <button
+            onClick={handleApplyNetwork}
+            disabled={savingNetwork}
+            className="btn-icon px-5 py-3 rounded-xl flex items-center gap-2 font-medium shadow-sm disabled:opacity-50 disabled:cursor-not-allowed transition-all bg-blue-500 text-white hover:bg-blue-600"
+          >
+            {savingNetwork ? <RefreshCw size={16} className="animate-spin" /> : <Check size={16} />}
+            {savingNetwork ? "保存中..." : "应用"}
+          </button>
+        </section>
+
         {/* Kiro IDE 状态 */}
         <section className={`card-glow ${colors.card} rounded-2xl p-6 shadow-sm border ${colors.cardBorder} mb-6 animate-slide-in-left delay-500`}>
           <div className="flex items-center justify-between mb-5">
@@ -828,6 +977,60 @@ function Settings() {
             </div>
           )}
         </section>
+
+        {/* 诊断日志 */}
+        <section className={`card-glow ${colors.card} rounded-2xl p-6 shadow-sm border ${colors.cardBorder} mb-6 animate-slide-in-left delay-600`}>
+          <div className="flex items-center gap-2 mb-1">
+            <FileText size={18} className="text-blue-500" />
+            <h2 className={`text-lg font-semibold ${colors.text}`}>{"诊断日志"}</h2>
+          </div>
+          <p className={`text-sm ${colors.textMuted} mb-5`}>
+            {"最近的运行日志，token、密钥和授权码已自动隐藏，可复制后用于反馈问题"}
+          </p>
+
+          <div className="flex gap-3 mb-3">
+            <select
+              value={logLevel}
+              onChange={(e) => setLogLevel(e.target.value)}
+              className={`px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 transition-all`}
+            >
+              <option value="error">{"错误"}</option>
+              <option value="warn">{"警告"}</option>
+              <option value="info">{"信息"}</option>
+              <option value="debug">{"调试"}</option>
+            </select>
+            <button
+              onClick={handleLoadLogs}
+              disabled={loadingLogs}
+              className="btn-icon px-5 py-3 rounded-xl flex items-center gap-2 font-medium shadow-sm bg-blue-500 text-white hover:bg-blue-600 disabled:opacity-50 transition-all"
+            >
+              {loadingLogs ? <RefreshCw size={16} className="animate-spin" /> : <FileText size={16} />}
+              {"读取日志"}
+            </button>
+            <button
+              onClick={handleCopyLogs}
+              disabled={!logs?.entries.length}
+              className={`btn-icon px-4 py-3 border rounded-xl ${isDark ? 'border-gray-700 hover:bg-white/5' : 'border-gray-200 hover:bg-gray-50'} ${colors.text} transition-all flex items-center gap-2 disabled:opacity-50 disabled:cursor-not-allowed`}
+            >
+              {logsCopied ? <Check size={16} className="text-green-500" /> : <Copy size={16} />}
+              {logsCopied ? "已复制" : "复制"}
+            </button>
+          </div>
+
+          {logs && (
+            <>
+              <textarea
+                readOnly
+                value={logs.entries.length ? logs.entries.join('\n') : "暂无日志"}
+                rows={12}
+                className={`w-full px-4 py-3 border rounded-xl font-mono text-xs ${colors.text} ${colors.input} transition-all`}
+              />
+              <p className={`text-xs ${colors.textMuted} mt-2`}>
+                {"日志目录："}{logs.directory}
+              </p>
+            </>
+          )}
+        </section>
       </div>
     </div>
   )
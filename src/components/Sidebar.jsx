@@ -1,5 +1,6 @@
 import { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { getVersion } from '@tauri-apps/api/app'
 import { Key, Settings, Info, User, Sun, Moon, Palette, Settings2 } from 'lucide-react'
 import { useTheme, themes } from '../contexts/ThemeContext'
@@ -20,6 +21,11 @@ function Sidebar({ activeMenu, onMenuChange }) {
   useEffect(() => {
     invoke('get_kiro_local_token').then(setLocalToken).catch(() => {})
     getVersion().then(setVersion)
+    // IDE token 文件变化（IDE 自行刷新或切换账号）
+    const unlisten = listen('ide-active-account', () => {
+      invoke('get_kiro_local_token').then(setLocalToken).catch(() => setLocalToken(null))
+    })
+    return () => { unlisten.then(fn => fn()) }
   }, [])
 
   const themeIcons = { light: Sun, dark: Moon, purple: Palette, green: Palette }
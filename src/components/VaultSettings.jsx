@@ -0,0 +1,180 @@
+import { useState, useEffect, useCallback } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import { emit } from '@tauri-apps/api/event'
+import { Lock, Unlock, Key, RefreshCw, ShieldOff } from 'lucide-react'
+import { useTheme } from '../contexts/ThemeContext'
+import { useDialog } from '../contexts/DialogContext'
+import { errorMessage } from '../utils/errors'
+
+// 账号库加密：启用、解锁、锁定、更换主密码、关闭
+function VaultSettings() {
+  const { theme, colors } = useTheme()
+  const { showConfirm, showError, showSuccess } = useDialog()
+  const isDark = theme === 'dark'
+  const [status, setStatus] = useState(null)
+  const [busy, setBusy] = useState(false)
+  const [passphrase, setPassphrase] = useState('')
+  const [confirmPassphrase, setConfirmPassphrase] = useState('')
+  const [newPassphrase, setNewPassphrase] = useState('')
+  const [mode, setMode] = useState(null) // 'change' | 'disable'
+
+  const loadStatus = useCallback(async () => {
+    try {
+      setStatus(await invoke('get_vault_status'))
+    } catch (e) {
+      console.error(e)
+    }
+  }, [])
+
+  useEffect(() => { loadStatus() }, [loadStatus])
+
+  const resetForm = () => {
+    setPassphrase('')
+    setConfirmPassphrase('')
+    setNewPassphrase('')
+    setMode(null)
+  }
+
+  // 执行命令后刷新状态，并通知账号列表重新读取
+  const run = async (command, args, successMessage) => {
+    setBusy(true)
+    try {
+      setStatus(await invoke(command, args))
+      resetForm()
+      await emit('vault-changed')
+      if (successMessage) await showSuccess("操作成功", successMessage)
+    } catch (e) {
+      await showError("操作失败", errorMessage(e))
+    } finally {
+      setBusy(false)
+    }
+  }
+
+  const handleEnable = async () => {
+    if (passphrase.length < 8) {
+      await showError("操作失败", "主密码至少 8 位")
+      return
+    }
+    if (passphrase !== confirmPassphrase) {
+      await showError("操作失败", "两次输入的主密码不一致")
+      return
+    }
+    const confirmed = await showConfirm("启用加密", "忘记主密码将无法恢复账号中的 Token，确定启用？")
+    if (!confirmed) return
+    await run('enable_vault', { passphrase }, "账号已加密保存")
+  }
+
+  const handleChange = async () => {
+    if (newPassphrase.length < 8) {
+      await showError("操作失败", "新主密码至少 8 位")
+      return
+    }
+    if (newPassphrase !== confirmPassphrase) {
+      await showError("操作失败", "两次输入的新主密码不一致")
+      return
+    }
+    await run('change_vault_passphrase', { oldPassphrase: passphrase, newPassphrase }, "主密码已更换")
+  }
+
+  const handleDisable = async () => {
+    const confirmed = await showConfirm("关闭加密", "关闭后账号将以明文保存，确定关闭？")
+    if (!confirmed) return
+    await run('disable_vault', { passphrase }, "已关闭加密")
+  }
+
+  const inputClass = `w-full px-4 py-3 border rounded-xl ${colors.text} ${colors.input} ${colors.inputFocus} focus:ring-2 transition-all`
+  const primaryButton = "btn-icon px-5 py-3 rounded-xl flex items-center gap-2 font-medium shadow-sm disabled:opacity-50 disabled:cursor-not-allowed transition-all bg-blue-500 text-white hover:bg-blue-600"
+  const secondaryButton = `btn-icon px-4 py-3 rounded-xl flex items-center gap-2 font-medium disabled:opacity-50 disabled:cursor-not-allowed transition-all ${isDark ? 'bg-white/10 hover:bg-white/15' : 'bg-gray-100 hover:bg-gray-200'} ${colors.text}`
+
+  const passwordInput = (value, onChange, placeholder) => (
+    <input
+      type="password"
+      value={value}
+      onChange={(e) => onChange(e.target.value)}
+      placeholder={placeholder}
+      className={inputClass}
+    />
+  )
+
+  if (!status) return null
+
+  return (
+    <div>
+      <div className={`flex items-center gap-2 text-sm mb-4 ${colors.textMuted}`}>
+        {status.enabled ? (status.locked ? <Lock size={16} className="text-amber-500" /> : <Unlock size={16} className="text-green-500" />) : <ShieldOff size={16} />}
+        {status.enabled ? (status.locked ? "已启用，当前已锁定" : "已启用，当前已解锁") : "未启用，Token 以明文保存"}
+      </div>
+
+      {/* 未启用：设置主密码 */}
+      {!status.enabled && (
+        <div className="space-y-3">
+          <div className="grid grid-cols-2 gap-3">
+            {passwordInput(passphrase, setPassphrase, "主密码（至少 8 位）")}
+            {passwordInput(confirmPassphrase, setConfirmPassphrase, "确认主密码")}
+          </div>
+          <button onClick={handleEnable} disabled={busy || !passphrase} className={primaryButton}>
+            {busy ? <RefreshCw size={16} className="animate-spin" /> : <Lock size={16} />}
+            {"启用加密"}
+          </button>
+        </div>
+      )}
+
+      {/* 已锁定：解锁 */}
+      {status.enabled && status.locked && (
+        <div className="flex gap-3">
+          {passwordInput(passphrase, setPassphrase, "主密码")}
+          <button onClick={() => run('unlock_vault', { passphrase })} disabled={busy || !passphrase} className={`${primaryButton} flex-shrink-0`}>
+            {busy ? <RefreshCw size={16} className="animate-spin" /> : <Unlock size={16} />}
+            {"解锁"}
+          </button>
+        </div>
+      )}
+
+      {/* 已解锁：锁定、更换主密码、关闭加密 */}
+      {status.enabled && !status.locked && (
+        <div className="space-y-3">
+          <div className="flex flex-wrap gap-2">
+            <button onClick={() => run('lock_vault', {})} disabled={busy} className={primaryButton}>
+              <Lock size={16} />
+              {"立即锁定"}
+            </button>
+            <button onClick={() => { resetForm(); setMode(mode === 'change' ? null : 'change') }} disabled={busy} className={secondaryButton}>
+              <Key size={16} />
+              {"更换主密码"}
+            </button>
+            <button onClick={() => { resetForm(); setMode(mode === 'disable' ? null : 'disable') }} disabled={busy} className={secondaryButton}>
+              <ShieldOff size={16} />
+              {"关闭加密"}
+            </button>
+          </div>
+
+          {mode === 'change' && (
+            <div className={`p-4 rounded-xl space-y-3 ${isDark ? 'bg-white/5' : 'bg-gray-50'}`}>
+              {passwordInput(passphrase, setPassphrase, "当前主密码")}
+              <div className="grid grid-cols-2 gap-3">
+                {passwordInput(newPassphrase, setNewPassphrase, "新主密码（至少 8 位）")}
+                {passwordInput(confirmPassphrase, setConfirmPassphrase, "确认新主密码")}
+              </div>
+              <button onClick={handleChange} disabled={busy || !passphrase || !newPassphrase} className={primaryButton}>
+                {busy ? <RefreshCw size={16} className="animate-spin" /> : <Key size={16} />}
+                {"确认更换"}
+              </button>
+            </div>
+          )}
+
+          {mode === 'disable' && (
+            <div className={`p-4 rounded-xl flex gap-3 ${isDark ? 'bg-white/5' : 'bg-gray-50'}`}>
+              {passwordInput(passphrase, setPassphrase, "当前主密码")}
+              <button onClick={handleDisable} disabled={busy || !passphrase} className="btn-icon px-5 py-3 rounded-xl flex items-center gap-2 font-medium flex-shrink-0 disabled:opacity-50 disabled:cursor-not-allowed transition-all bg-red-500 text-white hover:bg-red-600">
+                {busy ? <RefreshCw size={16} className="animate-spin" /> : <ShieldOff size={16} />}
+                {"关闭"}
+              </button>
+            </div>
+          )}
+        </div>
+      )}
+    </div>
+  )
+}
+
+export default VaultSettings
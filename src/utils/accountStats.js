@@ -1,5 +1,7 @@
 // 账号统计计算工具函数
 
+import { isActive } from './accountStatus'
+
 // 从 account 获取 quota（兼容旧数据和新 usageData）
 // API 返回 camelCase，后端 serde 序列化也是 camelCase
 // 兼容 usageBreakdownList（数组）和 usageBreakdown（单个对象）
@@ -31,7 +33,7 @@ const getSubPlan = (a) => a.usageData?.subscriptionInfo?.subscriptionTitle ?? a.
 
 export function calcAccountStats(accounts) {
   const total = accounts.length
-  const active = accounts.filter(a => a.status === '正常' || a.status === '有效').length
+  const active = accounts.filter(isActive).length
   // 使用 Math.round 避免浮点数精度问题
   const totalQuota = Math.round(accounts.reduce((sum, a) => sum + getQuota(a), 0))
   const totalUsed = Math.round(accounts.reduce((sum, a) => sum + getUsed(a), 0))
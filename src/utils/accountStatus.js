@@ -0,0 +1,35 @@
+// 账号状态工具函数
+// 后端 status 为 { state, reason, since }，state 取值见 STATUS_LABELS
+
+const STATUS_LABELS = {
+  'active': '正常',
+  'banned': '已封禁',
+  'token-expired': 'Token已失效',
+  'refresh-failed': '刷新失败',
+  'needs-reauth': '需重新登录',
+  'unknown': '未同步',
+}
+
+export const getStatusState = (a) => a.status?.state ?? 'unknown'
+export const isActive = (a) => getStatusState(a) === 'active'
+export const isBanned = (a) => getStatusState(a) === 'banned'
+
+export const getStatusLabel = (a) => STATUS_LABELS[getStatusState(a)] ?? getStatusState(a)
+
+// IdC 客户端注册到期前多少天开始提醒
+export const REGISTRATION_WARN_DAYS = 14
+
+// IdC 客户端注册剩余天数，没有记录时为 null
+export const getRegistrationDaysLeft = (a) =>
+  a.clientSecretExpiresAt ? Math.floor((new Date(a.clientSecretExpiresAt) - Date.now()) / 86400000) : null
+
+export const needsReregistration = (a) => {
+  const days = getRegistrationDaysLeft(a)
+  return days !== null && days <= REGISTRATION_WARN_DAYS
+}
+
+// 悬停提示：原因 + 时间
+export const getStatusTitle = (a) => {
+  const { reason, since } = a.status || {}
+  return [reason, since && new Date(since).toLocaleString()].filter(Boolean).join('\n')
+}
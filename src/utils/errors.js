@@ -0,0 +1,8 @@
+// 后端错误工具函数
+// 账号/登录相关命令返回 { code, message, details }，其余命令仍返回字符串
+
+export const errorMessage = (e) => e?.message ?? String(e)
+
+export const errorCode = (e) => e?.code ?? 'other'
+
+export const isSuspended = (e) => errorCode(e) === 'account-suspended'